@@ -0,0 +1,188 @@
+use crate::analysis::classifier::{classify, LoopFeatureMeter};
+use crate::analysis::decoder::DecodedAudio;
+use crate::analysis::drums::{predict_drum, DrumPrediction};
+use crate::analysis::loudness::{Loudness, LoudnessMeter};
+use crate::analysis::mfcc::timbre_descriptor;
+use crate::analysis::silence::{SilenceDetector, TrimPoints, SILENCE_THRESHOLD_DB};
+use crate::analysis::spectral::spectral_features;
+use crate::analysis::stream::AudioStream;
+use crate::analysis::tempo::{bpm_from_name, detect_tempo, snap_to_bars};
+use crate::cache::buffer_pool::BufferPool;
+use crate::db::sound::{Sound, SoundKind};
+use crate::settings::manager::AnalysisSettings;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// One-shots longer than this are not classified as drums, in seconds.
+const MAX_DRUM_SECS: f64 = 5.0;
+/// Length of the excerpt kept for tempo, drum and timbre analysis, in seconds.
+const EXCERPT_SECS: f64 = 20.0;
+
+/// The combined result of all analysis passes run on a sound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Analysis {
+    /// Loudness and level measurements.
+    pub loudness: Loudness,
+    /// Suggested trim points, or `None` if the sound is entirely silent.
+    pub trim: Option<TrimPoints>,
+    /// Duration of the sound, in seconds.
+    pub duration: f64,
+    /// Tempo in BPM, taken from the file name or detected for loops.
+    pub bpm: Option<f64>,
+    /// Whether the sound is a one-shot or a loop.
+    pub kind: SoundKind,
+    /// The predicted drum type, for short one-shots only.
+    pub drum: Option<DrumPrediction>,
+    /// Timbre descriptor used for similarity search, or `None` for silence.
+    pub fingerprint: Option<Vec<f32>>,
+}
+
+impl Analysis {
+    /// Runs every analysis pass over a stream, one chunk at a time.
+    ///
+    /// Loudness, silence and loop boundaries are measured over the whole
+    /// sound. Tempo, drum and timbre analysis only look at the first
+    /// `EXCERPT_SECS`, which is the only part kept in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The opened audio stream to analyse.
+    /// * `buffers` - The pool the decode buffer is taken from.
+    /// * `path` - The path the audio is decoded from, used for name hints.
+    /// * `settings` - The analysis thresholds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Analysis`, or an error message if decoding fails.
+    pub fn from_stream(
+        stream: &mut AudioStream,
+        buffers: &Arc<BufferPool>,
+        path: &str,
+        settings: &AnalysisSettings,
+    ) -> Result<Self, String> {
+        let (sample_rate, channels) = (stream.sample_rate(), stream.channels());
+        let mut loudness = LoudnessMeter::new(sample_rate, channels);
+        let mut silence = SilenceDetector::new(sample_rate, channels, SILENCE_THRESHOLD_DB);
+        let mut boundaries = LoopFeatureMeter::new(sample_rate, channels);
+
+        let excerpt_len = (EXCERPT_SECS * sample_rate as f64) as usize * channels;
+        let mut excerpt = Vec::new();
+
+        let mut chunk = buffers.acquire();
+        while stream.read_chunk(&mut chunk)? > 0 {
+            loudness.push(&chunk);
+            silence.push(&chunk);
+            boundaries.push(&chunk);
+
+            let wanted = excerpt_len.saturating_sub(excerpt.len()).min(chunk.len());
+            excerpt.extend_from_slice(&chunk[..wanted]);
+        }
+        drop(chunk);
+
+        let excerpt = DecodedAudio {
+            samples: excerpt,
+            channels,
+            sample_rate,
+        };
+        let features = boundaries.finish();
+        let stem = Path::new(path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+
+        let named_bpm = bpm_from_name(stem);
+        let bpm = named_bpm.or_else(|| {
+            detect_tempo(&excerpt)
+                .filter(|tempo| tempo.confidence >= settings.min_tempo_confidence)
+                .map(|tempo| snap_to_bars(tempo.bpm, features.duration))
+        });
+        let kind = classify(&features, bpm, path);
+        let trim = silence.finish();
+
+        let drum = if kind == SoundKind::OneShot && features.duration <= MAX_DRUM_SECS {
+            let onset = trim.map(|t| t.start).unwrap_or(0.0);
+            spectral_features(&excerpt, onset).map(|spectral| predict_drum(&spectral, path))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            loudness: loudness.finish(),
+            trim,
+            duration: features.duration,
+            bpm: if named_bpm.is_some() || kind == SoundKind::Loop {
+                bpm
+            } else {
+                None
+            },
+            kind,
+            drum,
+            fingerprint: timbre_descriptor(&excerpt),
+        })
+    }
+
+    /// Copies the analysis results onto the matching `Sound` fields.
+    ///
+    /// A confident drum prediction is added as a tag, replacing the tag an
+    /// earlier analysis added. Tags the user added are never removed, and a
+    /// predicted tag the user already added is left to the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - The sound entry to update.
+    /// * `settings` - The analysis thresholds.
+    pub fn apply_to(&self, sound: &mut Sound, settings: &AnalysisSettings) {
+        sound.lufs = self.loudness.integrated_lufs;
+        sound.true_peak = self.loudness.true_peak;
+        sound.rms = self.loudness.rms;
+        sound.crest_factor = self.loudness.crest_factor;
+        sound.trim_start = self.trim.map(|t| t.start);
+        sound.trim_end = self.trim.map(|t| t.end);
+        sound.duration = Some(self.duration);
+        sound.bpm = self.bpm;
+        sound.kind = Some(self.kind);
+        sound.drum_class = self.drum.map(|d| d.class);
+        sound.drum_confidence = self.drum.map(|d| d.confidence);
+
+        if let Some(previous) = sound.auto_tag.take() {
+            sound.tags.retain(|tag| *tag != previous);
+        }
+        if let Some(drum) = self
+            .drum
+            .filter(|d| d.confidence >= settings.drum_tag_confidence)
+        {
+            let tag = drum.class.tag().to_string();
+            if !sound.tags.contains(&tag) {
+                sound.tags.push(tag.clone());
+                sound.auto_tag = Some(tag);
+            }
+        }
+    }
+}
+
+/// Streams and analyses an audio file on the blocking thread pool.
+///
+/// # Arguments
+///
+/// * `path` - The path to the audio file.
+/// * `buffers` - The pool the decode buffer is taken from.
+/// * `settings` - The analysis thresholds.
+///
+/// # Returns
+///
+/// A `Result` containing the `Analysis`, or an error message if the file
+/// could not be decoded.
+pub async fn analyze_file(
+    path: &str,
+    buffers: Arc<BufferPool>,
+    settings: AnalysisSettings,
+) -> Result<Analysis, String> {
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut stream = AudioStream::open(&path)?;
+        Analysis::from_stream(&mut stream, &buffers, &path, &settings)
+    })
+    .await
+    .map_err(|e| format!("Analysis task failed: {}", e))?
+}
@@ -0,0 +1,315 @@
+use crate::analysis::tempo::bar_fit;
+use crate::db::sound::SoundKind;
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Sounds shorter than this are always treated as one-shots, in seconds.
+const MAX_ONE_SHOT_SECS: f64 = 0.75;
+/// Length of the edge windows compared for loop boundary analysis, in seconds.
+const EDGE_SECS: f64 = 0.02;
+/// Bar fits within this relative tempo error count as cut to length.
+const BAR_FIT_TOLERANCE: f64 = 0.02;
+
+/// File name tokens that indicate a loop.
+const LOOP_HINTS: &[&str] = &["loop", "loops", "lp"];
+/// File name tokens that indicate a one-shot.
+const ONE_SHOT_HINTS: &[&str] = &["oneshot", "oneshots", "shot", "shots", "hit", "hits"];
+
+/// Measurements the one-shot/loop classifier is based on.
+#[derive(Debug, Clone, Copy)]
+pub struct LoopFeatures {
+    /// Duration of the sound, in seconds.
+    pub duration: f64,
+    /// Level of the last edge window relative to the overall RMS level.
+    pub tail_ratio: f64,
+    /// Level difference between the last and first edge window, in dB.
+    pub boundary_delta_db: f64,
+}
+
+/// Measures the boundary features of a sound fed in consecutive chunks.
+///
+/// A loop ends at a level comparable to where it starts so that it can
+/// repeat seamlessly, while a one-shot decays into silence. Only the first
+/// and the most recent edge window are kept in memory.
+pub struct LoopFeatureMeter {
+    sample_rate: u32,
+    channels: usize,
+    edge_len: usize,
+    frames: usize,
+    sum_squares: f64,
+    head: Vec<f32>,
+    tail: VecDeque<f32>,
+}
+
+impl LoopFeatureMeter {
+    /// Creates a meter for audio in a given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The sample rate of the audio, in Hz.
+    /// * `channels` - The number of interleaved channels.
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let edge_len = ((EDGE_SECS * sample_rate as f64) as usize).max(1) * channels;
+        Self {
+            sample_rate,
+            channels,
+            edge_len,
+            frames: 0,
+            sum_squares: 0.0,
+            head: Vec::with_capacity(edge_len),
+            tail: VecDeque::with_capacity(edge_len),
+        }
+    }
+
+    /// Feeds the next chunk of interleaved samples.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Interleaved samples following the previous chunk.
+    pub fn push(&mut self, samples: &[f32]) {
+        let samples = &samples[..samples.len() - samples.len() % self.channels];
+        self.frames += samples.len() / self.channels;
+        self.sum_squares += samples
+            .iter()
+            .map(|&s| (s as f64) * (s as f64))
+            .sum::<f64>();
+
+        let head_missing = self.edge_len - self.head.len();
+        self.head
+            .extend_from_slice(&samples[..head_missing.min(samples.len())]);
+
+        let recent = &samples[samples.len().saturating_sub(self.edge_len)..];
+        let overflow = (self.tail.len() + recent.len()).saturating_sub(self.edge_len);
+        self.tail.drain(..overflow);
+        self.tail.extend(recent);
+    }
+
+    /// Returns the measured features.
+    pub fn finish(&self) -> LoopFeatures {
+        if self.frames == 0 {
+            return LoopFeatures {
+                duration: 0.0,
+                tail_ratio: 0.0,
+                boundary_delta_db: 0.0,
+            };
+        }
+
+        let rms = |sum: f64, len: usize| (sum / len.max(1) as f64).sqrt();
+        let square_sum = |samples: &mut dyn Iterator<Item = &f32>| {
+            samples.map(|&s| (s as f64) * (s as f64)).sum::<f64>()
+        };
+
+        let overall = rms(self.sum_squares, self.frames * self.channels);
+        let head = rms(square_sum(&mut self.head.iter()), self.head.len());
+        let tail = rms(square_sum(&mut self.tail.iter()), self.tail.len());
+
+        let to_db = |value: f64| 20.0 * (value + 1e-9).log10();
+
+        LoopFeatures {
+            duration: self.frames as f64 / self.sample_rate.max(1) as f64,
+            tail_ratio: if overall > 0.0 { tail / overall } else { 0.0 },
+            boundary_delta_db: to_db(tail) - to_db(head),
+        }
+    }
+}
+
+/// Classifies a sound as a one-shot or a loop.
+///
+/// Evidence from the file path, the duration, how well the tempo fits a
+/// whole number of bars and the loop boundary is summed into a score.
+/// A positive score means loop.
+///
+/// # Arguments
+///
+/// * `features` - The measured boundary features.
+/// * `bpm` - The detected or name-derived tempo, if any.
+/// * `path` - The path of the sound, used for name hints.
+pub fn classify(features: &LoopFeatures, bpm: Option<f64>, path: &str) -> SoundKind {
+    if features.duration < MAX_ONE_SHOT_SECS {
+        return SoundKind::OneShot;
+    }
+
+    let mut score = path_hint_score(path);
+
+    match bpm.and_then(|bpm| bar_fit(bpm, features.duration)) {
+        Some((_, error)) if error <= BAR_FIT_TOLERANCE => score += 2.0,
+        Some(_) => score -= 0.5,
+        None => score -= 1.0,
+    }
+
+    if features.tail_ratio < 0.02 {
+        score -= 1.5;
+    } else if features.tail_ratio > 0.1 {
+        score += 1.5;
+    }
+
+    if features.boundary_delta_db.abs() < 12.0 {
+        score += 0.5;
+    }
+
+    if score > 0.0 {
+        SoundKind::Loop
+    } else {
+        SoundKind::OneShot
+    }
+}
+
+/// Scores loop and one-shot keywords in a path.
+///
+/// Keywords in the file name weigh more than those in parent folders.
+fn path_hint_score(path: &str) -> f64 {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let folders = path
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    3.0 * keyword_score(stem) + 1.5 * keyword_score(&folders)
+}
+
+/// Splits a file name or path into lowercase alphanumeric tokens.
+///
+/// # Arguments
+///
+/// * `text` - The text to split.
+pub fn name_tokens(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns +1 for loop keywords, -1 for one-shot keywords and 0 otherwise.
+fn keyword_score(text: &str) -> f64 {
+    let tokens = name_tokens(text);
+
+    let is_loop = tokens
+        .iter()
+        .any(|t| LOOP_HINTS.contains(&t.as_str()) || t.ends_with("bpm"));
+    let is_one_shot = tokens.iter().any(|t| ONE_SHOT_HINTS.contains(&t.as_str()))
+        || tokens
+            .windows(2)
+            .any(|pair| pair[0] == "one" && pair[1].starts_with("shot"));
+
+    match (is_loop, is_one_shot) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::decoder::DecodedAudio;
+    use crate::analysis::tempo::detect_tempo;
+
+    /// A mono 110 Hz sine with the given amplitude envelope.
+    fn tone(seconds: f64, envelope: impl Fn(f64) -> f64) -> DecodedAudio {
+        let sample_rate = 44_100;
+        let frames = (seconds * sample_rate as f64) as usize;
+        let samples = (0..frames)
+            .map(|n| {
+                let t = n as f64 / sample_rate as f64;
+                (envelope(t) * (2.0 * std::f64::consts::PI * 110.0 * t).sin()) as f32
+            })
+            .collect();
+        DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate,
+        }
+    }
+
+    /// Two bars of clicks at 120 BPM over a sustained pad.
+    fn click_loop() -> DecodedAudio {
+        let mut audio = tone(4.0, |_| 0.2);
+        let beat = audio.sample_rate as usize / 2;
+        let click = audio.sample_rate as usize / 200;
+        for (n, sample) in audio.samples.iter_mut().enumerate() {
+            let phase = n % beat;
+            if phase < click {
+                *sample += 0.7 * (1.0 - phase as f32 / click as f32);
+            }
+        }
+        audio
+    }
+
+    /// Measures the loop features of decoded audio in chunks.
+    fn measure(audio: &DecodedAudio) -> LoopFeatures {
+        let mut meter = LoopFeatureMeter::new(audio.sample_rate, audio.channels);
+        for chunk in audio.samples.chunks(1000) {
+            meter.push(chunk);
+        }
+        meter.finish()
+    }
+
+    #[test]
+    fn a_click_loop_is_a_loop() {
+        let audio = click_loop();
+        let bpm = detect_tempo(&audio).map(|t| t.bpm);
+        let features = measure(&audio);
+
+        assert!(
+            features.tail_ratio > 0.1,
+            "tail ratio {}",
+            features.tail_ratio
+        );
+        assert_eq!(
+            classify(&features, bpm, "/samples/pad.wav"),
+            SoundKind::Loop
+        );
+    }
+
+    #[test]
+    fn a_decaying_hit_is_a_one_shot() {
+        let audio = tone(1.5, |t| (-t * 8.0).exp());
+        let features = measure(&audio);
+
+        assert!(
+            features.tail_ratio < 0.02,
+            "tail ratio {}",
+            features.tail_ratio
+        );
+        assert_eq!(
+            classify(&features, None, "/samples/boom.wav"),
+            SoundKind::OneShot
+        );
+    }
+
+    #[test]
+    fn short_sounds_are_one_shots() {
+        let features = measure(&click_loop());
+        let short = LoopFeatures {
+            duration: 0.5,
+            ..features
+        };
+        assert_eq!(
+            classify(&short, Some(120.0), "/loops/pad_loop.wav"),
+            SoundKind::OneShot
+        );
+    }
+
+    #[test]
+    fn names_outweigh_weak_evidence() {
+        let features = LoopFeatures {
+            duration: 3.0,
+            tail_ratio: 0.05,
+            boundary_delta_db: 20.0,
+        };
+        assert_eq!(
+            classify(&features, None, "/kits/snare_loop.wav"),
+            SoundKind::Loop
+        );
+        assert_eq!(
+            classify(&features, None, "/loops/snare one shot.wav"),
+            SoundKind::OneShot
+        );
+        assert_eq!(keyword_score("Drum Loops"), 1.0);
+        assert_eq!(keyword_score("kick_hit"), -1.0);
+        assert_eq!(keyword_score("kick"), 0.0);
+    }
+}
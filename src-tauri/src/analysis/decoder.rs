@@ -0,0 +1,173 @@
+use crate::analysis::stream::AudioStream;
+
+/// Fully decoded PCM data of an audio file.
+///
+/// Samples are stored interleaved as `f32` in the range `-1.0..=1.0`,
+/// so frame `n` of channel `c` lives at `samples[n * channels + c]`.
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    /// Interleaved PCM samples.
+    pub samples: Vec<f32>,
+    /// Number of interleaved channels.
+    pub channels: usize,
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+}
+
+impl DecodedAudio {
+    /// Returns the number of frames (samples per channel).
+    pub fn frames(&self) -> usize {
+        self.samples.len().checked_div(self.channels).unwrap_or(0)
+    }
+
+    /// Converts the audio to another sample rate and channel count.
+    ///
+    /// See `FormatConverter` for how channels are mapped.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The target sample rate in Hz.
+    /// * `channels` - The target channel count.
+    pub fn converted(&self, sample_rate: u32, channels: usize) -> DecodedAudio {
+        let mut converter =
+            FormatConverter::new(self.sample_rate, self.channels, sample_rate, channels);
+        let mut samples = Vec::with_capacity(converter.output_len(self.frames()) + channels);
+        converter.push(&self.samples, &mut samples);
+        converter.finish(&mut samples);
+
+        DecodedAudio {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+}
+
+/// Converts interleaved audio to another sample rate and channel count, chunk by chunk.
+///
+/// Resampling uses linear interpolation, which is adequate for previews.
+/// The last frame of each chunk is carried over so that chunk boundaries
+/// are seamless. Mono is copied to every output channel, a mono output
+/// receives the average of all channels, and otherwise channels are mapped
+/// in order with missing ones repeating the last source channel.
+pub struct FormatConverter {
+    source_channels: usize,
+    channels: usize,
+    ratio: f64,
+    position: f64,
+    previous: Vec<f32>,
+}
+
+impl FormatConverter {
+    /// Creates a converter between two formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_rate` - The sample rate of the input, in Hz.
+    /// * `source_channels` - The channel count of the input.
+    /// * `sample_rate` - The target sample rate, in Hz.
+    /// * `channels` - The target channel count.
+    pub fn new(
+        source_rate: u32,
+        source_channels: usize,
+        sample_rate: u32,
+        channels: usize,
+    ) -> Self {
+        Self {
+            source_channels: source_channels.max(1),
+            channels: channels.max(1),
+            ratio: source_rate as f64 / sample_rate.max(1) as f64,
+            position: 0.0,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Returns roughly how many output frames a number of input frames yields.
+    pub fn output_len(&self, frames: usize) -> usize {
+        (frames as f64 / self.ratio).ceil() as usize * self.channels
+    }
+
+    /// Converts the next chunk of input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Interleaved input samples.
+    /// * `out` - The buffer the converted samples are appended to.
+    pub fn push(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        let carried = self.previous.len() / self.source_channels;
+        let frames = carried + input.len() / self.source_channels;
+        if frames == 0 {
+            return;
+        }
+
+        let source_frame = |index: usize| -> &[f32] {
+            if index < carried {
+                &self.previous
+            } else {
+                let start = (index - carried) * self.source_channels;
+                &input[start..start + self.source_channels]
+            }
+        };
+
+        while (self.position as usize) + 1 < frames {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let (current, next) = (source_frame(index), source_frame(index + 1));
+            for channel in 0..self.channels {
+                let a = self.map_channel(current, channel);
+                let b = self.map_channel(next, channel);
+                out.push(a + (b - a) * fraction);
+            }
+            self.position += self.ratio;
+        }
+
+        self.position -= (frames - 1) as f64;
+        let last = source_frame(frames - 1).to_vec();
+        self.previous = last;
+    }
+
+    /// Flushes the frames that depend on the last input frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - The buffer the remaining samples are appended to.
+    pub fn finish(&mut self, out: &mut Vec<f32>) {
+        if self.previous.is_empty() {
+            return;
+        }
+        while self.position < 1.0 {
+            for channel in 0..self.channels {
+                out.push(self.map_channel(&self.previous, channel));
+            }
+            self.position += self.ratio;
+        }
+        self.previous.clear();
+        self.position = 0.0;
+    }
+
+    /// Picks or mixes the source value of one output channel.
+    fn map_channel(&self, frame: &[f32], channel: usize) -> f32 {
+        if self.channels == 1 {
+            frame.iter().sum::<f32>() / self.source_channels as f32
+        } else {
+            frame[channel.min(self.source_channels - 1)]
+        }
+    }
+}
+
+/// Decodes an audio file into interleaved `f32` PCM.
+///
+/// The container format is probed using the file extension as a hint.
+/// Corrupt packets are skipped rather than aborting the decode.
+///
+/// # Arguments
+///
+/// * `path` - The path to the audio file.
+///
+/// # Returns
+///
+/// A `Result` containing the decoded audio, or an error message if the
+/// file cannot be opened or contains no decodable audio track.
+pub fn decode_file(path: &str) -> Result<DecodedAudio, String> {
+    AudioStream::open(path)?.read_all()
+}
@@ -0,0 +1,241 @@
+use crate::analysis::classifier::name_tokens;
+use crate::analysis::spectral::SpectralFeatures;
+use crate::db::sound::DrumClass;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Score bonus for a class whose keyword appears in the file name.
+const NAME_HINT_BONUS: f64 = 4.0;
+
+/// Default feature spread of a prototype, in feature units.
+const DEFAULT_SPREAD: [f64; 5] = [0.5, 0.8, 0.7, 0.4, 0.2];
+/// Wider spread of the catch-all percussion prototype.
+const PERC_SPREAD: [f64; 5] = [0.9, 0.9, 1.0, 0.9, 0.3];
+
+/// A typical feature set for one drum class.
+struct Prototype {
+    class: DrumClass,
+    centroid: f64,
+    rolloff: f64,
+    zero_crossing_rate: f64,
+    decay: f64,
+    low_ratio: f64,
+    spread: [f64; 5],
+}
+
+impl Prototype {
+    const fn new(
+        class: DrumClass,
+        centroid: f64,
+        rolloff: f64,
+        zero_crossing_rate: f64,
+        decay: f64,
+        low_ratio: f64,
+    ) -> Self {
+        Self {
+            class,
+            centroid,
+            rolloff,
+            zero_crossing_rate,
+            decay,
+            low_ratio,
+            spread: DEFAULT_SPREAD,
+        }
+    }
+
+    /// Returns the log-likelihood of the features under this prototype.
+    fn score(&self, features: &SpectralFeatures) -> f64 {
+        let distances = [
+            features.centroid.ln() - self.centroid.ln(),
+            features.rolloff.max(1.0).ln() - self.rolloff.ln(),
+            (features.zero_crossing_rate + 1e-4).ln() - (self.zero_crossing_rate + 1e-4).ln(),
+            (features.decay + 0.01).ln() - (self.decay + 0.01).ln(),
+            features.low_ratio - self.low_ratio,
+        ];
+
+        -0.5 * distances
+            .iter()
+            .zip(&self.spread)
+            .map(|(distance, spread)| (distance / spread).powi(2))
+            .sum::<f64>()
+    }
+}
+
+/// Hand-tuned prototypes of every drum class.
+const PROTOTYPES: &[Prototype] = &[
+    Prototype::new(DrumClass::Kick, 180.0, 400.0, 0.008, 0.3, 0.8),
+    Prototype::new(DrumClass::Bass808, 80.0, 160.0, 0.003, 1.2, 0.95),
+    Prototype::new(DrumClass::Snare, 2500.0, 7000.0, 0.12, 0.25, 0.1),
+    Prototype::new(DrumClass::Clap, 2200.0, 5500.0, 0.15, 0.25, 0.02),
+    Prototype::new(DrumClass::ClosedHat, 8500.0, 14000.0, 0.45, 0.08, 0.0),
+    Prototype::new(DrumClass::OpenHat, 7500.0, 13500.0, 0.4, 0.5, 0.0),
+    Prototype::new(DrumClass::Cymbal, 5500.0, 12000.0, 0.3, 2.0, 0.01),
+    Prototype::new(DrumClass::Tom, 400.0, 1500.0, 0.02, 0.5, 0.35),
+    Prototype {
+        spread: PERC_SPREAD,
+        ..Prototype::new(DrumClass::Perc, 1800.0, 5000.0, 0.08, 0.12, 0.1)
+    },
+];
+
+/// A predicted drum class with its probability.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DrumPrediction {
+    /// The most likely drum class.
+    pub class: DrumClass,
+    /// Probability of the predicted class among all classes, from 0 to 1.
+    pub confidence: f64,
+}
+
+/// Predicts the drum type of a percussive one-shot.
+///
+/// Each class is modelled as a prototype in a log-scaled feature space.
+/// Keywords in the file name add a bonus to their class, and the scores
+/// are normalised with a softmax into a confidence.
+///
+/// # Arguments
+///
+/// * `features` - The spectral features of the sound.
+/// * `path` - The path of the sound, used for name hints.
+pub fn predict_drum(features: &SpectralFeatures, path: &str) -> DrumPrediction {
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("");
+    let hinted = name_hint(stem);
+
+    let scores: Vec<(DrumClass, f64)> = PROTOTYPES
+        .iter()
+        .map(|prototype| {
+            let bonus = if hinted == Some(prototype.class) {
+                NAME_HINT_BONUS
+            } else {
+                0.0
+            };
+            (prototype.class, prototype.score(features) + bonus)
+        })
+        .collect();
+
+    let best = scores
+        .iter()
+        .copied()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((DrumClass::Perc, 0.0));
+    let normaliser: f64 = scores.iter().map(|(_, score)| (score - best.1).exp()).sum();
+
+    DrumPrediction {
+        class: best.0,
+        confidence: 1.0 / normaliser,
+    }
+}
+
+/// Maps drum keywords in a file name to a class.
+fn name_hint(name: &str) -> Option<DrumClass> {
+    let tokens = name_tokens(name);
+    let has = |words: &[&str]| tokens.iter().any(|t| words.contains(&t.as_str()));
+
+    if has(&["808", "sub"]) {
+        Some(DrumClass::Bass808)
+    } else if has(&["kick", "kik", "kck", "bd", "bassdrum"]) {
+        Some(DrumClass::Kick)
+    } else if has(&["snare", "snr", "sd", "rim", "rimshot"]) {
+        Some(DrumClass::Snare)
+    } else if has(&["clap", "clp", "claps"]) {
+        Some(DrumClass::Clap)
+    } else if has(&["ohh", "oh", "openhat"]) || (has(&["hat", "hh", "hihat"]) && has(&["open"])) {
+        Some(DrumClass::OpenHat)
+    } else if has(&["hat", "hats", "hh", "chh", "ch", "hihat", "closedhat"]) {
+        Some(DrumClass::ClosedHat)
+    } else if has(&["crash", "ride", "cymbal", "cym", "splash", "china"]) {
+        Some(DrumClass::Cymbal)
+    } else if has(&["tom", "toms"]) {
+        Some(DrumClass::Tom)
+    } else if has(&[
+        "perc", "shaker", "conga", "bongo", "cowbell", "block", "tamb",
+    ]) {
+        Some(DrumClass::Perc)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::decoder::DecodedAudio;
+    use crate::analysis::spectral::spectral_features;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn features(samples: Vec<f32>) -> SpectralFeatures {
+        let audio = DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+        };
+        spectral_features(&audio, 0.0).unwrap()
+    }
+
+    /// A short burst of white noise with a 20 ms decay.
+    fn noise_burst() -> SpectralFeatures {
+        let mut state = 0x2545_f491_u32;
+        features(
+            (0..SAMPLE_RATE as usize / 2)
+                .map(|n| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    let noise = state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                    noise * (-(n as f32) / (0.02 * SAMPLE_RATE as f32)).exp()
+                })
+                .collect(),
+        )
+    }
+
+    /// A 55 Hz sine with a 150 ms decay.
+    fn low_sine() -> SpectralFeatures {
+        features(
+            (0..SAMPLE_RATE as usize)
+                .map(|n| {
+                    let t = n as f32 / SAMPLE_RATE as f32;
+                    (-t / 0.15).exp() * (2.0 * std::f32::consts::PI * 55.0 * t).sin()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn a_noise_burst_is_a_hat() {
+        let prediction = predict_drum(&noise_burst(), "/samples/untitled.wav");
+        assert_eq!(prediction.class, DrumClass::ClosedHat);
+        assert!(prediction.confidence > 0.5);
+    }
+
+    #[test]
+    fn a_low_sine_is_a_kick_or_808() {
+        let prediction = predict_drum(&low_sine(), "/samples/untitled.wav");
+        assert!(
+            matches!(prediction.class, DrumClass::Kick | DrumClass::Bass808),
+            "predicted {:?}",
+            prediction.class
+        );
+    }
+
+    #[test]
+    fn names_tip_the_balance() {
+        let prediction = predict_drum(&low_sine(), "/samples/Big 808 Sub.wav");
+        assert_eq!(prediction.class, DrumClass::Bass808);
+        assert!(prediction.confidence > 0.9);
+
+        assert_eq!(name_hint("open_hh_03"), Some(DrumClass::OpenHat));
+        assert_eq!(name_hint("Snare Rim"), Some(DrumClass::Snare));
+        assert_eq!(name_hint("pad"), None);
+    }
+
+    #[test]
+    fn confidences_are_probabilities() {
+        for features in [noise_burst(), low_sine()] {
+            let confidence = predict_drum(&features, "").confidence;
+            assert!((0.0..=1.0).contains(&confidence));
+        }
+    }
+}
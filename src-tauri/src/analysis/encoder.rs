@@ -0,0 +1,68 @@
+use crate::analysis::decoder::DecodedAudio;
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+/// Length of the fade applied to the end of a trimmed sound, in seconds.
+const FADE_OUT_SECS: f64 = 0.005;
+
+/// Writes decoded audio to a 24-bit PCM WAV file.
+///
+/// # Arguments
+///
+/// * `path` - The destination path. An existing file is overwritten.
+/// * `audio` - The audio to write.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or an error message on failure.
+pub fn write_wav(path: &str, audio: &DecodedAudio) -> Result<(), String> {
+    let spec = WavSpec {
+        channels: audio.channels as u16,
+        sample_rate: audio.sample_rate,
+        bits_per_sample: 24,
+        sample_format: SampleFormat::Int,
+    };
+    let max = ((1 << 23) - 1) as f32;
+
+    let mut writer =
+        WavWriter::create(path, spec).map_err(|e| format!("Error creating '{}': {}", path, e))?;
+    for &sample in &audio.samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * max) as i32)
+            .map_err(|e| format!("Error writing '{}': {}", path, e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Error finalizing '{}': {}", path, e))
+}
+
+/// Cuts a frame range out of decoded audio and fades out its end.
+///
+/// The short linear fade prevents a click where the cut lands inside
+/// a decaying tail.
+///
+/// # Arguments
+///
+/// * `audio` - The audio to trim.
+/// * `start` - The first frame to keep.
+/// * `end` - The frame after the last frame to keep.
+pub fn trim(audio: &DecodedAudio, start: usize, end: usize) -> DecodedAudio {
+    let channels = audio.channels;
+    let mut samples = audio.samples[start * channels..end * channels].to_vec();
+
+    let frames = end - start;
+    let fade = ((FADE_OUT_SECS * audio.sample_rate as f64) as usize).min(frames);
+    for (index, frame) in samples
+        .chunks_exact_mut(channels)
+        .skip(frames - fade)
+        .enumerate()
+    {
+        let gain = 1.0 - (index + 1) as f32 / fade as f32;
+        frame.iter_mut().for_each(|s| *s *= gain);
+    }
+
+    DecodedAudio {
+        samples,
+        channels,
+        sample_rate: audio.sample_rate,
+    }
+}
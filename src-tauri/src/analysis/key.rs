@@ -0,0 +1,146 @@
+/// Words that mark a minor key.
+const MINOR_WORDS: &[&str] = &["m", "min", "minor"];
+/// Words that mark a major key.
+const MAJOR_WORDS: &[&str] = &["maj", "major"];
+
+/// A musical key made of a tonic pitch class and a mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MusicalKey {
+    /// Pitch class of the tonic, with C = 0 up to B = 11.
+    pub tonic: i32,
+    /// Whether the key is minor.
+    pub minor: bool,
+}
+
+impl MusicalKey {
+    /// Parses a key written like `F#m`, `Bb major`, `A minor` or `C`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The key text.
+    pub fn parse(text: &str) -> Option<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            [key] => parse_token(key),
+            [key, mode] => {
+                let key = parse_token(key)?;
+                let mode = mode.to_lowercase();
+                if MINOR_WORDS.contains(&mode.as_str()) {
+                    Some(Self { minor: true, ..key })
+                } else if MAJOR_WORDS.contains(&mode.as_str()) {
+                    Some(Self {
+                        minor: false,
+                        ..key
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the pitch class of the relative major key.
+    fn major_tonic(&self) -> i32 {
+        if self.minor {
+            (self.tonic + 3).rem_euclid(12)
+        } else {
+            self.tonic
+        }
+    }
+
+    /// Returns the smallest transposition from this key to another, in semitones.
+    ///
+    /// Keys are compared through their relative majors, so A minor to
+    /// C major needs no transposition. The result lies in `-6..=5`.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The key to transpose to.
+    pub fn semitones_to(&self, target: &MusicalKey) -> i32 {
+        let distance = (target.major_tonic() - self.major_tonic()).rem_euclid(12);
+        if distance >= 6 {
+            distance - 12
+        } else {
+            distance
+        }
+    }
+}
+
+/// Finds a key written into a file name, such as `pad_F#m_120` or `Lead A minor`.
+///
+/// Single-letter keys are only accepted in upper case, or when followed by
+/// a mode word, so that words like `a` are not mistaken for keys.
+///
+/// # Arguments
+///
+/// * `name` - The file name or stem to scan.
+pub fn key_from_name(name: &str) -> Option<MusicalKey> {
+    let tokens: Vec<&str> = name
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let next = tokens.get(index + 1).map(|t| t.to_lowercase());
+        let followed_by_mode = next
+            .as_deref()
+            .is_some_and(|mode| MINOR_WORDS[1..].contains(&mode) || MAJOR_WORDS.contains(&mode));
+
+        if token.len() == 1 && !followed_by_mode && !token.chars().all(|c| c.is_ascii_uppercase()) {
+            continue;
+        }
+
+        let text = match (&next, followed_by_mode) {
+            (Some(mode), true) => format!("{} {}", token, mode),
+            _ => token.to_string(),
+        };
+        if let Some(key) = MusicalKey::parse(&text) {
+            return Some(key);
+        }
+    }
+
+    None
+}
+
+/// Parses a single key token such as `F#m`, `Ebmaj` or `Csharpminor`.
+fn parse_token(token: &str) -> Option<MusicalKey> {
+    let lower = token.to_lowercase();
+    let mut chars = lower.chars();
+    let tonic: i32 = match chars.next()? {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        'b' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let (accidental, mode) = if let Some(rest) = rest.strip_prefix("sharp") {
+        (1, rest)
+    } else if let Some(rest) = rest.strip_prefix("flat") {
+        (-1, rest)
+    } else if let Some(rest) = rest.strip_prefix('#') {
+        (1, rest)
+    } else if let Some(rest) = rest.strip_prefix('b') {
+        (-1, rest)
+    } else {
+        (0, rest)
+    };
+
+    let minor = if mode.is_empty() || MAJOR_WORDS.contains(&mode) {
+        false
+    } else if MINOR_WORDS.contains(&mode) {
+        true
+    } else {
+        return None;
+    };
+
+    Some(MusicalKey {
+        tonic: (tonic + accidental).rem_euclid(12),
+        minor,
+    })
+}
@@ -0,0 +1,198 @@
+use crate::analysis::decoder::DecodedAudio;
+use crate::analysis::spectral::{magnitude_spectra, mono, FRAME_SIZE};
+use std::f64::consts::PI;
+
+/// Number of cepstral coefficients kept per frame.
+pub const COEFFICIENTS: usize = 13;
+/// Length of a timbre descriptor: the mean and standard deviation of every coefficient.
+pub const DESCRIPTOR_LEN: usize = COEFFICIENTS * 2;
+/// Number of triangular mel filters.
+const MEL_BANDS: usize = 40;
+/// Lowest frequency covered by the mel filterbank, in Hz.
+const MIN_HZ: f64 = 20.0;
+/// Highest frequency covered by the mel filterbank, in Hz.
+const MAX_HZ: f64 = 16000.0;
+/// Only the beginning of long sounds is described, in seconds.
+const MAX_ANALYSIS_SECS: f64 = 10.0;
+/// Frames quieter than this relative to the loudest frame are skipped, in dB.
+const SILENT_FRAME_DB: f64 = -60.0;
+
+/// Computes a compact timbre descriptor from MFCC statistics.
+///
+/// The descriptor holds the mean of each mel-frequency cepstral
+/// coefficient followed by its standard deviation, taken over all
+/// non-silent frames.
+///
+/// # Arguments
+///
+/// * `audio` - The decoded audio to describe.
+///
+/// # Returns
+///
+/// The descriptor, or `None` if the sound is silent.
+pub fn timbre_descriptor(audio: &DecodedAudio) -> Option<Vec<f32>> {
+    let mut signal = mono(audio);
+    signal.truncate((MAX_ANALYSIS_SECS * audio.sample_rate as f64) as usize);
+
+    let filterbank = mel_filterbank(audio.sample_rate);
+    let spectra = magnitude_spectra(&signal);
+
+    let frame_energies: Vec<f64> = spectra
+        .iter()
+        .map(|spectrum| spectrum.iter().map(|m| m * m).sum())
+        .collect();
+    let loudest = frame_energies.iter().copied().fold(0.0, f64::max);
+    if loudest <= 0.0 {
+        return None;
+    }
+    let floor = loudest * 10f64.powf(SILENT_FRAME_DB / 10.0);
+
+    let frames: Vec<[f64; COEFFICIENTS]> = spectra
+        .iter()
+        .zip(&frame_energies)
+        .filter(|(_, &energy)| energy >= floor)
+        .map(|(spectrum, _)| cepstrum(spectrum, &filterbank))
+        .collect();
+
+    let count = frames.len() as f64;
+    let mut descriptor = vec![0.0f32; DESCRIPTOR_LEN];
+    for coefficient in 0..COEFFICIENTS {
+        let mean = frames.iter().map(|f| f[coefficient]).sum::<f64>() / count;
+        let variance = frames
+            .iter()
+            .map(|f| (f[coefficient] - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        descriptor[coefficient] = mean as f32;
+        descriptor[COEFFICIENTS + coefficient] = variance.sqrt() as f32;
+    }
+
+    Some(descriptor)
+}
+
+/// Applies the mel filterbank, log compression and a DCT-II to a spectrum.
+fn cepstrum(spectrum: &[f64], filterbank: &[Vec<(usize, f64)>]) -> [f64; COEFFICIENTS] {
+    let log_energies: Vec<f64> = filterbank
+        .iter()
+        .map(|filter| {
+            let energy: f64 = filter
+                .iter()
+                .map(|&(bin, weight)| weight * spectrum[bin] * spectrum[bin])
+                .sum();
+            (energy + 1e-10).ln()
+        })
+        .collect();
+
+    let mut coefficients = [0.0; COEFFICIENTS];
+    for (k, coefficient) in coefficients.iter_mut().enumerate() {
+        *coefficient = log_energies
+            .iter()
+            .enumerate()
+            .map(|(n, e)| e * (PI * k as f64 * (n as f64 + 0.5) / MEL_BANDS as f64).cos())
+            .sum();
+    }
+    coefficients
+}
+
+/// Builds triangular mel filters as sparse lists of `(bin, weight)` pairs.
+fn mel_filterbank(sample_rate: u32) -> Vec<Vec<(usize, f64)>> {
+    let to_mel = |hz: f64| 2595.0 * (1.0 + hz / 700.0).log10();
+    let to_hz = |mel: f64| 700.0 * (10f64.powf(mel / 2595.0) - 1.0);
+
+    let nyquist = sample_rate as f64 / 2.0;
+    let bin_hz = sample_rate as f64 / FRAME_SIZE as f64;
+    let (low, high) = (to_mel(MIN_HZ), to_mel(MAX_HZ.min(nyquist)));
+    let edges: Vec<f64> = (0..MEL_BANDS + 2)
+        .map(|i| to_hz(low + (high - low) * i as f64 / (MEL_BANDS + 1) as f64))
+        .collect();
+
+    (0..MEL_BANDS)
+        .map(|band| {
+            let (start, center, end) = (edges[band], edges[band + 1], edges[band + 2]);
+            (0..=FRAME_SIZE / 2)
+                .filter_map(|bin| {
+                    let hz = bin as f64 * bin_hz;
+                    let weight = if hz > start && hz <= center {
+                        (hz - start) / (center - start)
+                    } else if hz > center && hz < end {
+                        (end - hz) / (end - center)
+                    } else {
+                        return None;
+                    };
+                    Some((bin, weight))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn audio(samples: Vec<f32>) -> DecodedAudio {
+        DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+        }
+    }
+
+    /// One second of a mono sine at half scale.
+    fn sine(frequency: f64) -> DecodedAudio {
+        audio(
+            (0..SAMPLE_RATE as usize)
+                .map(|n| {
+                    (0.5 * (2.0 * PI * frequency * n as f64 / SAMPLE_RATE as f64).sin()) as f32
+                })
+                .collect(),
+        )
+    }
+
+    /// One second of white noise at half scale.
+    fn noise() -> DecodedAudio {
+        let mut state = 0x9e37_79b9_u32;
+        audio(
+            (0..SAMPLE_RATE as usize)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as f32 / u32::MAX as f32 - 0.5
+                })
+                .collect(),
+        )
+    }
+
+    fn distance(a: &DecodedAudio, b: &DecodedAudio) -> f64 {
+        let a = timbre_descriptor(a).unwrap();
+        let b = timbre_descriptor(b).unwrap();
+        a.iter()
+            .zip(&b)
+            .map(|(x, y)| ((x - y) as f64).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    #[test]
+    fn identical_signals_have_no_distance() {
+        let descriptor = timbre_descriptor(&sine(440.0)).unwrap();
+        assert_eq!(descriptor.len(), DESCRIPTOR_LEN);
+        assert!(descriptor.iter().all(|v| v.is_finite()));
+        assert_eq!(distance(&sine(440.0), &sine(440.0)), 0.0);
+    }
+
+    #[test]
+    fn similar_timbres_are_closer_than_different_ones() {
+        let near = distance(&sine(440.0), &sine(466.0));
+        let far = distance(&sine(440.0), &noise());
+        assert!(near < far / 4.0, "near {} far {}", near, far);
+    }
+
+    #[test]
+    fn silence_has_no_descriptor() {
+        assert_eq!(timbre_descriptor(&audio(vec![0.0; 4096])), None);
+    }
+}
@@ -0,0 +1,13 @@
+pub mod analyzer;
+pub mod classifier;
+pub mod decoder;
+pub mod drums;
+pub mod encoder;
+pub mod key;
+pub mod loudness;
+pub mod mfcc;
+pub mod silence;
+pub mod spectral;
+pub mod stream;
+pub mod tempo;
+pub mod waveform;
@@ -0,0 +1,176 @@
+use crate::analysis::decoder::DecodedAudio;
+use serde::{Deserialize, Serialize};
+
+/// Level below which audio at the edges of a sound counts as silence, in dBFS.
+pub const SILENCE_THRESHOLD_DB: f64 = -60.0;
+
+/// Suggested start and end offsets that cut leading and trailing silence.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrimPoints {
+    /// Offset of the first audible frame, in seconds.
+    pub start: f64,
+    /// Offset just after the last audible frame, in seconds.
+    pub end: f64,
+}
+
+impl TrimPoints {
+    /// Converts the trim points into a frame range for the given sample rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The sample rate of the audio being trimmed.
+    /// * `frames` - The total number of frames, used to clamp the range.
+    pub fn frame_range(&self, sample_rate: u32, frames: usize) -> (usize, usize) {
+        let to_frame = |secs: f64| ((secs * sample_rate as f64).round() as usize).min(frames);
+        let start = to_frame(self.start);
+        (start, to_frame(self.end).max(start))
+    }
+}
+
+/// Detects leading and trailing silence of a sound.
+///
+/// A frame is audible when any of its channels reaches the threshold.
+///
+/// # Arguments
+///
+/// * `audio` - The decoded audio to scan.
+/// * `threshold_db` - The silence threshold, in dBFS.
+///
+/// # Returns
+///
+/// The trim points, or `None` if the whole sound is below the threshold.
+pub fn detect_silence(audio: &DecodedAudio, threshold_db: f64) -> Option<TrimPoints> {
+    let mut detector = SilenceDetector::new(audio.sample_rate, audio.channels, threshold_db);
+    detector.push(&audio.samples);
+    detector.finish()
+}
+
+/// Streaming silence detector fed with consecutive chunks of a sound.
+pub struct SilenceDetector {
+    sample_rate: u32,
+    channels: usize,
+    threshold: f32,
+    frames: usize,
+    first: Option<usize>,
+    last: Option<usize>,
+}
+
+impl SilenceDetector {
+    /// Creates a detector for audio in a given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The sample rate of the audio, in Hz.
+    /// * `channels` - The number of interleaved channels.
+    /// * `threshold_db` - The silence threshold, in dBFS.
+    pub fn new(sample_rate: u32, channels: usize, threshold_db: f64) -> Self {
+        Self {
+            sample_rate,
+            channels: channels.max(1),
+            threshold: 10f64.powf(threshold_db / 20.0) as f32,
+            frames: 0,
+            first: None,
+            last: None,
+        }
+    }
+
+    /// Feeds the next chunk of interleaved samples.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Interleaved samples following the previous chunk.
+    pub fn push(&mut self, samples: &[f32]) {
+        let is_audible = |frame: &[f32]| frame.iter().any(|s| s.abs() >= self.threshold);
+        let chunk = samples.chunks_exact(self.channels);
+        let frames = chunk.len();
+
+        if let Some(last) = chunk.clone().rposition(is_audible) {
+            if self.first.is_none() {
+                self.first = chunk.clone().position(is_audible).map(|f| self.frames + f);
+            }
+            self.last = Some(self.frames + last);
+        }
+        self.frames += frames;
+    }
+
+    /// Returns the trim points, or `None` if everything was below the threshold.
+    pub fn finish(&self) -> Option<TrimPoints> {
+        let (first, last) = (self.first?, self.last?);
+        let sample_rate = self.sample_rate as f64;
+        Some(TrimPoints {
+            start: first as f64 / sample_rate,
+            end: (last + 1) as f64 / sample_rate,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mono 440 Hz square wave at -6 dBFS padded with digital silence.
+    fn padded_tone(lead: usize, tone: usize, tail: usize) -> DecodedAudio {
+        let sample_rate = 48_000;
+        let mut samples = vec![0.0; lead];
+        samples.extend((0..tone).map(|n| {
+            let t = n as f32 / sample_rate as f32;
+            0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin().signum()
+        }));
+        samples.extend(std::iter::repeat_n(0.0, tail));
+        DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate,
+        }
+    }
+
+    #[test]
+    fn finds_the_audible_span() {
+        let audio = padded_tone(24_000, 48_000, 12_000);
+        let trim = detect_silence(&audio, SILENCE_THRESHOLD_DB).unwrap();
+
+        assert!((trim.start - 0.5).abs() < 1e-9, "start {}", trim.start);
+        assert!((trim.end - 1.5).abs() < 1e-9, "end {}", trim.end);
+        assert_eq!(
+            trim.frame_range(audio.sample_rate, audio.frames()),
+            (24_000, 72_000)
+        );
+    }
+
+    #[test]
+    fn leaves_unpadded_sounds_untouched() {
+        let audio = padded_tone(0, 4_800, 0);
+        let trim = detect_silence(&audio, SILENCE_THRESHOLD_DB).unwrap();
+
+        assert_eq!(trim.start, 0.0);
+        assert!((trim.end - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn silence_has_no_trim_points() {
+        let audio = padded_tone(4_800, 0, 4_800);
+        assert_eq!(detect_silence(&audio, SILENCE_THRESHOLD_DB), None);
+    }
+
+    #[test]
+    fn chunks_give_the_same_span() {
+        let audio = padded_tone(24_000, 48_000, 12_000);
+        let mut detector = SilenceDetector::new(audio.sample_rate, 1, SILENCE_THRESHOLD_DB);
+        for chunk in audio.samples.chunks(1000) {
+            detector.push(chunk);
+        }
+        assert_eq!(
+            detector.finish(),
+            detect_silence(&audio, SILENCE_THRESHOLD_DB)
+        );
+    }
+
+    #[test]
+    fn frame_range_is_clamped() {
+        let trim = TrimPoints {
+            start: 2.0,
+            end: 1.0,
+        };
+        assert_eq!(trim.frame_range(1_000, 1_500), (1_500, 1_500));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::analysis::decoder::DecodedAudio;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use std::f64::consts::PI;
+
+/// FFT frame length used for spectral analysis.
+pub const FRAME_SIZE: usize = 2048;
+/// Hop between consecutive FFT frames.
+pub const HOP_SIZE: usize = 512;
+/// Portion of the sound after its onset that spectral features describe, in seconds.
+const FEATURE_WINDOW_SECS: f64 = 0.5;
+/// Fraction of spectral energy below the rolloff frequency.
+const ROLLOFF_FRACTION: f64 = 0.85;
+/// Upper edge of the low band used for the low-energy ratio, in Hz.
+const LOW_BAND_HZ: f64 = 200.0;
+/// Envelope window used for decay measurement, in seconds.
+const ENVELOPE_SECS: f64 = 0.005;
+/// Level drop from the peak that marks the end of the decay, in dB.
+const DECAY_DROP_DB: f64 = 30.0;
+
+/// Spectral and envelope descriptors of a short percussive sound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectralFeatures {
+    /// Magnitude-weighted mean frequency, in Hz.
+    pub centroid: f64,
+    /// Frequency below which 85% of the spectral energy lies, in Hz.
+    pub rolloff: f64,
+    /// Fraction of consecutive samples that change sign.
+    pub zero_crossing_rate: f64,
+    /// Time from the envelope peak until it falls 30 dB, in seconds.
+    pub decay: f64,
+    /// Share of spectral energy below 200 Hz.
+    pub low_ratio: f64,
+}
+
+/// Mixes all channels of decoded audio down to mono.
+///
+/// # Arguments
+///
+/// * `audio` - The decoded audio to mix down.
+pub fn mono(audio: &DecodedAudio) -> Vec<f32> {
+    let channels = audio.channels.max(1);
+    audio
+        .samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Computes the magnitude spectra of successive Hann-windowed frames.
+///
+/// The signal is zero-padded so that even sounds shorter than one frame
+/// yield a spectrum.
+///
+/// # Arguments
+///
+/// * `signal` - The mono signal to transform.
+///
+/// # Returns
+///
+/// One spectrum of `FRAME_SIZE / 2 + 1` magnitude bins per frame.
+pub fn magnitude_spectra(signal: &[f32]) -> Vec<Vec<f64>> {
+    let fft = FftPlanner::<f64>::new().plan_fft_forward(FRAME_SIZE);
+    let window: Vec<f64> = (0..FRAME_SIZE)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f64 / FRAME_SIZE as f64).cos())
+        .collect();
+
+    let frames = signal.len().saturating_sub(FRAME_SIZE) / HOP_SIZE + 1;
+    let mut buffer = vec![Complex::new(0.0, 0.0); FRAME_SIZE];
+
+    (0..frames)
+        .map(|index| {
+            let start = index * HOP_SIZE;
+            for (n, value) in buffer.iter_mut().enumerate() {
+                let sample = signal.get(start + n).copied().unwrap_or(0.0) as f64;
+                *value = Complex::new(sample * window[n], 0.0);
+            }
+            fft.process(&mut buffer);
+            buffer[..=FRAME_SIZE / 2].iter().map(|c| c.norm()).collect()
+        })
+        .collect()
+}
+
+/// Measures the spectral features of a sound starting at its onset.
+///
+/// # Arguments
+///
+/// * `audio` - The decoded audio to measure.
+/// * `onset` - Offset of the first audible frame, in seconds.
+///
+/// # Returns
+///
+/// The features, or `None` if the sound is silent after the onset.
+pub fn spectral_features(audio: &DecodedAudio, onset: f64) -> Option<SpectralFeatures> {
+    let sample_rate = audio.sample_rate as f64;
+    let signal = mono(audio);
+    let start = ((onset * sample_rate) as usize).min(signal.len());
+    let end = (start + (FEATURE_WINDOW_SECS * sample_rate) as usize).min(signal.len());
+    let window = &signal[start..end];
+    if window.is_empty() {
+        return None;
+    }
+
+    let mut power = vec![0.0f64; FRAME_SIZE / 2 + 1];
+    for spectrum in magnitude_spectra(window) {
+        for (total, magnitude) in power.iter_mut().zip(spectrum) {
+            *total += magnitude * magnitude;
+        }
+    }
+
+    let total: f64 = power.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let bin_hz = sample_rate / FRAME_SIZE as f64;
+    let magnitude_sum: f64 = power.iter().map(|p| p.sqrt()).sum();
+    let centroid = power
+        .iter()
+        .enumerate()
+        .map(|(bin, p)| bin as f64 * bin_hz * p.sqrt())
+        .sum::<f64>()
+        / magnitude_sum;
+
+    let mut cumulative = 0.0;
+    let rolloff_bin = power
+        .iter()
+        .position(|p| {
+            cumulative += p;
+            cumulative >= ROLLOFF_FRACTION * total
+        })
+        .unwrap_or(power.len() - 1);
+
+    let low_bins = (LOW_BAND_HZ / bin_hz).ceil() as usize;
+    let low_ratio = power.iter().take(low_bins).sum::<f64>() / total;
+
+    let crossings = window
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+
+    Some(SpectralFeatures {
+        centroid,
+        rolloff: rolloff_bin as f64 * bin_hz,
+        zero_crossing_rate: crossings as f64 / window.len().max(2) as f64,
+        decay: decay_time(&signal[start..], sample_rate),
+        low_ratio,
+    })
+}
+
+/// Measures how long the RMS envelope takes to fall 30 dB below its peak.
+fn decay_time(signal: &[f32], sample_rate: f64) -> f64 {
+    let window = ((ENVELOPE_SECS * sample_rate) as usize).max(1);
+    let envelope: Vec<f64> = signal
+        .chunks(window)
+        .map(|chunk| {
+            let sum: f64 = chunk.iter().map(|&s| (s as f64) * (s as f64)).sum();
+            (sum / chunk.len() as f64).sqrt()
+        })
+        .collect();
+
+    let Some((peak_index, &peak)) = envelope
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+    else {
+        return 0.0;
+    };
+
+    let floor = peak * 10f64.powf(-DECAY_DROP_DB / 20.0);
+    let end = envelope[peak_index..]
+        .iter()
+        .position(|&level| level < floor)
+        .unwrap_or(envelope.len() - peak_index);
+
+    end as f64 * window as f64 / sample_rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// One second of a mono sine with an exponentially decaying envelope.
+    fn decaying_sine(frequency: f64, time_constant: f64) -> DecodedAudio {
+        let samples = (0..SAMPLE_RATE as usize)
+            .map(|n| {
+                let t = n as f64 / SAMPLE_RATE as f64;
+                ((-t / time_constant).exp() * (2.0 * PI * frequency * t).sin()) as f32
+            })
+            .collect();
+        DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+        }
+    }
+
+    #[test]
+    fn describes_a_pure_tone() {
+        let features = spectral_features(&decaying_sine(1000.0, 10.0), 0.0).unwrap();
+
+        assert!(
+            (features.centroid - 1000.0).abs() < 100.0,
+            "centroid {}",
+            features.centroid
+        );
+        assert!(
+            (features.rolloff - 1000.0).abs() < 50.0,
+            "rolloff {}",
+            features.rolloff
+        );
+        assert!(
+            features.low_ratio < 0.01,
+            "low ratio {}",
+            features.low_ratio
+        );
+        // A sine crosses zero twice per period.
+        assert!((features.zero_crossing_rate - 2000.0 / SAMPLE_RATE as f64).abs() < 0.001);
+    }
+
+    #[test]
+    fn measures_the_decay() {
+        // The envelope falls 30 dB after ln(10^1.5) time constants.
+        let features = spectral_features(&decaying_sine(200.0, 0.05), 0.0).unwrap();
+        let expected = 0.05 * 10f64.powf(1.5).ln();
+        assert!(
+            (features.decay - expected).abs() < 0.01,
+            "decay {}",
+            features.decay
+        );
+    }
+
+    #[test]
+    fn silence_has_no_features() {
+        let mut audio = decaying_sine(440.0, 1.0);
+        audio.samples.iter_mut().for_each(|s| *s = 0.0);
+        assert_eq!(spectral_features(&audio, 0.0), None);
+        assert_eq!(spectral_features(&decaying_sine(440.0, 1.0), 2.0), None);
+    }
+
+    #[test]
+    fn short_signals_yield_one_spectrum() {
+        let spectra = magnitude_spectra(&[0.5; 100]);
+        assert_eq!(spectra.len(), 1);
+        assert_eq!(spectra[0].len(), FRAME_SIZE / 2 + 1);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::analysis::decoder::DecodedAudio;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Number of frames returned by each call to `AudioStream::read_chunk`.
+pub const CHUNK_FRAMES: usize = 16384;
+
+/// An audio file decoded incrementally in chunks of interleaved `f32` PCM.
+///
+/// Only one chunk and the remainder of the last decoded packet are held in
+/// memory at a time, so long files can be scanned without loading them.
+pub struct AudioStream {
+    path: String,
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    channels: usize,
+    total_frames: Option<u64>,
+    sample_buf: Option<SampleBuffer<f32>>,
+    pending: Vec<f32>,
+    skip_frames: usize,
+    finished: bool,
+}
+
+impl AudioStream {
+    /// Opens an audio file for chunked decoding.
+    ///
+    /// The container format is probed using the file extension as a hint.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the audio file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the stream, or an error message if the file
+    /// cannot be opened or contains no decodable audio track.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Error opening '{}': {}", path, e))?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext) = Path::new(path).extension().and_then(|s| s.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| format!("Unsupported format '{}': {}", path, e))?;
+        let format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| format!("No audio track found in '{}'", path))?;
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| format!("Unsupported codec in '{}': {}", path, e))?;
+
+        let mut stream = Self {
+            path: path.to_string(),
+            track_id: track.id,
+            sample_rate: track.codec_params.sample_rate.unwrap_or(0),
+            channels: track.codec_params.channels.map(|c| c.count()).unwrap_or(0),
+            total_frames: track.codec_params.n_frames,
+            format,
+            decoder,
+            sample_buf: None,
+            pending: Vec::new(),
+            skip_frames: 0,
+            finished: false,
+        };
+
+        // Some containers only reveal the stream layout in the first packet.
+        if stream.channels == 0 || stream.sample_rate == 0 {
+            stream.decode_packet()?;
+        }
+        if stream.channels == 0 || stream.sample_rate == 0 {
+            return Err(format!("Invalid stream parameters in '{}'", path));
+        }
+
+        Ok(stream)
+    }
+
+    /// Returns the sample rate, in Hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the number of interleaved channels.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Returns the duration in seconds, if the container declares it.
+    pub fn duration(&self) -> Option<f64> {
+        self.total_frames
+            .map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Decodes the next chunk of audio.
+    ///
+    /// Corrupt packets are skipped rather than aborting the decode.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - The buffer receiving up to `CHUNK_FRAMES` interleaved
+    ///   frames. It is cleared first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of frames read, which is zero at the
+    /// end of the stream, or an error message if decoding fails.
+    pub fn read_chunk(&mut self, out: &mut Vec<f32>) -> Result<usize, String> {
+        out.clear();
+        let wanted = CHUNK_FRAMES * self.channels;
+
+        while out.len() < wanted {
+            if self.pending.is_empty() && !self.decode_packet()? {
+                break;
+            }
+            let take = (wanted - out.len()).min(self.pending.len());
+            out.extend(self.pending.drain(..take));
+        }
+
+        Ok(out.len() / self.channels)
+    }
+
+    /// Decodes the rest of the stream into memory.
+    pub fn read_all(mut self) -> Result<DecodedAudio, String> {
+        let mut samples = Vec::new();
+        let mut chunk = Vec::with_capacity(CHUNK_FRAMES * self.channels);
+        while self.read_chunk(&mut chunk)? > 0 {
+            samples.extend_from_slice(&chunk);
+        }
+
+        Ok(DecodedAudio {
+            samples,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+        })
+    }
+
+    /// Moves the stream to a frame position.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame the next chunk should start at.
+    pub fn seek(&mut self, frame: u64) -> Result<(), String> {
+        let seeked = self
+            .format
+            .seek(
+                SeekMode::Accurate,
+                SeekTo::TimeStamp {
+                    ts: frame,
+                    track_id: self.track_id,
+                },
+            )
+            .map_err(|e| format!("Error seeking in '{}': {}", self.path, e))?;
+
+        self.decoder.reset();
+        self.pending.clear();
+        self.skip_frames = seeked.required_ts.saturating_sub(seeked.actual_ts) as usize;
+        self.finished = false;
+        Ok(())
+    }
+
+    /// Decodes the next packet of the track into `pending`.
+    ///
+    /// Returns `false` once the end of the stream is reached.
+    fn decode_packet(&mut self) -> Result<bool, String> {
+        while !self.finished {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    self.finished = true;
+                    break;
+                }
+                Err(SymphoniaError::ResetRequired) => {
+                    self.finished = true;
+                    break;
+                }
+                Err(e) => return Err(format!("Error reading '{}': {}", self.path, e)),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    let spec = *decoded.spec();
+                    if self.channels == 0 || self.sample_rate == 0 {
+                        self.channels = spec.channels.count();
+                        self.sample_rate = spec.rate;
+                    }
+                    if spec.channels.count() != self.channels {
+                        continue;
+                    }
+
+                    let buf = match self.sample_buf.as_mut() {
+                        Some(buf) if buf.capacity() >= decoded.capacity() * self.channels => buf,
+                        _ => self
+                            .sample_buf
+                            .insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+                    };
+                    buf.copy_interleaved_ref(decoded);
+
+                    let skip = (self.skip_frames * self.channels).min(buf.samples().len());
+                    self.skip_frames -= skip / self.channels;
+                    self.pending.extend_from_slice(&buf.samples()[skip..]);
+                    if !self.pending.is_empty() {
+                        return Ok(true);
+                    }
+                }
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(format!("Error decoding '{}': {}", self.path, e)),
+            }
+        }
+
+        Ok(false)
+    }
+}
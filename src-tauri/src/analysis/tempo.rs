@@ -0,0 +1,279 @@
+use crate::analysis::classifier::name_tokens;
+use crate::analysis::decoder::DecodedAudio;
+
+/// Window length of the onset envelope, in seconds.
+const WINDOW_SECS: f64 = 0.023;
+/// Hop between onset envelope frames, in seconds.
+const HOP_SECS: f64 = 0.0116;
+/// Shortest sound, in seconds, that tempo detection is attempted on.
+const MIN_DURATION_SECS: f64 = 2.0;
+/// Slowest tempo considered, in BPM.
+const MIN_BPM: f64 = 70.0;
+/// Fastest tempo considered, in BPM.
+const MAX_BPM: f64 = 180.0;
+/// Bar counts a cut loop is expected to span.
+const BAR_COUNTS: &[f64] = &[1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+/// Maximum relative tempo change allowed when snapping to a bar length.
+const SNAP_TOLERANCE: f64 = 0.04;
+
+/// A tempo estimate for a sound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tempo {
+    /// Tempo in beats per minute.
+    pub bpm: f64,
+    /// Strength of the periodicity behind the estimate, from 0 to 1.
+    pub confidence: f64,
+}
+
+/// Estimates the tempo of a sound from its onset envelope.
+///
+/// Onsets are approximated by the positive change of the frame energy.
+/// The autocorrelation of that envelope is searched for the strongest
+/// beat period between 70 and 180 BPM.
+///
+/// # Arguments
+///
+/// * `audio` - The decoded audio to analyse.
+///
+/// # Returns
+///
+/// The tempo estimate, or `None` if the sound is too short or has no
+/// rhythmic content.
+pub fn detect_tempo(audio: &DecodedAudio) -> Option<Tempo> {
+    let sample_rate = audio.sample_rate as f64;
+    if (audio.frames() as f64) < MIN_DURATION_SECS * sample_rate {
+        return None;
+    }
+
+    let envelope = onset_envelope(audio);
+    let frame_rate = sample_rate / (HOP_SECS * sample_rate).round();
+
+    let min_lag = (60.0 * frame_rate / MAX_BPM).floor() as usize;
+    let max_lag = ((60.0 * frame_rate / MIN_BPM).ceil() as usize).min(envelope.len() / 2);
+    if min_lag < 1 || max_lag <= min_lag + 1 {
+        return None;
+    }
+
+    let energy: f64 = envelope.iter().map(|v| v * v).sum();
+    if energy <= 0.0 {
+        return None;
+    }
+
+    let correlation: Vec<f64> = (0..=max_lag + 1)
+        .map(|lag| {
+            envelope
+                .iter()
+                .zip(&envelope[lag.min(envelope.len())..])
+                .map(|(a, b)| a * b)
+                .sum::<f64>()
+                / energy
+        })
+        .collect();
+
+    let best = (min_lag..=max_lag).max_by(|&a, &b| correlation[a].total_cmp(&correlation[b]))?;
+
+    // Refine the peak position with a parabolic fit over its neighbours.
+    let (left, center, right) = (
+        correlation[best - 1],
+        correlation[best],
+        correlation[best + 1],
+    );
+    let curvature = left - 2.0 * center + right;
+    let offset = if curvature < 0.0 {
+        (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+
+    Some(Tempo {
+        bpm: 60.0 * frame_rate / (best as f64 + offset),
+        confidence: center.clamp(0.0, 1.0),
+    })
+}
+
+/// Builds the half-wave rectified energy flux of a mono mixdown.
+fn onset_envelope(audio: &DecodedAudio) -> Vec<f64> {
+    let sample_rate = audio.sample_rate as f64;
+    let window = ((WINDOW_SECS * sample_rate) as usize).max(1);
+    let hop = ((HOP_SECS * sample_rate).round() as usize).max(1);
+    let channels = audio.channels.max(1);
+
+    let mono: Vec<f64> = audio
+        .samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|&s| s as f64).sum::<f64>() / channels as f64)
+        .collect();
+
+    let energies: Vec<f64> = (0..mono.len().saturating_sub(window) / hop + 1)
+        .map(|index| {
+            let start = index * hop;
+            let end = (start + window).min(mono.len());
+            let power = mono[start..end].iter().map(|s| s * s).sum::<f64>() / window as f64;
+            (power + 1e-10).ln()
+        })
+        .collect();
+
+    let mut envelope: Vec<f64> = energies
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.0))
+        .collect();
+
+    let mean = envelope.iter().sum::<f64>() / envelope.len().max(1) as f64;
+    envelope.iter_mut().for_each(|v| *v = (*v - mean).max(0.0));
+    envelope
+}
+
+/// Reads a tempo written into a file name, such as `drums_128bpm` or `140 BPM`.
+///
+/// # Arguments
+///
+/// * `name` - The file name or stem to scan.
+///
+/// # Returns
+///
+/// The tempo, or `None` if the name carries no plausible BPM marker.
+pub fn bpm_from_name(name: &str) -> Option<f64> {
+    let tokens = name_tokens(name);
+
+    let parse = |digits: &str| {
+        digits
+            .parse::<f64>()
+            .ok()
+            .filter(|bpm| (40.0..=250.0).contains(bpm))
+    };
+
+    for (index, token) in tokens.iter().enumerate() {
+        if let Some(digits) = token.strip_suffix("bpm").filter(|d| !d.is_empty()) {
+            if let Some(bpm) = parse(digits) {
+                return Some(bpm);
+            }
+        }
+        if let Some(digits) = token.strip_prefix("bpm").filter(|d| !d.is_empty()) {
+            if let Some(bpm) = parse(digits) {
+                return Some(bpm);
+            }
+        }
+        if *token == "bpm" {
+            let neighbours = [index.checked_sub(1), Some(index + 1)];
+            for neighbour in neighbours.into_iter().flatten() {
+                if let Some(bpm) = tokens.get(neighbour).and_then(|t| parse(t)) {
+                    return Some(bpm);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Finds the bar count whose length best matches a sound's duration.
+///
+/// Bars are assumed to be four beats long.
+///
+/// # Arguments
+///
+/// * `bpm` - The tempo of the sound.
+/// * `duration` - The duration of the sound, in seconds.
+///
+/// # Returns
+///
+/// The closest bar count and the relative tempo change needed to make the
+/// sound span exactly that many bars.
+pub fn bar_fit(bpm: f64, duration: f64) -> Option<(f64, f64)> {
+    if bpm <= 0.0 || duration <= 0.0 {
+        return None;
+    }
+
+    BAR_COUNTS
+        .iter()
+        .map(|&bars| {
+            let exact_bpm = 240.0 * bars / duration;
+            (bars, (exact_bpm - bpm).abs() / bpm)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Snaps a tempo to the value that makes the sound span whole bars.
+///
+/// Loops are usually cut to an exact number of bars, so this corrects small
+/// estimation errors. Tempos further away than the snap tolerance are
+/// returned unchanged.
+///
+/// # Arguments
+///
+/// * `bpm` - The estimated tempo.
+/// * `duration` - The duration of the sound, in seconds.
+pub fn snap_to_bars(bpm: f64, duration: f64) -> f64 {
+    match bar_fit(bpm, duration) {
+        Some((bars, error)) if error <= SNAP_TOLERANCE => 240.0 * bars / duration,
+        _ => bpm,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mono click track: a 5 ms burst on every beat.
+    fn click_track(bpm: f64, seconds: f64) -> DecodedAudio {
+        let sample_rate = 44_100;
+        let frames = (seconds * sample_rate as f64) as usize;
+        let beat = 60.0 * sample_rate as f64 / bpm;
+        let click = (0.005 * sample_rate as f64) as usize;
+        let samples = (0..frames)
+            .map(|n| {
+                let phase = n - ((n as f64 / beat).floor() * beat) as usize;
+                if phase < click {
+                    0.8 * (1.0 - phase as f32 / click as f32)
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate,
+        }
+    }
+
+    #[test]
+    fn detects_the_tempo_of_a_click_track() {
+        for bpm in [90.0, 110.0, 128.0] {
+            let tempo = detect_tempo(&click_track(bpm, 8.0)).unwrap();
+            assert!(
+                (tempo.bpm - bpm).abs() / bpm < 0.03,
+                "expected {} BPM, got {}",
+                bpm,
+                tempo.bpm
+            );
+            assert!(tempo.confidence > 0.5, "confidence {}", tempo.confidence);
+        }
+    }
+
+    #[test]
+    fn short_or_silent_sounds_have_no_tempo() {
+        assert_eq!(detect_tempo(&click_track(120.0, 1.0)), None);
+
+        let mut silence = click_track(120.0, 4.0);
+        silence.samples.iter_mut().for_each(|s| *s = 0.0);
+        assert_eq!(detect_tempo(&silence), None);
+    }
+
+    #[test]
+    fn reads_bpm_from_names() {
+        assert_eq!(bpm_from_name("drums_128bpm"), Some(128.0));
+        assert_eq!(bpm_from_name("Funky Loop 140 BPM"), Some(140.0));
+        assert_eq!(bpm_from_name("bpm95_hats"), Some(95.0));
+        assert_eq!(bpm_from_name("kick_808"), None);
+        assert_eq!(bpm_from_name("noise_900bpm"), None);
+    }
+
+    #[test]
+    fn snaps_to_whole_bars() {
+        // Four bars of 4/4 at 120 BPM last eight seconds.
+        assert_eq!(bar_fit(120.0, 8.0), Some((4.0, 0.0)));
+        assert!((snap_to_bars(118.0, 8.0) - 120.0).abs() < 1e-9);
+        assert_eq!(snap_to_bars(100.0, 8.0), 100.0);
+    }
+}
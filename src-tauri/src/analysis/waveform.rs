@@ -0,0 +1,114 @@
+use crate::analysis::stream::AudioStream;
+use crate::cache::buffer_pool::BufferPool;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Frames summarised by each intermediate peak while streaming.
+const BLOCK_FRAMES: usize = 256;
+/// Largest number of peaks a waveform can be requested with.
+pub const MAX_WAVEFORM_POINTS: usize = 8192;
+
+/// A downsampled waveform for drawing an overview of a sound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waveform {
+    /// Duration of the sound, in seconds.
+    pub duration: f64,
+    /// Lowest and highest sample of each segment, across all channels.
+    pub peaks: Vec<[f32; 2]>,
+}
+
+/// Computes the waveform of a stream, one chunk at a time.
+///
+/// Every block of 256 frames is reduced to its extremes while decoding, so
+/// memory stays small even for long recordings. The blocks are merged into
+/// the requested number of points at the end.
+///
+/// # Arguments
+///
+/// * `stream` - The opened audio stream.
+/// * `buffers` - The pool the decode buffer is taken from.
+/// * `points` - The number of peaks to return.
+///
+/// # Returns
+///
+/// A `Result` containing the waveform, or an error message if decoding fails.
+pub fn compute_waveform(
+    stream: &mut AudioStream,
+    buffers: &Arc<BufferPool>,
+    points: usize,
+) -> Result<Waveform, String> {
+    let channels = stream.channels();
+    let mut blocks: Vec<[f32; 2]> = Vec::new();
+    let mut current = [0.0f32; 2];
+    let mut filled = 0;
+    let mut frames = 0;
+
+    let mut chunk = buffers.acquire();
+    while stream.read_chunk(&mut chunk)? > 0 {
+        for frame in chunk.chunks_exact(channels) {
+            for &sample in frame {
+                current[0] = current[0].min(sample);
+                current[1] = current[1].max(sample);
+            }
+            filled += 1;
+            if filled == BLOCK_FRAMES {
+                blocks.push(current);
+                current = [0.0; 2];
+                filled = 0;
+            }
+        }
+        frames += chunk.len() / channels;
+    }
+    drop(chunk);
+    if filled > 0 {
+        blocks.push(current);
+    }
+
+    let points = points.clamp(1, MAX_WAVEFORM_POINTS);
+    let peaks = if blocks.is_empty() {
+        Vec::new()
+    } else {
+        (0..points)
+            .map(|point| {
+                let start = point * blocks.len() / points;
+                let end = ((point + 1) * blocks.len() / points).max(start + 1);
+                blocks[start..end.min(blocks.len())]
+                    .iter()
+                    .fold([0.0f32; 2], |[low, high], block| {
+                        [low.min(block[0]), high.max(block[1])]
+                    })
+            })
+            .collect()
+    };
+
+    Ok(Waveform {
+        duration: frames as f64 / stream.sample_rate() as f64,
+        peaks,
+    })
+}
+
+/// Streams an audio file and computes its waveform on the blocking thread pool.
+///
+/// # Arguments
+///
+/// * `path` - The path to the audio file.
+/// * `points` - The number of peaks to return.
+/// * `buffers` - The pool the decode buffer is taken from.
+///
+/// # Returns
+///
+/// A `Result` containing the waveform, or an error message if the file
+/// could not be decoded.
+pub async fn load_waveform(
+    path: &str,
+    points: usize,
+    buffers: Arc<BufferPool>,
+) -> Result<Waveform, String> {
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut stream = AudioStream::open(&path)?;
+        compute_waveform(&mut stream, &buffers, points)
+    })
+    .await
+    .map_err(|e| format!("Waveform task failed: {}", e))?
+}
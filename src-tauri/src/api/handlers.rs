@@ -1,77 +1,1190 @@
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::State;
 
-use crate::db::sound::{Sound, SoundRepository};
-use crate::import::importer::Importer;
-use crate::settings::manager::{add_import_path, get_import_paths, remove_import_path};
+use crate::analysis::key::{key_from_name, MusicalKey};
+use crate::analysis::waveform::{load_waveform, Waveform, MAX_WAVEFORM_POINTS};
+use crate::api::library::{Library, LibraryInfo, LibraryListeners};
+use crate::cache::cache_module::{CacheCapacity, CacheStats};
+use crate::db::backup::{BackupInfo, IntegrityReport};
+use crate::db::collection::Collection;
+use crate::db::journal::{JournalEntry, JournalOp, SoundFields};
+use crate::db::sound::{
+    unix_now, BatchFailure, BatchSummary, Sound, SoundCursor, SoundEdit, SoundPage, SoundQuery,
+    SoundStatus,
+};
+use crate::import::health::{HealthAction, HealthReport};
+use crate::import::importer::RescanSummary;
+use crate::import::library_file::{
+    relative_path, LibraryEntry, LibraryFile, LibraryFormat, LibraryImportSummary,
+    LIBRARY_FILE_VERSION,
+};
+use crate::import::scanner::ImportFilter;
+use crate::import::volume::volume_of;
+use crate::playback::engine::{PlaybackEngine, PlaybackStatus};
+use crate::playback::stretch::StretchParams;
+use crate::settings::manager::{
+    CacheSettings, ImportPath, PreviewTarget, Settings, SettingsManager, SettingsState,
+};
+use crate::similarity::index::SimilarSound;
+use crate::utils::logger::{log, LogLevel};
+use crate::utils::trash::{restore_file, trash_file};
+
+/// Adds an import path to a library along with the volume it lives on.
+fn track_import_path(settings: &SettingsManager, library: &str, path: String) {
+    if let Some(volume) = volume_of(&path) {
+        settings.set_import_volume(library, &path, volume);
+    }
+    settings.add_import_path(library, path);
+}
+
+/// Moves the files of sounds to the desktop trash.
+///
+/// Files that are already gone are skipped. With `delete_untrashable`,
+/// files that cannot be trashed are deleted instead.
+///
+/// # Returns
+///
+/// The IDs of the sounds whose file was trashed with its path inside the
+/// trash, and the sounds whose file could be neither trashed nor deleted.
+async fn trash_sound_files(
+    sounds: &[Sound],
+    delete_untrashable: bool,
+) -> (Vec<(i64, String)>, Vec<BatchFailure>) {
+    let mut trashed = vec![];
+    let mut failed = vec![];
+    for sound in sounds {
+        let Some(id) = sound.id else { continue };
+        if !Path::new(&sound.path).exists() {
+            continue;
+        }
+        let path = PathBuf::from(&sound.path);
+        let outcome = tokio::task::spawn_blocking(move || {
+            trash_file(&path).or_else(|e| {
+                if !delete_untrashable {
+                    return Err(e);
+                }
+                fs::remove_file(&path)
+                    .map(|_| PathBuf::new())
+                    .map_err(|remove| format!("{}; deleting it failed: {}", e, remove))
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|outcome| outcome);
+        match outcome {
+            Ok(location) if location.as_os_str().is_empty() => {}
+            Ok(location) => trashed.push((id, location.to_string_lossy().into_owned())),
+            Err(error) => failed.push(BatchFailure { id, error }),
+        }
+    }
+    (trashed, failed)
+}
+
+/// Moves trashed files back to where they were, when storing that they
+/// were trashed failed. Files that cannot be moved back are logged.
+async fn untrash_sound_files(sounds: &[Sound], trashed: &[(i64, String)]) {
+    for (id, location) in trashed {
+        let Some(sound) = sounds.iter().find(|sound| sound.id == Some(*id)) else {
+            continue;
+        };
+        let location = PathBuf::from(location);
+        let original = PathBuf::from(&sound.path);
+        let outcome = tokio::task::spawn_blocking(move || restore_file(&location, &original))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|outcome| outcome);
+        if let Err(e) = outcome {
+            log(
+                LogLevel::Error,
+                "untrash_sound_files",
+                &format!("Failed to move '{}' back from the trash: {}", sound.path, e),
+            );
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Api {
-    pub repo: Arc<SoundRepository>,
-    pub importer: Arc<Importer>,
+    library: Arc<RwLock<Arc<Library>>>,
+    listeners: Arc<Mutex<Option<LibraryListeners>>>,
+    switching: Arc<tokio::sync::Mutex<()>>,
+    pub settings: Arc<SettingsManager>,
+    pub player: Arc<PlaybackEngine>,
 }
 
 impl Api {
-    pub fn new(repo: Arc<SoundRepository>, importer: Arc<Importer>) -> Self {
-        Self { repo, importer }
+    pub fn new(
+        settings: Arc<SettingsManager>,
+        library: Library,
+        player: Arc<PlaybackEngine>,
+    ) -> Self {
+        Self {
+            library: Arc::new(RwLock::new(Arc::new(library))),
+            listeners: Arc::new(Mutex::new(None)),
+            switching: Arc::new(tokio::sync::Mutex::new(())),
+            settings,
+            player,
+        }
+    }
+
+    /// Returns the active library.
+    pub fn library(&self) -> Arc<Library> {
+        Arc::clone(&self.library.read().unwrap())
+    }
+
+    /// Connects the active library, and every library switched to later,
+    /// to the UI.
+    pub fn set_library_listeners(&self, listeners: LibraryListeners) {
+        self.library().attach(&listeners);
+        *self.listeners.lock().unwrap() = Some(listeners);
+    }
+
+    pub fn list_libraries_method(&self) -> Vec<LibraryInfo> {
+        let active = self.settings.active_library().name;
+        self.settings
+            .libraries()
+            .iter()
+            .map(|library| LibraryInfo::new(library, &active))
+            .collect()
+    }
+
+    pub fn create_library_method(&self, name: String) -> Result<LibraryInfo, String> {
+        let library = self.settings.add_library(&name)?;
+        log(
+            LogLevel::Info,
+            "Api::create_library_method",
+            &format!("Created library '{}'", library.name),
+        );
+        Ok(LibraryInfo::new(
+            &library,
+            &self.settings.active_library().name,
+        ))
+    }
+
+    /// Opens another library and swaps it in for the active one.
+    ///
+    /// Playback is stopped since the playing sound belongs to the previous
+    /// library, whose database is closed once nothing uses it anymore.
+    pub async fn switch_library_method(&self, name: String) -> Result<LibraryInfo, String> {
+        let _switching = self.switching.lock().await;
+        let entry = self
+            .settings
+            .libraries()
+            .into_iter()
+            .find(|library| library.name == name)
+            .ok_or_else(|| format!("Library '{}' does not exist", name))?;
+        if self.library().name == entry.name {
+            return Ok(LibraryInfo::new(&entry, &entry.name));
+        }
+
+        let library = Library::open(&self.settings, &entry).await?;
+        self.settings.set_active_library(&entry.name)?;
+        if let Some(listeners) = self.listeners.lock().unwrap().as_ref() {
+            library.attach(listeners);
+        }
+        self.player.stop();
+        self.player.set_cache(Arc::clone(&library.importer.cache));
+        let previous = std::mem::replace(&mut *self.library.write().unwrap(), Arc::new(library));
+        previous.detach();
+
+        log(
+            LogLevel::Info,
+            "Api::switch_library_method",
+            &format!(
+                "Switched from library '{}' to '{}'",
+                previous.name, entry.name
+            ),
+        );
+        Ok(LibraryInfo::new(&entry, &entry.name))
     }
 
     pub async fn import_sound_method(&self, name: String, path: String) -> Result<(), String> {
-        self.importer
+        let library = self.library();
+        library
+            .importer
             .import_sound(&name, &path)
             .await
             .map_err(|e| format!("Error importing sound: {}", e))?;
-        add_import_path(path);
+        track_import_path(&self.settings, &library.name, path);
         Ok(())
     }
 
     pub async fn import_directory_method(&self, dir_path: String) -> Result<(), String> {
-        self.importer
-            .import_directory(&dir_path)
+        let library = self.library();
+        let options = self
+            .settings
+            .import_path_options(&library.name)
+            .into_iter()
+            .find(|entry| entry.path == dir_path)
+            .unwrap_or_else(|| ImportPath::new(dir_path.clone()));
+        library
+            .importer
+            .import_directory(&options)
             .await
             .map_err(|e| format!("Error importing directory: {}", e))?;
-        add_import_path(dir_path);
+        track_import_path(&self.settings, &library.name, dir_path);
         Ok(())
     }
 
-    pub async fn get_sounds_method(&self) -> Result<Vec<Sound>, String> {
-        let sounds = self
+    pub async fn get_sounds_method(
+        &self,
+        query: SoundQuery,
+        cursor: Option<SoundCursor>,
+        limit: Option<usize>,
+    ) -> Result<SoundPage, String> {
+        self.library()
             .repo
-            .get_all()
+            .list(
+                &query,
+                cursor.as_ref(),
+                limit.unwrap_or(self.settings.get().ui.page_size),
+            )
             .await
-            .map_err(|e| format!("Error fetching sounds: {}", e))?;
-        Ok(sounds)
+            .map_err(|e| format!("Error fetching sounds: {}", e))
+    }
+
+    pub async fn search_sounds_method(&self, query: SoundQuery) -> Result<Vec<Sound>, String> {
+        self.library()
+            .repo
+            .search(&query)
+            .await
+            .map_err(|e| format!("Error searching sounds: {}", e))
+    }
+
+    pub async fn analyze_sound_method(&self, id: i64) -> Result<Sound, String> {
+        self.library()
+            .importer
+            .analyze_sound(id)
+            .await
+            .map_err(|e| format!("Error analyzing sound: {}", e))
+    }
+
+    pub async fn get_waveform_method(&self, id: i64, points: usize) -> Result<Waveform, String> {
+        let library = self.library();
+        let sound = library
+            .repo
+            .get(id)
+            .await
+            .map_err(|e| format!("Error fetching sound: {}", e))?;
+        let sound = library.importer.refresh(sound).await?;
+
+        let cache = &library.importer.cache;
+        let points = points.clamp(1, MAX_WAVEFORM_POINTS);
+        let key = format!(
+            "{}-waveform-{}",
+            cache.content_key(&sound.path).await?,
+            points
+        );
+        if let Some(waveform) = cache.get_json(&key).await {
+            return Ok(waveform);
+        }
+
+        let waveform = load_waveform(&sound.path, points, cache.buffers())
+            .await
+            .map_err(|e| format!("Error computing waveform: {}", e))?;
+        cache.put_json(key, &waveform, true).await;
+        Ok(waveform)
+    }
+
+    pub async fn export_trimmed_sound_method(&self, id: i64) -> Result<String, String> {
+        self.library()
+            .importer
+            .export_trimmed(id)
+            .await
+            .map_err(|e| format!("Error exporting trimmed sound: {}", e))
+    }
+
+    pub async fn find_similar_method(
+        &self,
+        id: i64,
+        limit: usize,
+    ) -> Result<Vec<SimilarSound>, String> {
+        let library = self.library();
+        let neighbours = library
+            .importer
+            .fingerprints
+            .nearest(id, limit)
+            .await
+            .map_err(|e| format!("Error finding similar sounds: {}", e))?;
+
+        // A neighbour deleted since the search is left out instead of
+        // failing the whole search.
+        let mut similar = Vec::with_capacity(neighbours.len());
+        for (neighbour_id, distance) in neighbours {
+            match library.repo.get(neighbour_id).await {
+                Ok(sound) => similar.push(SimilarSound { sound, distance }),
+                Err(e) => log(
+                    LogLevel::Warn,
+                    "Api::find_similar_method",
+                    &format!("Skipping sound {}: {}", neighbour_id, e),
+                ),
+            }
+        }
+        Ok(similar)
     }
 
-    pub async fn delete_sound_method(&self, id: String) -> Result<(), String> {
+    pub async fn delete_sound_method(
+        &self,
+        id: String,
+        trash_file: bool,
+        delete_untrashable_file: bool,
+    ) -> Result<(), String> {
         let parsed_id: i64 = id.parse().map_err(|e| format!("Invalid id: {}", e))?;
-        self.repo
-            .delete(parsed_id)
+        let library = self.library();
+        let summary = self
+            .delete_sounds(&library, &[parsed_id], trash_file, delete_untrashable_file)
+            .await?;
+        match summary.failed.into_iter().next() {
+            Some(failure) => Err(failure.error),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn edit_sounds_method(
+        &self,
+        ids: Vec<i64>,
+        edit: SoundEdit,
+    ) -> Result<BatchSummary, String> {
+        let library = self.library();
+        let edit = match edit {
+            SoundEdit::Tag { tag } | SoundEdit::Untag { tag } if tag.trim().is_empty() => {
+                return Err("Tag must not be empty".to_string());
+            }
+            SoundEdit::Tag { tag } => SoundEdit::Tag {
+                tag: tag.trim().to_string(),
+            },
+            SoundEdit::Untag { tag } => SoundEdit::Untag {
+                tag: tag.trim().to_string(),
+            },
+            edit => edit,
+        };
+
+        if let SoundEdit::Delete {
+            trash_files,
+            delete_untrashable_files,
+        } = edit
+        {
+            return self
+                .delete_sounds(&library, &ids, trash_files, delete_untrashable_files)
+                .await;
+        }
+
+        if let SoundEdit::MoveToCollection {
+            collection_id: Some(id),
+        } = edit
+        {
+            match library.collections.get(id).await {
+                Ok(_) => {}
+                Err(sqlx::Error::RowNotFound) => {
+                    return Err(format!("Collection {} does not exist", id));
+                }
+                Err(e) => return Err(format!("Error fetching collection: {}", e)),
+            }
+        }
+
+        let before = library
+            .repo
+            .get_many(&ids)
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        let summary = library
+            .repo
+            .edit_many(&ids, &edit)
+            .await
+            .map_err(|e| format!("Error editing sounds: {}", e))?;
+
+        let count = summary.changed;
+        let label = match &edit {
+            SoundEdit::Favorite => format!("Favorite {} sounds", count),
+            SoundEdit::Unfavorite => format!("Unfavorite {} sounds", count),
+            SoundEdit::Tag { tag } => format!("Tag {} sounds with '{}'", count, tag),
+            SoundEdit::Untag { tag } => format!("Remove tag '{}' from {} sounds", tag, count),
+            SoundEdit::MoveToCollection {
+                collection_id: None,
+            } => {
+                format!("Remove {} sounds from their collection", count)
+            }
+            SoundEdit::MoveToCollection { .. } => format!("Move {} sounds to a collection", count),
+            SoundEdit::Delete { .. } => unreachable!(),
+        };
+        self.record_field_changes(&library, label, before).await?;
+        Ok(summary)
+    }
+
+    /// Moves sounds to the library's trash and records how to restore them.
+    ///
+    /// With `trash_files`, each file is moved to the desktop trash first,
+    /// and moved back if the sounds cannot be deleted after all. The
+    /// deletion and where the files went are stored together, so a failure
+    /// leaves no sound pointing at a trashed file. Sounds whose file can be
+    /// neither trashed nor, with `delete_untrashable_files`, deleted are kept
+    /// and reported as failed.
+    async fn delete_sounds(
+        &self,
+        library: &Library,
+        ids: &[i64],
+        trash_files: bool,
+        delete_untrashable_files: bool,
+    ) -> Result<BatchSummary, String> {
+        let sounds: Vec<Sound> = library
+            .repo
+            .get_many(ids)
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?
+            .into_iter()
+            .filter(|sound| sound.deleted_at.is_none())
+            .collect();
+
+        let (trashed, failed) = if trash_files {
+            trash_sound_files(&sounds, delete_untrashable_files).await
+        } else {
+            (vec![], vec![])
+        };
+
+        let deletable: Vec<i64> = sounds
+            .iter()
+            .filter_map(|sound| sound.id)
+            .filter(|id| !failed.iter().any(|failure| failure.id == *id))
+            .collect();
+        let changed = match library.repo.delete_many(&deletable, &trashed).await {
+            Ok(changed) => changed,
+            Err(e) => {
+                untrash_sound_files(&sounds, &trashed).await;
+                return Err(format!("Error deleting sounds: {}", e));
+            }
+        };
+        library.importer.fingerprints.forget(&deletable).await;
+
+        let mut requested = ids.to_vec();
+        requested.sort_unstable();
+        requested.dedup();
+        let summary = BatchSummary {
+            requested: requested.len(),
+            changed,
+            unchanged: deletable.len() - changed,
+            not_found: requested
+                .into_iter()
+                .filter(|id| !sounds.iter().any(|sound| sound.id == Some(*id)))
+                .collect(),
+            failed,
+        };
+
+        if !deletable.is_empty() {
+            let label = match sounds.as_slice() {
+                [only] => format!("Delete '{}'", only.name),
+                _ => format!("Delete {} sounds", deletable.len()),
+            };
+            self.record(
+                library,
+                label,
+                vec![JournalOp::RestoreSounds {
+                    ids: deletable.clone(),
+                }],
+                vec![JournalOp::DeleteSounds {
+                    ids: deletable,
+                    trashed: trashed.into_iter().map(|(id, _)| id).collect(),
+                }],
+            )
+            .await;
+        }
+        Ok(summary)
+    }
+
+    pub async fn restore_sounds_method(&self, ids: Vec<i64>) -> Result<BatchSummary, String> {
+        let library = self.library();
+        let mut requested = ids;
+        requested.sort_unstable();
+        requested.dedup();
+        let existing: Vec<i64> = library
+            .repo
+            .get_many(&requested)
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?
+            .iter()
+            .filter_map(|sound| sound.id)
+            .collect();
+
+        let restored = library
+            .repo
+            .restore(&requested)
+            .await
+            .map_err(|e| format!("Error restoring sounds: {}", e))?;
+        library.importer.fingerprints.restore(&restored).await?;
+        let (untrashed, failed) = self.restore_trashed_files(&library, &restored).await;
+
+        if !restored.is_empty() {
+            self.record(
+                &library,
+                format!("Restore {} sounds", restored.len()),
+                vec![JournalOp::DeleteSounds {
+                    ids: restored.clone(),
+                    trashed: untrashed,
+                }],
+                vec![JournalOp::RestoreSounds {
+                    ids: restored.clone(),
+                }],
+            )
+            .await;
+        }
+
+        Ok(BatchSummary {
+            requested: requested.len(),
+            changed: restored.len(),
+            unchanged: existing.len() - restored.len(),
+            not_found: requested
+                .into_iter()
+                .filter(|id| !existing.contains(id))
+                .collect(),
+            failed,
+        })
+    }
+
+    /// Moves the trashed files of restored sounds back into place.
+    ///
+    /// # Returns
+    ///
+    /// The IDs of the sounds whose file was moved back, and the sounds whose
+    /// file could not be.
+    async fn restore_trashed_files(
+        &self,
+        library: &Library,
+        ids: &[i64],
+    ) -> (Vec<i64>, Vec<BatchFailure>) {
+        let sounds = match library.repo.get_many(ids).await {
+            Ok(sounds) => sounds,
+            Err(e) => {
+                let failed = ids
+                    .iter()
+                    .map(|&id| BatchFailure {
+                        id,
+                        error: format!("Error fetching sound: {}", e),
+                    })
+                    .collect();
+                return (vec![], failed);
+            }
+        };
+
+        let mut untrashed = vec![];
+        let mut failed = vec![];
+        for sound in sounds {
+            let (Some(id), Some(trashed)) = (sound.id, sound.trashed_path) else {
+                continue;
+            };
+            let original = PathBuf::from(&sound.path);
+            let outcome =
+                tokio::task::spawn_blocking(move || restore_file(Path::new(&trashed), &original))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|outcome| outcome);
+            let outcome = match outcome {
+                Ok(()) => library
+                    .repo
+                    .update_trashed_path(id, None)
+                    .await
+                    .map_err(|e| format!("Error clearing trashed file location: {}", e)),
+                Err(e) => Err(e),
+            };
+            match outcome {
+                Ok(()) => untrashed.push(id),
+                Err(error) => failed.push(BatchFailure { id, error }),
+            }
+        }
+        (untrashed, failed)
+    }
+
+    /// Moves the files of deleted sounds to the desktop trash again when a
+    /// deletion is redone.
+    async fn retrash_files(&self, library: &Library, ids: &[i64]) -> Result<(), String> {
+        let sounds = library
+            .repo
+            .get_many(ids)
             .await
-            .map_err(|e| format!("Error deleting sound: {}", e))?;
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        let (trashed, failed) = trash_sound_files(&sounds, false).await;
+        for failure in failed {
+            log(
+                LogLevel::Warn,
+                "Api::retrash_files",
+                &format!("Sound {}: {}", failure.id, failure.error),
+            );
+        }
+        for (id, location) in trashed {
+            if let Err(e) = library.repo.update_trashed_path(id, Some(&location)).await {
+                untrash_sound_files(&sounds, &[(id, location)]).await;
+                return Err(format!("Error storing trashed file location: {}", e));
+            }
+        }
         Ok(())
     }
 
+    pub async fn purge_deleted_method(&self, all: bool) -> Result<usize, String> {
+        let library = self.library();
+        let cutoff = if all {
+            i64::MAX
+        } else {
+            unix_now() - i64::from(self.settings.get().trash.retention_days) * 86_400
+        };
+        let purged = library
+            .repo
+            .purge(cutoff)
+            .await
+            .map_err(|e| format!("Error purging deleted sounds: {}", e))?;
+        library.importer.fingerprints.forget(&purged).await;
+        Ok(purged.len())
+    }
+
+    pub async fn create_backup_method(&self) -> Result<BackupInfo, String> {
+        self.library()
+            .backups
+            .create("manual")
+            .await
+            .map_err(|e| format!("Error creating backup: {}", e))
+    }
+
+    pub async fn list_backups_method(&self) -> Result<Vec<BackupInfo>, String> {
+        self.library()
+            .backups
+            .list()
+            .map_err(|e| format!("Error listing backups: {}", e))
+    }
+
+    pub async fn restore_backup_method(&self, name: String) -> Result<(), String> {
+        let library = self.library();
+        library
+            .backups
+            .restore(&name)
+            .await
+            .map_err(|e| format!("Error restoring backup: {}", e))?;
+        library.importer.fingerprints.reload().await
+    }
+
+    pub async fn check_integrity_method(&self) -> Result<IntegrityReport, String> {
+        self.library()
+            .backups
+            .integrity_check()
+            .await
+            .map_err(|e| format!("Error checking database integrity: {}", e))
+    }
+
+    pub async fn get_trash_retention_days_method(&self) -> Result<u32, String> {
+        Ok(self.settings.get().trash.retention_days)
+    }
+
+    pub async fn set_trash_retention_days_method(&self, days: u32) -> Result<u32, String> {
+        let settings = self
+            .settings
+            .update(|settings| settings.trash.retention_days = days)?;
+        Ok(settings.trash.retention_days)
+    }
+
+    /// Records the field changes made to sounds since `before` was read.
+    async fn record_field_changes(
+        &self,
+        library: &Library,
+        label: String,
+        before: Vec<Sound>,
+    ) -> Result<(), String> {
+        let ids: Vec<i64> = before.iter().filter_map(|sound| sound.id).collect();
+        let after: HashMap<i64, SoundFields> = library
+            .repo
+            .get_many(&ids)
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?
+            .iter()
+            .filter_map(SoundFields::of)
+            .map(|fields| (fields.id, fields))
+            .collect();
+
+        let (undo, redo): (Vec<_>, Vec<_>) = before
+            .iter()
+            .filter_map(SoundFields::of)
+            .filter_map(|old| {
+                let new = after.get(&old.id)?;
+                (*new != old).then(|| (old, new.clone()))
+            })
+            .unzip();
+        if !undo.is_empty() {
+            self.record(
+                library,
+                label,
+                vec![JournalOp::SetFields { sounds: undo }],
+                vec![JournalOp::SetFields { sounds: redo }],
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Records an edit in the journal.
+    ///
+    /// The edit itself already succeeded, so a failure to record it is only
+    /// logged by the repository.
+    async fn record(
+        &self,
+        library: &Library,
+        label: String,
+        undo: Vec<JournalOp>,
+        redo: Vec<JournalOp>,
+    ) {
+        let _ = library.journal.record(&label, undo, redo).await;
+    }
+
+    pub async fn undo_method(&self) -> Result<Option<JournalEntry>, String> {
+        let library = self.library();
+        let applied = library
+            .journal
+            .undo()
+            .await
+            .map_err(|e| format!("Error undoing edit: {}", e))?;
+        self.finish_step(&library, applied).await
+    }
+
+    pub async fn redo_method(&self) -> Result<Option<JournalEntry>, String> {
+        let library = self.library();
+        let applied = library
+            .journal
+            .redo()
+            .await
+            .map_err(|e| format!("Error redoing edit: {}", e))?;
+        self.finish_step(&library, applied).await
+    }
+
+    pub async fn get_history_method(&self) -> Result<Vec<JournalEntry>, String> {
+        self.library()
+            .journal
+            .history()
+            .await
+            .map_err(|e| format!("Error fetching edit history: {}", e))
+    }
+
+    /// Brings the fingerprint index and trashed files in line with sounds
+    /// restored or deleted by a journal step.
+    ///
+    /// Files are moved back from the trash when a deletion is undone and
+    /// trashed again when it is redone.
+    async fn finish_step(
+        &self,
+        library: &Library,
+        applied: Option<(JournalEntry, Vec<JournalOp>)>,
+    ) -> Result<Option<JournalEntry>, String> {
+        let Some((entry, ops)) = applied else {
+            return Ok(None);
+        };
+        for op in ops {
+            match op {
+                JournalOp::RestoreSounds { ids } => {
+                    library.importer.fingerprints.restore(&ids).await?;
+                    for failure in self.restore_trashed_files(library, &ids).await.1 {
+                        log(
+                            LogLevel::Warn,
+                            "Api::finish_step",
+                            &format!("Sound {}: {}", failure.id, failure.error),
+                        );
+                    }
+                }
+                JournalOp::DeleteSounds { ids, trashed } => {
+                    library.importer.fingerprints.forget(&ids).await;
+                    self.retrash_files(library, &trashed).await?;
+                }
+                _ => {}
+            }
+        }
+        Ok(Some(entry))
+    }
+
+    pub async fn reanalyze_sounds_method(&self, ids: Vec<i64>) -> Result<BatchSummary, String> {
+        Ok(self.library().importer.reanalyze_many(&ids).await)
+    }
+
+    pub async fn get_collections_method(&self) -> Result<Vec<Collection>, String> {
+        self.library()
+            .collections
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching collections: {}", e))
+    }
+
+    pub async fn create_collection_method(&self, name: String) -> Result<i64, String> {
+        let library = self.library();
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Collection name must not be empty".to_string());
+        }
+        let id = library
+            .collections
+            .insert(name)
+            .await
+            .map_err(|e| format!("Error creating collection: {}", e))?;
+        self.record(
+            &library,
+            format!("Create collection '{}'", name),
+            vec![JournalOp::DeleteCollection { id }],
+            vec![JournalOp::InsertCollection {
+                id,
+                name: name.to_string(),
+                members: vec![],
+            }],
+        )
+        .await;
+        Ok(id)
+    }
+
+    pub async fn delete_collection_method(&self, id: i64) -> Result<(), String> {
+        let library = self.library();
+        let collection = library
+            .collections
+            .get(id)
+            .await
+            .map_err(|e| format!("Error fetching collection: {}", e))?;
+        let members = library
+            .collections
+            .members(id)
+            .await
+            .map_err(|e| format!("Error fetching collection: {}", e))?;
+        library
+            .collections
+            .delete(id)
+            .await
+            .map_err(|e| format!("Error deleting collection: {}", e))?;
+        self.record(
+            &library,
+            format!("Delete collection '{}'", collection.name),
+            vec![JournalOp::InsertCollection {
+                id,
+                name: collection.name,
+                members,
+            }],
+            vec![JournalOp::DeleteCollection { id }],
+        )
+        .await;
+        Ok(())
+    }
+
+    pub async fn export_library_method(
+        &self,
+        format: LibraryFormat,
+        path: String,
+    ) -> Result<usize, String> {
+        let library = self.library();
+        let sounds = library
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        let collections = library
+            .collections
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching collections: {}", e))?;
+        let names: HashMap<i64, String> = collections
+            .iter()
+            .map(|collection| (collection.id, collection.name.clone()))
+            .collect();
+        let roots = self.settings.get_import_paths(&library.name);
+
+        let library_file = LibraryFile {
+            version: LIBRARY_FILE_VERSION,
+            exported_at: unix_now(),
+            collections: collections.into_iter().map(|c| c.name).collect(),
+            sounds: sounds
+                .into_iter()
+                .map(|sound| {
+                    let collection = sound.collection_id.and_then(|id| names.get(&id).cloned());
+                    LibraryEntry::export(sound, &roots, collection)
+                })
+                .collect(),
+        };
+        fs::write(&path, library_file.write(format)?)
+            .map_err(|e| format!("Error writing '{}': {}", path, e))?;
+        Ok(library_file.sounds.len())
+    }
+
+    /// Applies the favorites, tags, ratings and collections of a library
+    /// file to the matching sounds.
+    ///
+    /// Entries are matched by their path relative to an import root, then
+    /// by content hash, in which case every sound with that hash is
+    /// matched. Entries matching no sound are imported if their file exists
+    /// below one of the import roots and is not in the library yet.
+    /// Curation is merged: favorites and tags are added, ratings and
+    /// collections from the file win, and nothing is removed.
+    pub async fn import_library_method(
+        &self,
+        file: String,
+    ) -> Result<LibraryImportSummary, String> {
+        let library = self.library();
+        let contents =
+            fs::read_to_string(&file).map_err(|e| format!("Error reading '{}': {}", file, e))?;
+        let library_file = LibraryFile::parse(&contents, LibraryFormat::of_file(Path::new(&file)))?;
+        let roots = self.settings.get_import_paths(&library.name);
+        let mut summary = LibraryImportSummary {
+            entries: library_file.sounds.len(),
+            ..Default::default()
+        };
+
+        let mut sounds = library
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        let known_paths: HashSet<String> = sounds
+            .iter()
+            .map(|sound| relative_path(&sound.path, &roots))
+            .collect();
+        let known_hashes: HashSet<&str> = sounds
+            .iter()
+            .filter_map(|sound| sound.content_hash.as_deref())
+            .collect();
+        // Local files already in the library, or imported for an earlier
+        // entry, are not imported again.
+        let mut local_paths: HashSet<PathBuf> = sounds
+            .iter()
+            .map(|sound| PathBuf::from(&sound.path))
+            .collect();
+        let mut imported = HashSet::new();
+        for entry in &library_file.sounds {
+            let hash = entry.sound.content_hash.as_deref();
+            if known_paths.contains(&entry.sound.path)
+                || hash.is_some_and(|h| known_hashes.contains(h))
+            {
+                continue;
+            }
+            let local = Some(PathBuf::from(&entry.sound.path))
+                .filter(|path| path.is_absolute())
+                .into_iter()
+                .chain(
+                    roots
+                        .iter()
+                        .map(|root| Path::new(root).join(&entry.sound.path)),
+                )
+                .find(|path| path.is_file());
+            let Some(local) = local.filter(|local| local_paths.insert(local.clone())) else {
+                continue;
+            };
+            if library
+                .importer
+                .import_sound(&entry.sound.name, &local.to_string_lossy())
+                .await?
+            {
+                imported.insert(entry.sound.path.clone());
+            }
+        }
+        summary.imported = imported.len();
+        if !imported.is_empty() {
+            sounds = library
+                .repo
+                .get_all()
+                .await
+                .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        }
+
+        let by_path: HashMap<String, &Sound> = sounds
+            .iter()
+            .map(|sound| (relative_path(&sound.path, &roots), sound))
+            .collect();
+        let mut by_hash: HashMap<&str, Vec<&Sound>> = HashMap::new();
+        for sound in &sounds {
+            if let Some(hash) = sound.content_hash.as_deref() {
+                by_hash.entry(hash).or_default().push(sound);
+            }
+        }
+
+        let mut collection_ids: HashMap<String, i64> = library
+            .collections
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching collections: {}", e))?
+            .into_iter()
+            .map(|collection| (collection.name, collection.id))
+            .collect();
+        let mut created = vec![];
+        let wanted = library_file.collections.iter().chain(
+            library_file
+                .sounds
+                .iter()
+                .filter_map(|e| e.collection.as_ref()),
+        );
+        for name in wanted {
+            let name = name.trim();
+            if name.is_empty() || collection_ids.contains_key(name) {
+                continue;
+            }
+            let id = library
+                .collections
+                .insert(name)
+                .await
+                .map_err(|e| format!("Error creating collection: {}", e))?;
+            collection_ids.insert(name.to_string(), id);
+            created.push((id, name.to_string()));
+        }
+        summary.created_collections = created.len();
+
+        let mut changes: HashMap<i64, (SoundFields, SoundFields)> = HashMap::new();
+        for entry in &library_file.sounds {
+            let hash = entry.sound.content_hash.as_deref();
+            // Copies of a file share its hash, so an entry matched by hash
+            // applies to every copy.
+            let matched: &[&Sound] = match by_path.get(&entry.sound.path) {
+                Some(sound) => {
+                    if !imported.contains(&entry.sound.path) {
+                        summary.matched_by_path += 1;
+                    }
+                    std::slice::from_ref(sound)
+                }
+                None => match hash.and_then(|h| by_hash.get(h)) {
+                    Some(sounds) => {
+                        summary.matched_by_hash += 1;
+                        sounds
+                    }
+                    None => {
+                        summary.unmatched.push(entry.sound.path.clone());
+                        continue;
+                    }
+                },
+            };
+            for old in matched.iter().filter_map(|sound| SoundFields::of(sound)) {
+                let new = &mut changes
+                    .entry(old.id)
+                    .or_insert_with(|| (old.clone(), old))
+                    .1;
+                new.is_favorite |= entry.sound.is_favorite;
+                for tag in entry.sound.tags.0.iter().map(|tag| tag.trim()) {
+                    if !tag.is_empty() && !new.tags.iter().any(|known| known == tag) {
+                        new.tags.push(tag.to_string());
+                    }
+                }
+                if let Some(rating) = entry.sound.rating.filter(|r| (1..=5).contains(r)) {
+                    new.rating = Some(rating);
+                }
+                if let Some(id) = entry
+                    .collection
+                    .as_deref()
+                    .and_then(|name| collection_ids.get(name.trim()))
+                {
+                    new.collection_id = Some(*id);
+                }
+            }
+        }
+
+        let mut changes: Vec<(SoundFields, SoundFields)> = changes
+            .into_values()
+            .filter(|(old, new)| old != new)
+            .collect();
+        changes.sort_by_key(|(old, _)| old.id);
+        let (undo, redo): (Vec<_>, Vec<_>) = changes.into_iter().unzip();
+        library
+            .repo
+            .update_fields(&redo)
+            .await
+            .map_err(|e| format!("Error updating sounds: {}", e))?;
+        summary.updated = redo.len();
+
+        if !redo.is_empty() || !created.is_empty() {
+            let label = format!(
+                "Import library from '{}'",
+                Path::new(&file)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or(file.clone())
+            );
+            let mut undo_ops = vec![JournalOp::SetFields { sounds: undo }];
+            let mut redo_ops = vec![];
+            for (id, name) in created {
+                undo_ops.push(JournalOp::DeleteCollection { id });
+                redo_ops.push(JournalOp::InsertCollection {
+                    id,
+                    name,
+                    members: vec![],
+                });
+            }
+            redo_ops.push(JournalOp::SetFields { sounds: redo });
+            self.record(&library, label, undo_ops, redo_ops).await;
+        }
+
+        log(
+            LogLevel::Info,
+            "Api::import_library",
+            &format!(
+                "Imported library '{}': {} entries, {} updated, {} unmatched",
+                file,
+                summary.entries,
+                summary.updated,
+                summary.unmatched.len()
+            ),
+        );
+        Ok(summary)
+    }
+
     pub async fn get_imported_paths_method(&self) -> Result<Vec<String>, String> {
-        Ok(get_import_paths())
+        Ok(self.settings.get_import_paths(&self.library().name))
+    }
+
+    pub async fn get_import_path_options_method(&self) -> Result<Vec<ImportPath>, String> {
+        Ok(self.settings.import_path_options(&self.library().name))
+    }
+
+    pub async fn set_import_path_options_method(
+        &self,
+        options: ImportPath,
+    ) -> Result<Vec<ImportPath>, String> {
+        ImportFilter::new(&options)?;
+        let library = self.library();
+        self.settings
+            .update_import_path_options(&library.name, options);
+        Ok(self.settings.import_path_options(&library.name))
     }
 
     pub async fn remove_imported_path_method(&self, path: String) -> Result<Vec<String>, String> {
-        Ok(remove_import_path(&path))
+        Ok(self
+            .settings
+            .remove_import_path(&self.library().name, &path))
+    }
+
+    pub async fn set_rating_method(&self, id: i64, rating: Option<i64>) -> Result<(), String> {
+        let library = self.library();
+        if rating.is_some_and(|r| !(1..=5).contains(&r)) {
+            return Err("Rating must be between 1 and 5".to_string());
+        }
+        let before = library
+            .repo
+            .get(id)
+            .await
+            .map_err(|e| format!("Error fetching sound: {}", e))?;
+        library
+            .repo
+            .update_rating(id, rating)
+            .await
+            .map_err(|e| format!("Error updating rating: {}", e))?;
+        self.record_field_changes(&library, format!("Rate '{}'", before.name), vec![before])
+            .await
     }
 
     pub async fn toggle_favorite_method(&self, id: i64) -> Result<String, String> {
-        let mut sound = self
+        let library = self.library();
+        let mut sound = library
             .repo
             .get(id)
             .await
             .map_err(|e| format!("Error fetching sound: {}", e))?;
+        let before = sound.clone();
 
         sound.is_favorite = !sound.is_favorite;
-        self.repo
+        library
+            .repo
             .update(&sound)
             .await
             .map_err(|e| format!("Error updating favorite status: {}", e))?;
+        let label = if sound.is_favorite {
+            format!("Favorite '{}'", sound.name)
+        } else {
+            format!("Unfavorite '{}'", sound.name)
+        };
+        self.record_field_changes(&library, label, vec![before])
+            .await?;
 
         Ok(if sound.is_favorite {
             "Added to favorites"
@@ -81,24 +1194,257 @@ impl Api {
         .to_string())
     }
 
+    pub async fn play_sound_method(&self, id: i64, synced: bool) -> Result<PlaybackStatus, String> {
+        let library = self.library();
+        let sound = library
+            .repo
+            .get(id)
+            .await
+            .map_err(|e| format!("Error fetching sound: {}", e))?;
+        let sound = library.importer.refresh(sound).await?;
+
+        let stretch = if synced {
+            let target = self.settings.get().playback.preview_target;
+            StretchParams::for_target(
+                sound.bpm,
+                target.bpm,
+                key_from_name(&sound.name),
+                target.key.as_deref().and_then(MusicalKey::parse),
+            )
+        } else {
+            None
+        };
+
+        let status = self
+            .player
+            .play(id, &sound.path, stretch)
+            .await
+            .map_err(|e| format!("Error playing sound: {}", e))?;
+
+        let played_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        library
+            .repo
+            .record_play(id, played_at)
+            .await
+            .map_err(|e| format!("Error recording play: {}", e))?;
+
+        Ok(status)
+    }
+
+    pub async fn stop_playback_method(&self) -> Result<PlaybackStatus, String> {
+        Ok(self.player.stop())
+    }
+
+    pub async fn seek_playback_method(&self, position: f64) -> Result<PlaybackStatus, String> {
+        Ok(self.player.seek(position))
+    }
+
+    /// Sets the playback volume and keeps it for the next start.
+    pub async fn set_playback_volume_method(&self, volume: f32) -> Result<PlaybackStatus, String> {
+        let settings = self
+            .settings
+            .update(|settings| settings.playback.volume = volume.clamp(0.0, 1.0))?;
+        Ok(self.player.set_volume(settings.playback.volume))
+    }
+
+    /// Enables or disables looping and keeps it for the next start.
+    pub async fn set_playback_looping_method(
+        &self,
+        looping: bool,
+    ) -> Result<PlaybackStatus, String> {
+        let settings = self
+            .settings
+            .update(|settings| settings.playback.looping = looping)?;
+        Ok(self.player.set_looping(settings.playback.looping))
+    }
+
+    pub async fn get_playback_status_method(&self) -> Result<PlaybackStatus, String> {
+        Ok(self.player.status())
+    }
+
+    pub async fn get_settings_method(&self) -> Result<SettingsState, String> {
+        Ok(self.settings.state())
+    }
+
+    /// Replaces all settings at once.
+    ///
+    /// Cache limits, volume and looping take effect immediately, the other
+    /// settings the next time they are used. Database settings apply to
+    /// libraries opened later.
+    pub async fn update_settings_method(&self, settings: Settings) -> Result<Settings, String> {
+        let library = self.library();
+        let previous = self.settings.get();
+        let settings = self.settings.update(|current| *current = settings)?;
+
+        if settings.cache != previous.cache {
+            library
+                .importer
+                .cache
+                .set_capacity(CacheCapacity::from_megabytes(
+                    settings.cache.memory_mb,
+                    settings.cache.disk_mb,
+                ))
+                .await;
+        }
+        if settings.playback.volume != previous.playback.volume {
+            self.player.set_volume(settings.playback.volume);
+        }
+        if settings.playback.looping != previous.playback.looping {
+            self.player.set_looping(settings.playback.looping);
+        }
+        Ok(settings)
+    }
+
+    pub async fn get_preview_target_method(&self) -> Result<PreviewTarget, String> {
+        Ok(self.settings.get().playback.preview_target)
+    }
+
+    pub async fn set_preview_target_method(
+        &self,
+        target: PreviewTarget,
+    ) -> Result<PreviewTarget, String> {
+        let settings = self
+            .settings
+            .update(|settings| settings.playback.preview_target = target)?;
+        Ok(settings.playback.preview_target)
+    }
+
+    pub async fn get_cache_stats_method(&self) -> Result<CacheStats, String> {
+        Ok(self.library().importer.cache.stats().await)
+    }
+
+    pub async fn set_cache_capacity_method(
+        &self,
+        memory_mb: u64,
+        disk_mb: u64,
+    ) -> Result<CacheStats, String> {
+        let library = self.library();
+        let settings = self
+            .settings
+            .update(|settings| settings.cache = CacheSettings { memory_mb, disk_mb })?;
+        library
+            .importer
+            .cache
+            .set_capacity(CacheCapacity::from_megabytes(
+                settings.cache.memory_mb,
+                settings.cache.disk_mb,
+            ))
+            .await;
+        Ok(library.importer.cache.stats().await)
+    }
+
+    pub async fn clear_cache_method(&self) -> Result<CacheStats, String> {
+        let library = self.library();
+        library.importer.cache.clear().await;
+        Ok(library.importer.cache.stats().await)
+    }
+
+    /// Points the sounds below a missing folder to its new location.
+    ///
+    /// The import path is only replaced if the new folder exists and at
+    /// least one sound was found there, so a mistyped folder never drops
+    /// an import path.
+    pub async fn relink_sounds_method(
+        &self,
+        old_root: String,
+        new_root: String,
+    ) -> Result<Vec<Sound>, String> {
+        let library = self.library();
+        let relinked = library
+            .importer
+            .relink(&old_root, &new_root, None)
+            .await
+            .map_err(|e| format!("Error relinking sounds: {}", e))?;
+        if !relinked.is_empty() && Path::new(&new_root).is_dir() {
+            self.settings
+                .replace_import_path(&library.name, &old_root, new_root);
+        }
+        Ok(relinked)
+    }
+
+    pub async fn library_health_method(&self) -> Result<HealthReport, String> {
+        self.library()
+            .importer
+            .check_health()
+            .await
+            .map_err(|e| format!("Error checking library: {}", e))
+    }
+
+    pub async fn resolve_health_issues_method(
+        &self,
+        ids: Vec<i64>,
+        action: HealthAction,
+    ) -> Result<usize, String> {
+        let library = self.library();
+        match action {
+            HealthAction::Remove => Ok(self
+                .delete_sounds(&library, &ids, false, false)
+                .await?
+                .changed),
+            HealthAction::Relink { old_root, new_root } => Ok(library
+                .importer
+                .relink(&old_root, &new_root, Some(&ids))
+                .await
+                .map_err(|e| format!("Error relinking sounds: {}", e))?
+                .len()),
+            HealthAction::MarkOffline => library.importer.mark_offline(&ids).await,
+        }
+    }
+
+    pub async fn rescan_method(&self, path: Option<String>) -> Result<RescanSummary, String> {
+        let library = self.library();
+        let mut entries = self.settings.import_path_options(&library.name);
+        if let Some(path) = path {
+            entries.retain(|entry| entry.path == path);
+            if entries.is_empty() {
+                return Err(format!("'{}' is not an import path", path));
+            }
+        }
+
+        library
+            .importer
+            .rescan(&entries)
+            .await
+            .map_err(|e| format!("Error rescanning: {}", e))
+    }
+
+    /// Rescans the import paths and moves sounds their rules no longer
+    /// accept to the trash, where they can be restored or the move undone.
     pub async fn recache_sounds_method(&self) -> Result<(), String> {
-        let active_paths = get_import_paths();
-        let sounds = self
+        let library = self.library();
+        let import_paths = self.settings.import_path_options(&library.name);
+        let filters = import_paths
+            .iter()
+            .map(ImportFilter::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        library
+            .importer
+            .rescan(&import_paths)
+            .await
+            .map_err(|e| format!("Error rescanning: {}", e))?;
+
+        let sounds = library
             .repo
             .get_all()
             .await
             .map_err(|e| format!("Error fetching sounds: {}", e))?;
 
-        for sound in sounds {
-            if !active_paths.iter().any(|path| sound.path.starts_with(path)) {
-                if let Some(id) = sound.id {
-                    self.repo
-                        .delete(id)
-                        .await
-                        .map_err(|e| format!("Error deleting sound: {}", e))?;
-                    self.importer.cache.remove_cached_sound(&sound.name).await;
-                }
-            }
+        let excluded: Vec<i64> = sounds
+            .iter()
+            .filter(|sound| sound.status != SoundStatus::Offline)
+            .filter(|sound| {
+                !filters
+                    .iter()
+                    .any(|filter| filter.accepts(Path::new(&sound.path)))
+            })
+            .filter_map(|sound| sound.id)
+            .collect();
+        if !excluded.is_empty() {
+            self.delete_sounds(&library, &excluded, false, false)
+                .await?;
         }
         Ok(())
     }
@@ -115,13 +1461,130 @@ pub async fn import_directory(api: State<'_, Api>, dir_path: String) -> Result<(
 }
 
 #[tauri::command]
-pub async fn get_sounds(api: State<'_, Api>) -> Result<Vec<Sound>, String> {
-    api.get_sounds_method().await
+pub async fn get_sounds(
+    api: State<'_, Api>,
+    query: Option<SoundQuery>,
+    cursor: Option<SoundCursor>,
+    limit: Option<usize>,
+) -> Result<SoundPage, String> {
+    api.get_sounds_method(query.unwrap_or_default(), cursor, limit)
+        .await
+}
+
+#[tauri::command]
+pub async fn search_sounds(api: State<'_, Api>, query: SoundQuery) -> Result<Vec<Sound>, String> {
+    api.search_sounds_method(query).await
+}
+
+#[tauri::command]
+pub async fn analyze_sound(api: State<'_, Api>, id: i64) -> Result<Sound, String> {
+    api.analyze_sound_method(id).await
+}
+
+#[tauri::command]
+pub async fn get_waveform(api: State<'_, Api>, id: i64, points: usize) -> Result<Waveform, String> {
+    api.get_waveform_method(id, points).await
+}
+
+#[tauri::command]
+pub async fn export_trimmed_sound(api: State<'_, Api>, id: i64) -> Result<String, String> {
+    api.export_trimmed_sound_method(id).await
+}
+
+#[tauri::command]
+pub async fn find_similar(
+    api: State<'_, Api>,
+    id: i64,
+    limit: usize,
+) -> Result<Vec<SimilarSound>, String> {
+    api.find_similar_method(id, limit).await
+}
+
+#[tauri::command]
+pub async fn delete_sound(
+    api: State<'_, Api>,
+    id: String,
+    trash_file: Option<bool>,
+    delete_untrashable_file: Option<bool>,
+) -> Result<(), String> {
+    api.delete_sound_method(
+        id,
+        trash_file.unwrap_or(false),
+        delete_untrashable_file.unwrap_or(false),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn restore_sounds(api: State<'_, Api>, ids: Vec<i64>) -> Result<BatchSummary, String> {
+    api.restore_sounds_method(ids).await
+}
+
+#[tauri::command]
+pub async fn purge_deleted_sounds(api: State<'_, Api>, all: bool) -> Result<usize, String> {
+    api.purge_deleted_method(all).await
+}
+
+#[tauri::command]
+pub async fn create_backup(api: State<'_, Api>) -> Result<BackupInfo, String> {
+    api.create_backup_method().await
+}
+
+#[tauri::command]
+pub async fn list_backups(api: State<'_, Api>) -> Result<Vec<BackupInfo>, String> {
+    api.list_backups_method().await
+}
+
+#[tauri::command]
+pub async fn restore_backup(api: State<'_, Api>, name: String) -> Result<(), String> {
+    api.restore_backup_method(name).await
+}
+
+#[tauri::command]
+pub async fn check_integrity(api: State<'_, Api>) -> Result<IntegrityReport, String> {
+    api.check_integrity_method().await
+}
+
+#[tauri::command]
+pub async fn get_trash_retention_days(api: State<'_, Api>) -> Result<u32, String> {
+    api.get_trash_retention_days_method().await
 }
 
 #[tauri::command]
-pub async fn delete_sound(api: State<'_, Api>, id: String) -> Result<(), String> {
-    api.delete_sound_method(id).await
+pub async fn set_trash_retention_days(api: State<'_, Api>, days: u32) -> Result<u32, String> {
+    api.set_trash_retention_days_method(days).await
+}
+
+#[tauri::command]
+pub async fn export_library(
+    api: State<'_, Api>,
+    format: LibraryFormat,
+    path: String,
+) -> Result<usize, String> {
+    api.export_library_method(format, path).await
+}
+
+#[tauri::command]
+pub async fn import_library(
+    api: State<'_, Api>,
+    file: String,
+) -> Result<LibraryImportSummary, String> {
+    api.import_library_method(file).await
+}
+
+#[tauri::command]
+pub async fn list_libraries(api: State<'_, Api>) -> Result<Vec<LibraryInfo>, String> {
+    Ok(api.list_libraries_method())
+}
+
+#[tauri::command]
+pub async fn create_library(api: State<'_, Api>, name: String) -> Result<LibraryInfo, String> {
+    api.create_library_method(name)
+}
+
+#[tauri::command]
+pub async fn switch_library(api: State<'_, Api>, name: String) -> Result<LibraryInfo, String> {
+    api.switch_library_method(name).await
 }
 
 #[tauri::command]
@@ -129,6 +1592,19 @@ pub async fn get_imported_paths(api: State<'_, Api>) -> Result<Vec<String>, Stri
     api.get_imported_paths_method().await
 }
 
+#[tauri::command]
+pub async fn get_import_path_options(api: State<'_, Api>) -> Result<Vec<ImportPath>, String> {
+    api.get_import_path_options_method().await
+}
+
+#[tauri::command]
+pub async fn set_import_path_options(
+    api: State<'_, Api>,
+    options: ImportPath,
+) -> Result<Vec<ImportPath>, String> {
+    api.set_import_path_options_method(options).await
+}
+
 #[tauri::command]
 pub async fn remove_imported_path(
     api: State<'_, Api>,
@@ -142,7 +1618,166 @@ pub async fn toggle_favorite(api: State<'_, Api>, id: i64) -> Result<String, Str
     api.toggle_favorite_method(id).await
 }
 
+#[tauri::command]
+pub async fn edit_sounds(
+    api: State<'_, Api>,
+    ids: Vec<i64>,
+    edit: SoundEdit,
+) -> Result<BatchSummary, String> {
+    api.edit_sounds_method(ids, edit).await
+}
+
+#[tauri::command]
+pub async fn reanalyze_sounds(api: State<'_, Api>, ids: Vec<i64>) -> Result<BatchSummary, String> {
+    api.reanalyze_sounds_method(ids).await
+}
+
+#[tauri::command]
+pub async fn get_collections(api: State<'_, Api>) -> Result<Vec<Collection>, String> {
+    api.get_collections_method().await
+}
+
+#[tauri::command]
+pub async fn create_collection(api: State<'_, Api>, name: String) -> Result<i64, String> {
+    api.create_collection_method(name).await
+}
+
+#[tauri::command]
+pub async fn delete_collection(api: State<'_, Api>, id: i64) -> Result<(), String> {
+    api.delete_collection_method(id).await
+}
+
+#[tauri::command]
+pub async fn undo(api: State<'_, Api>) -> Result<Option<JournalEntry>, String> {
+    api.undo_method().await
+}
+
+#[tauri::command]
+pub async fn redo(api: State<'_, Api>) -> Result<Option<JournalEntry>, String> {
+    api.redo_method().await
+}
+
+#[tauri::command]
+pub async fn get_history(api: State<'_, Api>) -> Result<Vec<JournalEntry>, String> {
+    api.get_history_method().await
+}
+
+#[tauri::command]
+pub async fn set_rating(api: State<'_, Api>, id: i64, rating: Option<i64>) -> Result<(), String> {
+    api.set_rating_method(id, rating).await
+}
+
+#[tauri::command]
+pub async fn get_cache_stats(api: State<'_, Api>) -> Result<CacheStats, String> {
+    api.get_cache_stats_method().await
+}
+
+#[tauri::command]
+pub async fn set_cache_capacity(
+    api: State<'_, Api>,
+    memory_mb: u64,
+    disk_mb: u64,
+) -> Result<CacheStats, String> {
+    api.set_cache_capacity_method(memory_mb, disk_mb).await
+}
+
+#[tauri::command]
+pub async fn clear_cache(api: State<'_, Api>) -> Result<CacheStats, String> {
+    api.clear_cache_method().await
+}
+
+#[tauri::command]
+pub async fn relink_sounds(
+    api: State<'_, Api>,
+    old_root: String,
+    new_root: String,
+) -> Result<Vec<Sound>, String> {
+    api.relink_sounds_method(old_root, new_root).await
+}
+
+#[tauri::command]
+pub async fn library_health(api: State<'_, Api>) -> Result<HealthReport, String> {
+    api.library_health_method().await
+}
+
+#[tauri::command]
+pub async fn resolve_health_issues(
+    api: State<'_, Api>,
+    ids: Vec<i64>,
+    action: HealthAction,
+) -> Result<usize, String> {
+    api.resolve_health_issues_method(ids, action).await
+}
+
+#[tauri::command]
+pub async fn rescan(api: State<'_, Api>, path: Option<String>) -> Result<RescanSummary, String> {
+    api.rescan_method(path).await
+}
+
 #[tauri::command]
 pub async fn recache_sounds(api: State<'_, Api>) -> Result<(), String> {
     api.recache_sounds_method().await
 }
+
+#[tauri::command]
+pub async fn play_sound(
+    api: State<'_, Api>,
+    id: i64,
+    synced: Option<bool>,
+) -> Result<PlaybackStatus, String> {
+    api.play_sound_method(id, synced.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn stop_playback(api: State<'_, Api>) -> Result<PlaybackStatus, String> {
+    api.stop_playback_method().await
+}
+
+#[tauri::command]
+pub async fn seek_playback(api: State<'_, Api>, position: f64) -> Result<PlaybackStatus, String> {
+    api.seek_playback_method(position).await
+}
+
+#[tauri::command]
+pub async fn set_playback_volume(
+    api: State<'_, Api>,
+    volume: f32,
+) -> Result<PlaybackStatus, String> {
+    api.set_playback_volume_method(volume).await
+}
+
+#[tauri::command]
+pub async fn set_playback_looping(
+    api: State<'_, Api>,
+    looping: bool,
+) -> Result<PlaybackStatus, String> {
+    api.set_playback_looping_method(looping).await
+}
+
+#[tauri::command]
+pub async fn get_playback_status(api: State<'_, Api>) -> Result<PlaybackStatus, String> {
+    api.get_playback_status_method().await
+}
+
+#[tauri::command]
+pub async fn get_settings(api: State<'_, Api>) -> Result<SettingsState, String> {
+    api.get_settings_method().await
+}
+
+#[tauri::command]
+pub async fn update_settings(api: State<'_, Api>, settings: Settings) -> Result<Settings, String> {
+    api.update_settings_method(settings).await
+}
+
+#[tauri::command]
+pub async fn get_preview_target(api: State<'_, Api>) -> Result<PreviewTarget, String> {
+    api.get_preview_target_method().await
+}
+
+#[tauri::command]
+pub async fn set_preview_target(
+    api: State<'_, Api>,
+    target: PreviewTarget,
+) -> Result<PreviewTarget, String> {
+    api.set_preview_target_method(target).await
+}
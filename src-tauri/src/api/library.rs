@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::cache::cache_module::{Cache, CacheCapacity};
+use crate::db::backup::BackupManager;
+use crate::db::collection::CollectionRepository;
+use crate::db::connection::DatabasePool;
+use crate::db::fingerprint::FingerprintRepository;
+use crate::db::journal::JournalRepository;
+use crate::db::schema;
+use crate::db::sound::{unix_now, SoundRepository};
+use crate::import::importer::{ImportProgress, Importer};
+use crate::settings::manager::{LibrarySettings, SettingsManager};
+use crate::similarity::index::FingerprintIndex;
+use crate::utils::logger::{log, LogLevel};
+use crate::utils::paths::{backup_dir, cache_dir, database_path, library_dir};
+
+/// Callbacks that forward the events of the active library to the UI.
+#[derive(Clone)]
+pub struct LibraryListeners {
+    /// Called with the progress of imports and rescans.
+    pub progress: Arc<dyn Fn(&ImportProgress) + Send + Sync>,
+    /// Called when an import volume is mounted and the library was rescanned.
+    pub changed: Arc<dyn Fn() + Send + Sync>,
+}
+
+/// An open library with its own database, cache and importer.
+pub struct Library {
+    /// The name of the library.
+    pub name: String,
+    pub repo: Arc<SoundRepository>,
+    pub collections: Arc<CollectionRepository>,
+    pub journal: Arc<JournalRepository>,
+    pub backups: Arc<BackupManager>,
+    pub importer: Arc<Importer>,
+    volume_watch: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Library {
+    /// Opens a library, creating its directory and database if needed.
+    ///
+    /// The schema is migrated and sounds deleted longer ago than the trash
+    /// retention are purged, as on startup.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The application settings.
+    /// * `library` - The settings of the library.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the library, or an error message if the
+    /// database cannot be opened.
+    pub async fn open(
+        settings: &Arc<SettingsManager>,
+        library: &LibrarySettings,
+    ) -> Result<Self, String> {
+        let options = settings.get();
+        let dir = library_dir(&library.dir);
+        fs::create_dir_all(&dir).map_err(|e| format!("Error creating library directory: {}", e))?;
+
+        let db_path = database_path(&dir);
+        let db_pool = DatabasePool::new(
+            db_path.to_str().ok_or("Invalid library path")?,
+            &options.database,
+        )
+        .await
+        .map_err(|e| format!("Error opening library database: {}", e))?;
+
+        let backup_dir = backup_dir(&dir);
+        schema::migrate(&db_pool.get_db(), Some(&backup_dir))
+            .await
+            .map_err(|e| format!("Error migrating library database: {}", e))?;
+
+        let repo = Arc::new(SoundRepository::new(
+            db_pool.get_db(),
+            db_pool.get_read_db(),
+        ));
+        // Failures are logged by the repository; the sounds are purged next time.
+        let _ = repo
+            .purge(unix_now() - i64::from(options.trash.retention_days) * 86_400)
+            .await;
+
+        let cache = Arc::new(Cache::new(
+            cache_dir(&dir),
+            CacheCapacity::from_megabytes(options.cache.memory_mb, options.cache.disk_mb),
+        ));
+        let fingerprints = Arc::new(
+            FingerprintIndex::load(FingerprintRepository::new(
+                db_pool.get_db(),
+                db_pool.get_read_db(),
+            ))
+            .await?,
+        );
+
+        log(
+            LogLevel::Info,
+            "Library::open",
+            &format!("Opened library '{}' at '{}'", library.name, dir.display()),
+        );
+
+        Ok(Self {
+            name: library.name.clone(),
+            importer: Importer::new(
+                repo.clone(),
+                cache,
+                fingerprints,
+                Arc::clone(settings),
+                library.name.clone(),
+            ),
+            collections: Arc::new(CollectionRepository::new(
+                db_pool.get_db(),
+                db_pool.get_read_db(),
+            )),
+            journal: Arc::new(JournalRepository::new(db_pool.get_db())),
+            backups: Arc::new(BackupManager::new(db_pool.get_db(), backup_dir)),
+            repo,
+            volume_watch: Mutex::new(None),
+        })
+    }
+
+    /// Connects the library to the UI and starts watching its import volumes.
+    pub fn attach(&self, listeners: &LibraryListeners) {
+        let progress = Arc::clone(&listeners.progress);
+        self.importer
+            .set_progress_listener(Box::new(move |update| progress(update)));
+        let changed = Arc::clone(&listeners.changed);
+        let watch = self
+            .importer
+            .start_volume_watch(Box::new(move || changed()));
+        if let Some(previous) = self.volume_watch.lock().unwrap().replace(watch) {
+            previous.abort();
+        }
+    }
+
+    /// Stops watching the library's import volumes.
+    ///
+    /// Imports that are still running finish against the library's own
+    /// database.
+    pub fn detach(&self) {
+        if let Some(watch) = self.volume_watch.lock().unwrap().take() {
+            watch.abort();
+        }
+    }
+}
+
+/// A library as listed in the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryInfo {
+    pub name: String,
+    /// The directory holding the library's database, cache and backups.
+    pub path: String,
+    /// Whether the library is the one currently open.
+    pub active: bool,
+    /// The folders imported into the library.
+    pub import_paths: Vec<String>,
+}
+
+impl LibraryInfo {
+    /// Describes a library.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The settings of the library.
+    /// * `active` - The name of the active library.
+    pub fn new(settings: &LibrarySettings, active: &str) -> Self {
+        Self {
+            name: settings.name.clone(),
+            path: library_dir(&settings.dir).to_string_lossy().into_owned(),
+            active: settings.name == active,
+            import_paths: settings
+                .imported_paths
+                .iter()
+                .map(|entry| entry.path.clone())
+                .collect(),
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod handlers;
+pub mod library;
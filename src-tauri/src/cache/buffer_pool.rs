@@ -0,0 +1,146 @@
+use crate::utils::logger::{log, LogLevel};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Decode buffers handed out at once by the default pool.
+pub const DEFAULT_POOL_BUFFERS: usize = 16;
+
+/// A bounded pool of reusable sample buffers for chunked decoding.
+///
+/// Every streaming decode holds one buffer from the pool while it runs.
+/// When all buffers are in use, `acquire` blocks until one is returned,
+/// which caps both the memory spent on decode buffers and the number of
+/// files decoded at the same time.
+pub struct BufferPool {
+    capacity: usize,
+    state: Mutex<PoolState>,
+    returned: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<Vec<f32>>,
+    in_use: usize,
+}
+
+impl BufferPool {
+    /// Creates a new pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum number of buffers in use at once. It must
+    ///   be greater than zero.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the provided capacity is zero.
+    pub fn new(capacity: usize) -> Arc<Self> {
+        assert!(
+            capacity > 0,
+            "Buffer pool capacity must be greater than zero."
+        );
+        log(
+            LogLevel::Info,
+            "BufferPool::new",
+            &format!("Initializing buffer pool with {} buffers", capacity),
+        );
+        Arc::new(Self {
+            capacity,
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                in_use: 0,
+            }),
+            returned: Condvar::new(),
+        })
+    }
+
+    /// Takes an empty buffer from the pool, waiting while all are in use.
+    ///
+    /// The buffer goes back to the pool when the returned guard is dropped.
+    /// This blocks, so it must only be called from blocking threads.
+    pub fn acquire(self: &Arc<Self>) -> PooledBuffer {
+        let mut state = self.state.lock().unwrap();
+        while state.in_use >= self.capacity {
+            state = self.returned.wait(state).unwrap();
+        }
+        state.in_use += 1;
+        let buffer = state.idle.pop().unwrap_or_default();
+
+        PooledBuffer {
+            buffer,
+            pool: Arc::clone(self),
+        }
+    }
+
+    fn release(&self, mut buffer: Vec<f32>) {
+        buffer.clear();
+        let mut state = self.state.lock().unwrap();
+        state.in_use -= 1;
+        state.idle.push(buffer);
+        self.returned.notify_one();
+    }
+}
+
+/// A buffer borrowed from a `BufferPool`.
+pub struct PooledBuffer {
+    buffer: Vec<f32>,
+    pool: Arc<BufferPool>,
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<f32>;
+
+    fn deref(&self) -> &Vec<f32> {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<f32> {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        self.pool.release(std::mem::take(&mut self.buffer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn blocks_at_capacity_until_a_buffer_is_returned() {
+        let pool = BufferPool::new(1);
+        let held = pool.acquire();
+
+        let (tx, rx) = mpsc::channel();
+        let waiting = Arc::clone(&pool);
+        let thread = thread::spawn(move || {
+            let buffer = waiting.acquire();
+            tx.send(buffer.len()).unwrap();
+        });
+
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(held);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(0));
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn returned_buffers_are_cleared_and_reused() {
+        let pool = BufferPool::new(2);
+        let mut buffer = pool.acquire();
+        buffer.extend_from_slice(&[1.0; 1024]);
+        drop(buffer);
+
+        let buffer = pool.acquire();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 1024);
+        assert_eq!(pool.state.lock().unwrap().in_use, 1);
+    }
+}
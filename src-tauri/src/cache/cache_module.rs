@@ -1,89 +1,801 @@
+use crate::analysis::decoder::DecodedAudio;
+use crate::cache::buffer_pool::{BufferPool, DEFAULT_POOL_BUFFERS};
+use crate::cache::content::{hash_file, modified_secs};
 use crate::utils::logger::{log, LogLevel};
 use lru::LruCache;
-use std::num::NonZeroUsize;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::fs;
 use tokio::sync::Mutex;
 
-/// Cache system for storing and retrieving sounds using an LRU cache.
+/// Extension of the entry files in the on-disk tier.
+const ENTRY_EXTENSION: &str = "bin";
+/// Number of hash characters used in content keys.
+const HASH_KEY_LEN: usize = 32;
+
+/// Size limits of the cache tiers, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CacheCapacity {
+    /// Maximum size of the in-memory tier.
+    pub memory_bytes: u64,
+    /// Maximum size of the on-disk tier. Zero disables it.
+    pub disk_bytes: u64,
+}
+
+impl CacheCapacity {
+    /// Creates a capacity from sizes given in megabytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_mb` - The size of the in-memory tier.
+    /// * `disk_mb` - The size of the on-disk tier.
+    pub fn from_megabytes(memory_mb: u64, disk_mb: u64) -> Self {
+        Self {
+            memory_bytes: memory_mb * 1024 * 1024,
+            disk_bytes: disk_mb * 1024 * 1024,
+        }
+    }
+}
+
+/// Hit, miss and size statistics of the cache.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheStats {
+    /// Lookups answered from memory.
+    pub memory_hits: u64,
+    /// Lookups answered from disk.
+    pub disk_hits: u64,
+    /// Lookups that found nothing.
+    pub misses: u64,
+    /// Entries dropped from either tier to stay within capacity.
+    pub evictions: u64,
+    /// Number of entries in memory.
+    pub memory_entries: usize,
+    /// Bytes held in memory.
+    pub memory_bytes: u64,
+    /// Maximum bytes held in memory.
+    pub memory_capacity: u64,
+    /// Number of entries on disk.
+    pub disk_entries: usize,
+    /// Bytes stored on disk.
+    pub disk_bytes: u64,
+    /// Maximum bytes stored on disk.
+    pub disk_capacity: u64,
+}
+
+/// Bookkeeping for an entry file of the on-disk tier.
+struct DiskEntry {
+    size: u64,
+    last_used: u64,
+}
+
+/// The mutable state of both tiers.
+struct CacheState {
+    capacity: CacheCapacity,
+    memory: LruCache<String, Arc<Vec<u8>>>,
+    memory_bytes: u64,
+    disk: HashMap<String, DiskEntry>,
+    disk_bytes: u64,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl CacheState {
+    /// Stores a value in memory and evicts the least recently used entries
+    /// until the tier fits its capacity again.
+    fn insert_memory(&mut self, key: String, value: Arc<Vec<u8>>) {
+        if let Some(previous) = self.memory.pop(&key) {
+            self.memory_bytes -= previous.len() as u64;
+        }
+        if value.len() as u64 > self.capacity.memory_bytes {
+            return;
+        }
+
+        self.memory_bytes += value.len() as u64;
+        self.memory.put(key, value);
+        self.evict_memory();
+    }
+
+    fn evict_memory(&mut self) {
+        while self.memory_bytes > self.capacity.memory_bytes {
+            let Some((_, value)) = self.memory.pop_lru() else {
+                break;
+            };
+            self.memory_bytes -= value.len() as u64;
+            self.stats.evictions += 1;
+        }
+    }
+
+    /// Drops the least recently used disk entries until the tier fits its
+    /// capacity, returning the keys whose files must be deleted.
+    fn evict_disk(&mut self) -> Vec<String> {
+        let mut entries: Vec<(u64, String)> = self
+            .disk
+            .iter()
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+        entries.sort_unstable();
+
+        let mut victims = Vec::new();
+        for (_, key) in entries {
+            if self.disk_bytes <= self.capacity.disk_bytes {
+                break;
+            }
+            if let Some(entry) = self.disk.remove(&key) {
+                self.disk_bytes -= entry.size;
+                self.stats.evictions += 1;
+                victims.push(key);
+            }
+        }
+        victims
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+/// The content hash of a file, remembered until the file changes.
+struct ContentHash {
+    size: u64,
+    modified: i64,
+    hash: String,
+}
+
+/// Two-tier cache for expensive data derived from audio files.
 ///
-/// This struct utilizes an LRU (Least Recently Used) cache to store sound
-/// data with their corresponding names. The cache has a fixed size limit and
-/// evicts the least recently used entries when the limit is exceeded.
+/// Entries are opaque bytes stored under keys that start with the content
+/// key of their source file, so an edited file never returns stale data
+/// and a moved or renamed file keeps its entries. Recently used entries
+/// are kept in memory, and persistent entries are also written to disk so
+/// that they survive restarts. Both tiers evict their least recently used
+/// entries to stay within a byte capacity.
+///
+/// The cache also owns the buffer pool shared by all streaming decodes.
 pub struct Cache {
-    sound_cache: Arc<Mutex<LruCache<String, String>>>,
+    dir: PathBuf,
+    state: Mutex<CacheState>,
+    hashes: Mutex<HashMap<String, ContentHash>>,
+    buffers: Arc<BufferPool>,
 }
 
 impl Cache {
-    /// Creates a new cache instance with a given size.
+    /// Creates a cache and loads the index of its on-disk tier.
     ///
     /// # Arguments
     ///
-    /// * `size` - The maximum size of the cache. It must be greater than zero.
+    /// * `dir` - The directory holding the on-disk tier. It is created if missing.
+    /// * `capacity` - The size limits of both tiers.
     ///
     /// # Returns
     ///
     /// A new `Cache` instance.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if the provided size is zero or less.
-    pub fn new(size: usize) -> Self {
-        let size = NonZeroUsize::new(size).expect("Cache size must be greater than zero.");
+    pub fn new(dir: PathBuf, capacity: CacheCapacity) -> Self {
         log(
             LogLevel::Info,
             "Cache::new",
-            &format!("Initializing cache with size {}", size),
+            &format!(
+                "Initializing cache in '{}' with {} bytes in memory and {} bytes on disk",
+                dir.display(),
+                capacity.memory_bytes,
+                capacity.disk_bytes
+            ),
         );
+
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log(
+                LogLevel::Error,
+                "Cache::new",
+                &format!("Failed to create cache directory: {}", e),
+            );
+        }
+
+        let mut files: Vec<(i64, String, u64)> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some(ENTRY_EXTENSION) {
+                    return None;
+                }
+                let key = path.file_stem()?.to_str()?.to_string();
+                let metadata = entry.metadata().ok()?;
+                Some((modified_secs(&metadata), key, metadata.len()))
+            })
+            .collect();
+        files.sort_unstable();
+
+        let mut state = CacheState {
+            capacity,
+            memory: LruCache::unbounded(),
+            memory_bytes: 0,
+            disk: HashMap::new(),
+            disk_bytes: 0,
+            clock: 0,
+            stats: CacheStats::default(),
+        };
+        for (_, key, size) in files {
+            let last_used = state.tick();
+            state.disk_bytes += size;
+            state.disk.insert(key, DiskEntry { size, last_used });
+        }
+        for key in state.evict_disk() {
+            let _ = std::fs::remove_file(entry_path(&dir, &key));
+        }
+
         Self {
-            sound_cache: Arc::new(Mutex::new(LruCache::new(size))),
+            dir,
+            state: Mutex::new(state),
+            hashes: Mutex::new(HashMap::new()),
+            buffers: BufferPool::new(DEFAULT_POOL_BUFFERS),
+        }
+    }
+
+    /// Returns the pool of decode buffers shared by streaming decodes.
+    pub fn buffers(&self) -> Arc<BufferPool> {
+        Arc::clone(&self.buffers)
+    }
+
+    /// Returns the content key of a file, made of its content hash and
+    /// modification time.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the key, or an error message if the file cannot be read.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn content_key(&self, path: &str) -> Result<String, String> {
+        let (hash, modified) = self.hash_with_modified(path).await?;
+        Ok(format!("{}-{}", &hash[..HASH_KEY_LEN], modified))
+    }
+
+    /// Returns the BLAKE3 hash of the contents of a file.
+    ///
+    /// The hash is only recomputed when the size or modification time of
+    /// the file changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the hash as hex, or an error message if the file cannot be read.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn content_hash(&self, path: &str) -> Result<String, String> {
+        Ok(self.hash_with_modified(path).await?.0)
+    }
+
+    async fn hash_with_modified(&self, path: &str) -> Result<(String, i64), String> {
+        let metadata = fs::metadata(path)
+            .await
+            .map_err(|e| format!("Error reading '{}': {}", path, e))?;
+        let (size, modified) = (metadata.len(), modified_secs(&metadata));
+
+        if let Some(known) = self.hashes.lock().await.get(path) {
+            if known.size == size && known.modified == modified {
+                return Ok((known.hash.clone(), modified));
+            }
         }
+
+        let hash_path = path.to_string();
+        let hash = tokio::task::spawn_blocking(move || hash_file(&hash_path))
+            .await
+            .map_err(|e| format!("Hash task failed: {}", e))?
+            .map_err(|e| format!("Error hashing '{}': {}", path, e))?;
+
+        self.hashes.lock().await.insert(
+            path.to_string(),
+            ContentHash {
+                size,
+                modified,
+                hash: hash.clone(),
+            },
+        );
+        Ok((hash, modified))
     }
 
-    /// Caches a sound with its name and path, measuring execution time.
+    /// Removes every entry derived from a version of a file.
     ///
     /// # Arguments
     ///
-    /// * `name` - The name of the sound.
-    /// * `path` - The path to the sound file.
+    /// * `hash` - The content hash of the outdated version, as returned by `content_hash`.
     ///
     /// # Asynchronous
     ///
     /// This function is asynchronous and should be awaited.
-    pub async fn cache_sound(&self, name: String, path: String) {
-        let mut cache = self.sound_cache.lock().await;
-        cache.put(name.clone(), path);
+    pub async fn invalidate(&self, hash: &str) {
+        let prefix = &hash[..hash.len().min(HASH_KEY_LEN)];
+        let victims: Vec<String> = {
+            let mut state = self.state.lock().await;
+            let stale: Vec<String> = state
+                .memory
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect();
+            for key in stale {
+                if let Some(value) = state.memory.pop(&key) {
+                    state.memory_bytes -= value.len() as u64;
+                }
+            }
+
+            let stale: Vec<String> = state
+                .disk
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect();
+            for key in &stale {
+                if let Some(entry) = state.disk.remove(key) {
+                    state.disk_bytes -= entry.size;
+                }
+            }
+            stale
+        };
+
+        if !victims.is_empty() {
+            log(
+                LogLevel::Info,
+                "Cache::invalidate",
+                &format!("Dropped {} entries of outdated content", victims.len()),
+            );
+        }
+        self.remove_files(victims).await;
     }
 
-    /// Retrieves a cached sound by its name, measuring execution time.
+    /// Looks up an entry in memory, then on disk.
+    ///
+    /// Entries found on disk are promoted to memory.
     ///
     /// # Arguments
     ///
-    /// * `name` - The name of the sound.
+    /// * `key` - The key of the entry.
     ///
     /// # Returns
     ///
-    /// `Some(String)` if the sound is found in the cache, or `None` if it is not found.
+    /// `Some` with the stored bytes, or `None` if the entry is not cached.
     ///
     /// # Asynchronous
     ///
     /// This function is asynchronous and should be awaited.
-    pub async fn get_cached_sound(&self, name: &str) -> Option<String> {
-        let mut cache = self.sound_cache.lock().await;
-        let result = cache.get(name).cloned();
-        result
+    pub async fn get(&self, key: &str) -> Option<Arc<Vec<u8>>> {
+        {
+            let mut state = self.state.lock().await;
+            if let Some(value) = state.memory.get(key).cloned() {
+                state.stats.memory_hits += 1;
+                return Some(value);
+            }
+            if !state.disk.contains_key(key) {
+                state.stats.misses += 1;
+                return None;
+            }
+        }
+
+        let read = fs::read(entry_path(&self.dir, key)).await;
+
+        let mut state = self.state.lock().await;
+        match read {
+            Ok(bytes) => {
+                let tick = state.tick();
+                if let Some(entry) = state.disk.get_mut(key) {
+                    entry.last_used = tick;
+                }
+                let value = Arc::new(bytes);
+                state.insert_memory(key.to_string(), value.clone());
+                state.stats.disk_hits += 1;
+                Some(value)
+            }
+            Err(e) => {
+                log(
+                    LogLevel::Warn,
+                    "Cache::get",
+                    &format!("Dropping unreadable cache entry '{}': {}", key, e),
+                );
+                if let Some(entry) = state.disk.remove(key) {
+                    state.disk_bytes -= entry.size;
+                }
+                state.stats.misses += 1;
+                None
+            }
+        }
     }
 
-    /// Removes a cached sound by its name, measuring execution time.
+    /// Stores an entry, replacing any previous value.
     ///
     /// # Arguments
     ///
-    /// * `name` - The name of the sound to be removed.
+    /// * `key` - The key of the entry.
+    /// * `value` - The bytes to store.
+    /// * `persist` - Whether the entry is also written to the on-disk tier.
     ///
     /// # Asynchronous
     ///
     /// This function is asynchronous and should be awaited.
-    pub async fn remove_cached_sound(&self, name: &str) {
-        let mut cache = self.sound_cache.lock().await;
-        cache.pop(name);
+    pub async fn put(&self, key: String, value: Vec<u8>, persist: bool) {
+        let value = Arc::new(value);
+        let write_to_disk = {
+            let mut state = self.state.lock().await;
+            state.insert_memory(key.clone(), value.clone());
+            persist && value.len() as u64 <= state.capacity.disk_bytes
+        };
+        if !write_to_disk {
+            return;
+        }
+
+        let path = entry_path(&self.dir, &key);
+        let temp = path.with_extension("tmp");
+        let written = match fs::write(&temp, value.as_slice()).await {
+            Ok(()) => fs::rename(&temp, &path).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            log(
+                LogLevel::Error,
+                "Cache::put",
+                &format!("Failed to write cache entry '{}': {}", key, e),
+            );
+            let _ = fs::remove_file(&temp).await;
+            return;
+        }
+
+        let victims = {
+            let mut state = self.state.lock().await;
+            let last_used = state.tick();
+            let size = value.len() as u64;
+            if let Some(previous) = state.disk.insert(key, DiskEntry { size, last_used }) {
+                state.disk_bytes -= previous.size;
+            }
+            state.disk_bytes += size;
+            state.evict_disk()
+        };
+        self.remove_files(victims).await;
+    }
+
+    /// Looks up an entry stored with `put_json`.
+    ///
+    /// Entries that no longer deserialize into `T` count as missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the entry.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let bytes = self.get(key).await?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Stores a value serialized as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the entry.
+    /// * `value` - The value to store.
+    /// * `persist` - Whether the entry is also written to the on-disk tier.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn put_json<T: Serialize>(&self, key: String, value: &T, persist: bool) {
+        match serde_json::to_vec(value) {
+            Ok(bytes) => self.put(key, bytes, persist).await,
+            Err(e) => log(
+                LogLevel::Error,
+                "Cache::put_json",
+                &format!("Failed to serialize cache entry '{}': {}", key, e),
+            ),
+        }
+    }
+
+    /// Looks up decoded audio stored with `put_audio`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the entry.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn get_audio(&self, key: &str) -> Option<DecodedAudio> {
+        let bytes = self.get(key).await?;
+        if bytes.len() < 8 || bytes.len() % 4 != 0 {
+            return None;
+        }
+
+        let header = |index: usize| {
+            u32::from_le_bytes(bytes[index..index + 4].try_into().unwrap_or_default())
+        };
+        Some(DecodedAudio {
+            sample_rate: header(0),
+            channels: header(4) as usize,
+            samples: bytes[8..]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        })
+    }
+
+    /// Stores decoded audio as raw little-endian samples.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the entry.
+    /// * `audio` - The audio to store.
+    /// * `persist` - Whether the entry is also written to the on-disk tier.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn put_audio(&self, key: String, audio: &DecodedAudio, persist: bool) {
+        let mut bytes = Vec::with_capacity(8 + audio.samples.len() * 4);
+        bytes.extend_from_slice(&audio.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(audio.channels as u32).to_le_bytes());
+        for sample in &audio.samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        self.put(key, bytes, persist).await;
+    }
+
+    /// Returns hit, miss and size statistics.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn stats(&self) -> CacheStats {
+        let state = self.state.lock().await;
+        CacheStats {
+            memory_entries: state.memory.len(),
+            memory_bytes: state.memory_bytes,
+            memory_capacity: state.capacity.memory_bytes,
+            disk_entries: state.disk.len(),
+            disk_bytes: state.disk_bytes,
+            disk_capacity: state.capacity.disk_bytes,
+            ..state.stats.clone()
+        }
+    }
+
+    /// Changes the size limits, evicting entries that no longer fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The new size limits.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn set_capacity(&self, capacity: CacheCapacity) {
+        let victims = {
+            let mut state = self.state.lock().await;
+            state.capacity = capacity;
+            state.evict_memory();
+            state.evict_disk()
+        };
+        self.remove_files(victims).await;
+    }
+
+    /// Removes every entry from both tiers.
+    ///
+    /// # Asynchronous
+    ///
+    /// This function is asynchronous and should be awaited.
+    pub async fn clear(&self) {
+        let victims: Vec<String> = {
+            let mut state = self.state.lock().await;
+            state.memory.clear();
+            state.memory_bytes = 0;
+            state.disk_bytes = 0;
+            state.disk.drain().map(|(key, _)| key).collect()
+        };
+        self.remove_files(victims).await;
+    }
+
+    async fn remove_files(&self, keys: Vec<String>) {
+        for key in keys {
+            if let Err(e) = fs::remove_file(entry_path(&self.dir, &key)).await {
+                log(
+                    LogLevel::Warn,
+                    "Cache::remove_files",
+                    &format!("Failed to delete cache entry '{}': {}", key, e),
+                );
+            }
+        }
+    }
+}
+
+/// Returns the file of an on-disk entry.
+///
+/// Characters other than ASCII letters, digits, `-` and `_` are replaced so
+/// that every key maps to a plain file name.
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}.{}", name, ENTRY_EXTENSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capacity(memory_bytes: u64, disk_bytes: u64) -> CacheCapacity {
+        CacheCapacity {
+            memory_bytes,
+            disk_bytes,
+        }
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(100, 0));
+
+        cache.put("a".into(), vec![0; 40], false).await;
+        cache.put("b".into(), vec![1; 40], false).await;
+        assert!(cache.get("a").await.is_some());
+        cache.put("c".into(), vec![2; 40], false).await;
+
+        assert!(cache.get("b").await.is_none());
+        assert!(cache.get("a").await.is_some());
+        assert!(cache.get("c").await.is_some());
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.memory_entries, 2);
+        assert_eq!(stats.memory_bytes, 80);
+
+        // Values larger than the whole tier are never held.
+        cache.put("big".into(), vec![0; 101], false).await;
+        assert!(cache.get("big").await.is_none());
+        assert_eq!(cache.stats().await.memory_bytes, 80);
+    }
+
+    #[tokio::test]
+    async fn counts_hits_and_misses() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 1000));
+
+        assert!(cache.get("missing").await.is_none());
+        cache.put("kept".into(), vec![7; 10], true).await;
+        assert_eq!(cache.get("kept").await.unwrap().as_slice(), &[7; 10]);
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.memory_hits, 1);
+        assert_eq!(stats.disk_hits, 0);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.disk_entries, 1);
+        assert_eq!(stats.disk_bytes, 10);
+    }
+
+    #[tokio::test]
+    async fn reloads_the_disk_tier() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 1000));
+            cache.put("persisted".into(), vec![1; 30], true).await;
+            cache.put("memory-only".into(), vec![2; 30], false).await;
+        }
+
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 1000));
+        let stats = cache.stats().await;
+        assert_eq!(stats.disk_entries, 1);
+        assert_eq!(stats.disk_bytes, 30);
+        assert_eq!(stats.memory_entries, 0);
+
+        assert_eq!(cache.get("persisted").await.unwrap().as_slice(), &[1; 30]);
+        assert!(cache.get("memory-only").await.is_none());
+        let stats = cache.stats().await;
+        assert_eq!(stats.disk_hits, 1);
+        assert_eq!(stats.memory_entries, 1);
+
+        // Reopening with a smaller disk tier drops what no longer fits.
+        drop(cache);
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 10));
+        assert_eq!(cache.stats().await.disk_entries, 0);
+        assert!(!entry_path(dir.path(), "persisted").exists());
+    }
+
+    #[tokio::test]
+    async fn clear_removes_entry_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 1000));
+        cache.put("a/b".into(), vec![1; 5], true).await;
+        assert!(entry_path(dir.path(), "a/b").exists());
+
+        cache.clear().await;
+        assert!(!entry_path(dir.path(), "a/b").exists());
+        assert!(cache.get("a/b").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn invalidates_entries_by_hash_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 1000));
+        let stale = "a".repeat(64);
+        let fresh = "b".repeat(64);
+
+        let stale_key = format!("{}-100-mfcc", &stale[..HASH_KEY_LEN]);
+        let fresh_key = format!("{}-200-mfcc", &fresh[..HASH_KEY_LEN]);
+        cache.put(stale_key.clone(), vec![1; 10], true).await;
+        cache
+            .put(
+                format!("{}-100", &stale[..HASH_KEY_LEN]),
+                vec![2; 10],
+                false,
+            )
+            .await;
+        cache.put(fresh_key.clone(), vec![3; 10], true).await;
+
+        cache.invalidate(&stale).await;
+
+        assert!(!entry_path(dir.path(), &stale_key).exists());
+        assert!(entry_path(dir.path(), &fresh_key).exists());
+        let stats = cache.stats().await;
+        assert_eq!(stats.memory_entries, 1);
+        assert_eq!(stats.memory_bytes, 10);
+        assert_eq!(stats.disk_entries, 1);
+        assert_eq!(stats.disk_bytes, 10);
+        assert!(cache.get(&stale_key).await.is_none());
+        assert!(cache.get(&fresh_key).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn round_trips_decoded_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf(), capacity(1000, 0));
+        let audio = DecodedAudio {
+            samples: vec![0.25, -0.5, 1.0, 0.0],
+            channels: 2,
+            sample_rate: 44100,
+        };
+        cache.put_audio("audio".into(), &audio, false).await;
+
+        let cached = cache.get_audio("audio").await.unwrap();
+        assert_eq!(cached.samples, audio.samples);
+        assert_eq!(cached.channels, 2);
+        assert_eq!(cached.sample_rate, 44100);
+    }
+
+    #[tokio::test]
+    async fn content_keys_follow_the_file_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().join("cache"), capacity(1000, 0));
+        let first = dir.path().join("first.wav");
+        let second = dir.path().join("second.wav");
+        std::fs::write(&first, b"same bytes").unwrap();
+        std::fs::write(&second, b"same bytes").unwrap();
+
+        let key = cache.content_key(first.to_str().unwrap()).await.unwrap();
+        let hash = hash_file(first.to_str().unwrap()).unwrap();
+        assert!(key.starts_with(&hash[..HASH_KEY_LEN]));
+        let other = cache.content_key(second.to_str().unwrap()).await.unwrap();
+        assert_eq!(key[..HASH_KEY_LEN], other[..HASH_KEY_LEN]);
+
+        std::fs::write(&first, b"other bytes, other size").unwrap();
+        let changed = cache.content_key(first.to_str().unwrap()).await.unwrap();
+        assert_ne!(changed[..HASH_KEY_LEN], key[..HASH_KEY_LEN]);
     }
 }
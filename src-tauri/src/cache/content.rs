@@ -0,0 +1,31 @@
+use std::fs::{File, Metadata};
+use std::io;
+use std::time::UNIX_EPOCH;
+
+/// Hashes the full contents of a file with BLAKE3.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file.
+///
+/// # Returns
+///
+/// A `Result` containing the hash as lowercase hex, or an I/O error.
+pub fn hash_file(path: &str) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Returns the modification time of a file in whole seconds since the Unix epoch.
+///
+/// # Arguments
+///
+/// * `metadata` - The metadata of the file.
+pub fn modified_secs(metadata: &Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
@@ -1 +1,3 @@
+pub mod buffer_pool;
 pub mod cache_module;
+pub mod content;
@@ -0,0 +1,424 @@
+use crate::db::schema;
+use crate::utils::logger::{log, LogLevel};
+use serde::Serialize;
+use sqlx::{Connection, Error, SqlitePool};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+/// Number of backups kept for each reason. Older backups made for the same
+/// reason are deleted when a new one is made, so frequent manual backups
+/// never push out the ones taken before a migration or restore.
+pub const MAX_BACKUPS: usize = 10;
+
+/// A copy of the database in the backup directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    /// The file name, which identifies the backup.
+    pub name: String,
+    /// The full path of the file.
+    pub path: String,
+    /// Why the backup was made, such as `manual` or `pre-restore`.
+    pub reason: String,
+    /// When the backup was made, as a Unix timestamp in seconds.
+    pub created_at: i64,
+    /// Size of the file, in bytes.
+    pub size: u64,
+}
+
+/// The result of `PRAGMA integrity_check`.
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    /// Whether SQLite found no problems.
+    pub ok: bool,
+    /// The problems found, or `["ok"]`.
+    pub messages: Vec<String>,
+}
+
+/// Creates, lists and restores backups of a database.
+pub struct BackupManager {
+    pool: Arc<SqlitePool>,
+    dir: PathBuf,
+}
+
+impl BackupManager {
+    /// Creates a new `BackupManager` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - The connection pool of the database to back up.
+    /// * `dir` - The directory backups are stored in.
+    pub fn new(pool: Arc<SqlitePool>, dir: PathBuf) -> Self {
+        Self { pool, dir }
+    }
+
+    /// Writes a consistent copy of the database while it stays in use.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - A short word describing why the backup was made,
+    ///   included in the file name.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new backup.
+    pub async fn create(&self, reason: &str) -> Result<BackupInfo, Error> {
+        create_backup(&self.pool, &self.dir, reason).await
+    }
+
+    /// Lists the backups, newest first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the backups.
+    pub fn list(&self) -> Result<Vec<BackupInfo>, Error> {
+        list_backups(&self.dir)
+    }
+
+    /// Checks the database for corruption.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the findings.
+    pub async fn integrity_check(&self) -> Result<IntegrityReport, Error> {
+        integrity_check(&self.pool).await
+    }
+
+    /// Replaces the contents of the database with a backup.
+    ///
+    /// The backup is checked for corruption and brought up to the current
+    /// schema in a scratch copy first, and the current contents are backed
+    /// up before they are replaced. The replacement happens in a single
+    /// transaction on the open database, so other connections see either
+    /// the old or the restored contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The file name of the backup.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn restore(&self, name: &str) -> Result<(), Error> {
+        let backup = self
+            .list()?
+            .into_iter()
+            .find(|backup| backup.name == name)
+            .ok_or_else(|| Error::Protocol(format!("Backup '{}' does not exist", name)))?;
+
+        let scratch = self.dir.join(format!("{}.restoring", name));
+        fs::copy(&backup.path, &scratch)?;
+        let result = self.restore_from(&scratch).await;
+        let _ = fs::remove_file(&scratch);
+
+        match result {
+            Ok(()) => {
+                log(
+                    LogLevel::Info,
+                    "BackupManager::restore",
+                    &format!("Restored database from backup '{}'.", name),
+                );
+                Ok(())
+            }
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "BackupManager::restore",
+                    &format!("Failed to restore backup '{}': {:?}", name, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    async fn restore_from(&self, scratch: &Path) -> Result<(), Error> {
+        {
+            let source = SqlitePool::connect(&format!("sqlite://{}", scratch.display())).await?;
+            let report = integrity_check(&source).await?;
+            if !report.ok {
+                source.close().await;
+                return Err(Error::Protocol(format!(
+                    "The backup is damaged: {}",
+                    report.messages.join("; ")
+                )));
+            }
+            let migrated = schema::migrate(&source, None).await;
+            source.close().await;
+            migrated?;
+        }
+
+        self.create("pre-restore").await?;
+
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE ? AS backup")
+            .bind(scratch.to_string_lossy().into_owned())
+            .execute(&mut *conn)
+            .await?;
+
+        let copied = async {
+            let tables: Vec<String> = sqlx::query_scalar(
+                "SELECT name FROM main.sqlite_master WHERE type = 'table'
+                 AND name IN (SELECT name FROM backup.sqlite_master WHERE type = 'table')",
+            )
+            .fetch_all(&mut *conn)
+            .await?;
+
+            let mut tx = conn.begin().await?;
+            sqlx::query("PRAGMA defer_foreign_keys = ON")
+                .execute(&mut *tx)
+                .await?;
+            for table in &tables {
+                sqlx::query(&format!("DELETE FROM main.\"{}\"", table))
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query(&format!(
+                    "INSERT INTO main.\"{0}\" SELECT * FROM backup.\"{0}\"",
+                    table
+                ))
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await
+        }
+        .await;
+
+        sqlx::query("DETACH DATABASE backup")
+            .execute(&mut *conn)
+            .await?;
+        copied
+    }
+}
+
+/// Writes a consistent copy of a database with `VACUUM INTO` and deletes
+/// the oldest backups made for the same reason beyond `MAX_BACKUPS`.
+///
+/// # Arguments
+///
+/// * `pool` - The connection pool of the database to back up.
+/// * `dir` - The directory backups are stored in.
+/// * `reason` - A short word describing why the backup was made.
+///
+/// # Returns
+///
+/// A `Result` containing the new backup.
+pub async fn create_backup(
+    pool: &SqlitePool,
+    dir: &Path,
+    reason: &str,
+) -> Result<BackupInfo, Error> {
+    let result = async {
+        fs::create_dir_all(dir)?;
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let mut path = dir.join(format!("database-{}-{}.db", stamp, reason));
+        let mut attempt = 1;
+        while path.exists() {
+            attempt += 1;
+            path = dir.join(format!("database-{}-{}-{}.db", stamp, reason, attempt));
+        }
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(pool)
+            .await?;
+
+        let backup = backup_info(&path)?;
+        for old in list_backups(dir)?
+            .into_iter()
+            .filter(|old| old.path != backup.path && old.reason == backup.reason)
+            .skip(MAX_BACKUPS - 1)
+        {
+            fs::remove_file(&old.path)?;
+        }
+        Ok(backup)
+    }
+    .await;
+
+    match result {
+        Ok(backup) => {
+            log(
+                LogLevel::Info,
+                "backup::create_backup",
+                &format!("Backed up database to '{}'.", backup.path),
+            );
+            Ok(backup)
+        }
+        Err(err) => {
+            log(
+                LogLevel::Error,
+                "backup::create_backup",
+                &format!("Failed to back up database: {:?}", err),
+            );
+            Err(err)
+        }
+    }
+}
+
+/// Runs `PRAGMA integrity_check` on a database.
+async fn integrity_check(pool: &SqlitePool) -> Result<IntegrityReport, Error> {
+    let messages: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await?;
+    Ok(IntegrityReport {
+        ok: messages.len() == 1 && messages[0] == "ok",
+        messages,
+    })
+}
+
+/// Lists the backups in a directory, newest first.
+fn list_backups(dir: &Path) -> Result<Vec<BackupInfo>, Error> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut backups = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("database-") && name.ends_with(".db"));
+        if is_backup {
+            let modified = fs::metadata(&path)?.modified()?;
+            backups.push((modified, backup_info(&path)?));
+        }
+    }
+    // Sort on the precise modification time, as backups made within the same
+    // second share `created_at`.
+    backups.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+/// Extracts the reason from a backup file name, written like
+/// `database-20240101-120000-manual.db` or `database-20240101-120000-manual-2.db`
+/// when several backups were made within a second.
+fn backup_reason(name: &str) -> String {
+    let stem = name
+        .strip_prefix("database-")
+        .and_then(|rest| rest.strip_suffix(".db"))
+        .unwrap_or(name);
+    let reason = stem.splitn(3, '-').nth(2).unwrap_or_default();
+    match reason.rsplit_once('-') {
+        Some((base, attempt)) if attempt.parse::<u32>().is_ok() => base.to_string(),
+        _ => reason.to_string(),
+    }
+}
+
+fn backup_info(path: &Path) -> Result<BackupInfo, Error> {
+    let metadata = fs::metadata(path)?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(BackupInfo {
+        reason: backup_reason(&name),
+        name,
+        path: path.to_string_lossy().into_owned(),
+        created_at: metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default(),
+        size: metadata.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::migrate;
+    use crate::db::sound::{Sound, SoundRepository};
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+    /// Opens a new database file in `dir` with the current schema.
+    async fn pool(dir: &Path) -> Arc<SqlitePool> {
+        let options = SqliteConnectOptions::new()
+            .filename(dir.join("library.db"))
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        migrate(&pool, None).await.unwrap();
+        Arc::new(pool)
+    }
+
+    #[test]
+    fn reads_the_reason_from_file_names() {
+        assert_eq!(
+            backup_reason("database-20240101-120000-manual.db"),
+            "manual"
+        );
+        assert_eq!(
+            backup_reason("database-20240101-120000-manual-2.db"),
+            "manual"
+        );
+        assert_eq!(
+            backup_reason("database-20240101-120000-pre-migration-v3.db"),
+            "pre-migration-v3"
+        );
+        assert_eq!(
+            backup_reason("database-20240101-120000-pre-restore-12.db"),
+            "pre-restore"
+        );
+    }
+
+    #[tokio::test]
+    async fn rotates_backups_separately_for_each_reason() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = BackupManager::new(pool(dir.path()).await, dir.path().join("backups"));
+
+        let kept = backups.create("pre-migration-v1").await.unwrap();
+        let mut manual = vec![];
+        for _ in 0..MAX_BACKUPS + 2 {
+            manual.push(backups.create("manual").await.unwrap().name);
+        }
+
+        let listed = backups.list().unwrap();
+        let listed_manual: Vec<&str> = listed
+            .iter()
+            .filter(|backup| backup.reason == "manual")
+            .map(|backup| backup.name.as_str())
+            .collect();
+        assert_eq!(listed_manual.len(), MAX_BACKUPS);
+        assert_eq!(listed_manual[0], manual.last().unwrap());
+        assert!(listed.iter().any(|backup| backup.name == kept.name));
+    }
+
+    #[tokio::test]
+    async fn restores_the_contents_of_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = pool(dir.path()).await;
+        let repo = SoundRepository::new(Arc::clone(&pool), Arc::clone(&pool));
+        let backups = BackupManager::new(pool, dir.path().join("backups"));
+
+        repo.insert(Sound::new("kick", "/s/kick.wav"))
+            .await
+            .unwrap();
+        let backup = backups.create("manual").await.unwrap();
+        repo.insert(Sound::new("snare", "/s/snare.wav"))
+            .await
+            .unwrap();
+
+        backups.restore(&backup.name).await.unwrap();
+        let names: Vec<String> = repo
+            .get_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|sound| sound.name)
+            .collect();
+        assert_eq!(names, vec!["kick"]);
+
+        let listed = backups.list().unwrap();
+        assert!(listed.iter().any(|backup| backup.reason == "pre-restore"));
+        assert!(!Path::new(&backup.path)
+            .with_file_name(format!("{}.restoring", backup.name))
+            .exists());
+        assert!(backups.restore("database-missing.db").await.is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::utils::logger::{log, LogLevel};
+use serde::{Deserialize, Serialize};
+use sqlx::{Error, FromRow, SqlitePool};
+use std::sync::Arc;
+
+/// A named group of sounds.
+///
+/// Each sound belongs to at most one collection, referenced by its
+/// `collection_id`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Collection {
+    /// The unique identifier of the collection.
+    pub id: i64,
+    /// The name of the collection, unique across the library.
+    pub name: String,
+    /// Number of sounds in the collection.
+    pub sound_count: i64,
+}
+
+/// Provides an interface for database operations on the `collections` table.
+pub struct CollectionRepository {
+    pool: Arc<SqlitePool>,
+    read: Arc<SqlitePool>,
+}
+
+impl CollectionRepository {
+    /// Creates a new `CollectionRepository` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - A reference-counted SQLite connection pool.
+    /// * `read` - The pool used for queries, which may refuse writes.
+    pub fn new(pool: Arc<SqlitePool>, read: Arc<SqlitePool>) -> Self {
+        Self { pool, read }
+    }
+
+    /// Retrieves every collection, ordered by name.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the collections with their sound counts.
+    pub async fn get_all(&self) -> Result<Vec<Collection>, Error> {
+        let result = sqlx::query_as::<_, Collection>(
+            "SELECT c.id, c.name, COUNT(s.id) AS sound_count
+             FROM collections c LEFT JOIN sounds s ON s.collection_id = c.id
+             GROUP BY c.id ORDER BY c.name",
+        )
+        .fetch_all(&*self.read)
+        .await;
+
+        match result {
+            Ok(collections) => Ok(collections),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "CollectionRepository::get_all",
+                    &format!("Failed to fetch collections: {:?}", err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Retrieves a collection by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the collection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the collection, or an error if it does not exist.
+    pub async fn get(&self, id: i64) -> Result<Collection, Error> {
+        let result = sqlx::query_as::<_, Collection>(
+            "SELECT c.id, c.name, COUNT(s.id) AS sound_count
+             FROM collections c LEFT JOIN sounds s ON s.collection_id = c.id
+             WHERE c.id = ? GROUP BY c.id",
+        )
+        .bind(id)
+        .fetch_one(&*self.read)
+        .await;
+
+        match result {
+            Ok(collection) => Ok(collection),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "CollectionRepository::get",
+                    &format!("Failed to fetch collection with ID {}: {:?}", id, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Retrieves the IDs of the sounds in a collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the collection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the sound IDs in ascending order.
+    pub async fn members(&self, id: i64) -> Result<Vec<i64>, Error> {
+        let result = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM sounds WHERE collection_id = ? ORDER BY id",
+        )
+        .bind(id)
+        .fetch_all(&*self.read)
+        .await;
+
+        match result {
+            Ok(ids) => Ok(ids),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "CollectionRepository::members",
+                    &format!("Failed to fetch sounds of collection {}: {:?}", id, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Creates a new, empty collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the collection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the ID of the new collection, or an error if
+    /// the name is already taken.
+    pub async fn insert(&self, name: &str) -> Result<i64, Error> {
+        let result = sqlx::query("INSERT INTO collections (name) VALUES (?)")
+            .bind(name)
+            .execute(&*self.pool)
+            .await;
+
+        match result {
+            Ok(res) => Ok(res.last_insert_rowid()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "CollectionRepository::insert",
+                    &format!("Failed to create collection '{}': {:?}", name, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Deletes a collection. Its sounds are kept and no longer belong to
+    /// any collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the collection.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn delete(&self, id: i64) -> Result<(), Error> {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("UPDATE sounds SET collection_id = NULL WHERE collection_id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM collections WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "CollectionRepository::delete",
+                    &format!("Failed to delete collection with ID {}: {:?}", id, err),
+                );
+                Err(err)
+            }
+        }
+    }
+}
@@ -1,55 +1,71 @@
+use crate::settings::manager::DatabaseSettings;
 use crate::utils::logger::{log, LogLevel};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{Error, Pool, Sqlite};
-use std::fs;
-use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
-/// Represents a connection pool for an SQLite database.
+/// Represents the connection pools for an SQLite database.
+///
+/// Writes go through their own small pool, while reads use a separate pool
+/// whose connections refuse to write. In WAL mode readers never wait for
+/// the writer, so listing and searching stay responsive during imports.
 #[derive(Clone)]
 pub struct DatabasePool {
     db: Arc<Pool<Sqlite>>,
+    read: Arc<Pool<Sqlite>>,
 }
 
 impl DatabasePool {
-    /// Creates a new database connection pool.
+    /// Creates the database connection pools.
     ///
     /// This function attempts to connect to an SQLite database at the specified path.
-    /// If the database file does not exist, it will be created.
+    /// If the database file does not exist, it will be created. Every
+    /// connection uses WAL journaling with `synchronous = NORMAL`, waits for
+    /// locks up to the busy timeout, enforces foreign keys and caches
+    /// prepared statements.
     ///
     /// # Arguments
     ///
     /// * `db_path` - The file path of the SQLite database.
+    /// * `settings` - Pool sizes, busy timeout and statement cache size.
     ///
     /// # Returns
     ///
     /// Returns a `Result` containing the `DatabasePool` on success or an `sqlx::Error` on failure.
-    pub async fn new(db_path: &str) -> Result<Self, Error> {
+    pub async fn new(db_path: &str, settings: &DatabaseSettings) -> Result<Self, Error> {
         log(
             LogLevel::Info,
             "DatabasePool::new",
             &format!("Attempting to connect to SQLite database at '{}'.", db_path),
         );
 
-        if !Path::new(db_path).exists() {
-            fs::File::create(db_path).map_err(|e| {
-                log(
-                    LogLevel::Error,
-                    "DatabasePool::new",
-                    &format!("Unable to create database file '{}': {}", db_path, e),
-                );
-                e
-            })?;
-            log(
-                LogLevel::Info,
-                "DatabasePool::new",
-                &format!("Database file '{}' created.", db_path),
-            );
-        }
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(Duration::from_millis(settings.busy_timeout_ms))
+            .foreign_keys(true)
+            .statement_cache_capacity(settings.statement_cache_capacity);
 
-        let connection_string = format!("sqlite://{}", db_path);
+        let pools = async {
+            // The writer pool connects first so the database exists and is
+            // in WAL mode before any reader opens it.
+            let db = SqlitePoolOptions::new()
+                .max_connections(settings.write_connections.max(1))
+                .connect_with(options.clone())
+                .await?;
+            let read = SqlitePoolOptions::new()
+                .max_connections(settings.read_connections.max(1))
+                .connect_with(options.pragma("query_only", "ON"))
+                .await?;
+            Ok::<_, Error>((db, read))
+        }
+        .await;
 
-        match Pool::<Sqlite>::connect(&connection_string).await {
-            Ok(pool) => {
+        match pools {
+            Ok((db, read)) => {
                 log(
                     LogLevel::Info,
                     "DatabasePool::new",
@@ -58,7 +74,10 @@ impl DatabasePool {
                         db_path
                     ),
                 );
-                Ok(Self { db: Arc::new(pool) })
+                Ok(Self {
+                    db: Arc::new(db),
+                    read: Arc::new(read),
+                })
             }
             Err(err) => {
                 log(
@@ -86,4 +105,16 @@ impl DatabasePool {
         );
         self.db.clone()
     }
+
+    /// Retrieves the read-only connection pool.
+    ///
+    /// Statements that write fail on its connections, so it must only be
+    /// used for queries.
+    ///
+    /// # Returns
+    ///
+    /// An `Arc<Pool<Sqlite>>` that can be shared across the application.
+    pub fn get_read_db(&self) -> Arc<Pool<Sqlite>> {
+        self.read.clone()
+    }
 }
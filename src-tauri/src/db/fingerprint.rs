@@ -0,0 +1,172 @@
+use crate::db::sound::push_ids;
+use crate::utils::logger::{log, LogLevel};
+use sqlx::{Error, QueryBuilder, Sqlite, SqlitePool};
+use std::sync::Arc;
+
+/// Provides an interface for database operations on the `fingerprints` table.
+///
+/// Each row stores the timbre descriptor of one sound as a little-endian
+/// `f32` blob.
+pub struct FingerprintRepository {
+    pool: Arc<SqlitePool>,
+    read: Arc<SqlitePool>,
+}
+
+impl FingerprintRepository {
+    /// Creates a new `FingerprintRepository` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - A reference-counted SQLite connection pool.
+    /// * `read` - The pool used for queries, which may refuse writes.
+    pub fn new(pool: Arc<SqlitePool>, read: Arc<SqlitePool>) -> Self {
+        Self { pool, read }
+    }
+
+    /// Inserts or replaces the descriptor of a sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_id` - The ID of the sound the descriptor belongs to.
+    /// * `vector` - The descriptor values.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn upsert(&self, sound_id: i64, vector: &[f32]) -> Result<(), Error> {
+        let result =
+            sqlx::query("INSERT OR REPLACE INTO fingerprints (sound_id, vector) VALUES (?, ?)")
+                .bind(sound_id)
+                .bind(encode(vector))
+                .execute(&*self.pool)
+                .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "FingerprintRepository::upsert",
+                    &format!(
+                        "Failed to store fingerprint of sound {}: {:?}",
+                        sound_id, err
+                    ),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Retrieves the descriptors of every sound that is not deleted.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `(sound_id, vector)` pairs.
+    pub async fn get_all(&self) -> Result<Vec<(i64, Vec<f32>)>, Error> {
+        let result = sqlx::query_as::<_, (i64, Vec<u8>)>(
+            "SELECT f.sound_id, f.vector FROM fingerprints f
+             JOIN sounds s ON s.id = f.sound_id WHERE s.deleted_at IS NULL",
+        )
+        .fetch_all(&*self.read)
+        .await;
+
+        match result {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|(id, bytes)| (id, decode(&bytes)))
+                .collect()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "FingerprintRepository::get_all",
+                    &format!("Failed to fetch fingerprints: {:?}", err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Retrieves the descriptors of the given sounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_ids` - The IDs of the sounds. Sounds without a descriptor are skipped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `(sound_id, vector)` pairs.
+    pub async fn get_many(&self, sound_ids: &[i64]) -> Result<Vec<(i64, Vec<f32>)>, Error> {
+        if sound_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT sound_id, vector FROM fingerprints WHERE sound_id IN ",
+        );
+        push_ids(&mut builder, sound_ids);
+        let result = builder
+            .build_query_as::<(i64, Vec<u8>)>()
+            .fetch_all(&*self.read)
+            .await;
+
+        match result {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|(id, bytes)| (id, decode(&bytes)))
+                .collect()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "FingerprintRepository::get_many",
+                    &format!(
+                        "Failed to fetch fingerprints of sounds {:?}: {:?}",
+                        sound_ids, err
+                    ),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Deletes the descriptor of a sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_id` - The ID of the sound whose descriptor is removed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn delete(&self, sound_id: i64) -> Result<(), Error> {
+        let result = sqlx::query("DELETE FROM fingerprints WHERE sound_id = ?")
+            .bind(sound_id)
+            .execute(&*self.pool)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "FingerprintRepository::delete",
+                    &format!(
+                        "Failed to delete fingerprint of sound {}: {:?}",
+                        sound_id, err
+                    ),
+                );
+                Err(err)
+            }
+        }
+    }
+}
+
+fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
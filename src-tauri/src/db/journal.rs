@@ -0,0 +1,456 @@
+use crate::db::sound::{push_ids, unix_now, Sound};
+use crate::utils::logger::{log, LogLevel};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, Error, FromRow, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::sync::Arc;
+
+/// Number of edits kept in the journal. Older edits can no longer be undone.
+pub const JOURNAL_LIMIT: i64 = 100;
+
+/// The user-editable fields of a sound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundFields {
+    pub id: i64,
+    pub is_favorite: bool,
+    pub tags: Vec<String>,
+    pub rating: Option<i64>,
+    pub collection_id: Option<i64>,
+}
+
+impl SoundFields {
+    /// Captures the editable fields of a stored sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - The stored sound.
+    ///
+    /// # Returns
+    ///
+    /// `Some` with the fields, or `None` if the sound has no ID.
+    pub fn of(sound: &Sound) -> Option<Self> {
+        Some(Self {
+            id: sound.id?,
+            is_favorite: sound.is_favorite,
+            tags: sound.tags.0.clone(),
+            rating: sound.rating,
+            collection_id: sound.collection_id,
+        })
+    }
+}
+
+/// One step of undoing or redoing an edit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalOp {
+    /// Overwrite the editable fields of sounds.
+    SetFields { sounds: Vec<SoundFields> },
+    /// Bring deleted sounds back into the library.
+    RestoreSounds { ids: Vec<i64> },
+    /// Move sounds to the library's trash.
+    DeleteSounds {
+        ids: Vec<i64>,
+        /// The sounds whose files are moved to the desktop trash as well.
+        #[serde(default)]
+        trashed: Vec<i64>,
+    },
+    /// Create a collection under its old ID and move sounds into it.
+    InsertCollection {
+        id: i64,
+        name: String,
+        members: Vec<i64>,
+    },
+    /// Delete a collection, keeping its sounds.
+    DeleteCollection { id: i64 },
+}
+
+/// An edit recorded in the journal.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct JournalEntry {
+    /// The unique identifier of the entry.
+    pub id: i64,
+    /// A description of the edit.
+    pub label: String,
+    /// When the edit was made, as a Unix timestamp in seconds.
+    pub created_at: i64,
+    /// Whether the edit is currently undone.
+    pub undone: bool,
+}
+
+/// Provides an interface for database operations on the `journal` table.
+///
+/// The journal is a linear history: undone entries can be redone until a
+/// new edit is recorded, which discards them.
+pub struct JournalRepository {
+    pool: Arc<SqlitePool>,
+}
+
+impl JournalRepository {
+    /// Creates a new `JournalRepository` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - A reference-counted SQLite connection pool.
+    pub fn new(pool: Arc<SqlitePool>) -> Self {
+        Self { pool }
+    }
+
+    /// Records an edit, discarding undone edits and the oldest edits beyond
+    /// `JOURNAL_LIMIT`.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - A description of the edit.
+    /// * `undo` - The steps reverting the edit.
+    /// * `redo` - The steps applying the edit again.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn record(
+        &self,
+        label: &str,
+        undo: Vec<JournalOp>,
+        redo: Vec<JournalOp>,
+    ) -> Result<(), Error> {
+        let created_at = unix_now();
+
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("DELETE FROM journal WHERE undone = 1")
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("INSERT INTO journal (label, undo, redo, created_at) VALUES (?, ?, ?, ?)")
+                .bind(label)
+                .bind(Json(undo))
+                .bind(Json(redo))
+                .bind(created_at)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "DELETE FROM journal WHERE id NOT IN
+                 (SELECT id FROM journal ORDER BY id DESC LIMIT ?)",
+            )
+            .bind(JOURNAL_LIMIT)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "JournalRepository::record",
+                    &format!("Failed to record '{}': {:?}", label, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Reverts the latest edit that is not undone.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the undone entry and the steps applied, or
+    /// `None` if there is nothing to undo.
+    pub async fn undo(&self) -> Result<Option<(JournalEntry, Vec<JournalOp>)>, Error> {
+        self.step(
+            "SELECT id, label, created_at, undone, undo AS ops FROM journal
+             WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+            true,
+        )
+        .await
+    }
+
+    /// Applies the earliest undone edit again.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the redone entry and the steps applied, or
+    /// `None` if there is nothing to redo.
+    pub async fn redo(&self) -> Result<Option<(JournalEntry, Vec<JournalOp>)>, Error> {
+        self.step(
+            "SELECT id, label, created_at, undone, redo AS ops FROM journal
+             WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+            false,
+        )
+        .await
+    }
+
+    /// Retrieves the recorded edits, newest first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the journal entries.
+    pub async fn history(&self) -> Result<Vec<JournalEntry>, Error> {
+        let result = sqlx::query_as::<_, JournalEntry>(
+            "SELECT id, label, created_at, undone FROM journal ORDER BY id DESC",
+        )
+        .fetch_all(&*self.pool)
+        .await;
+
+        match result {
+            Ok(entries) => Ok(entries),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "JournalRepository::history",
+                    &format!("Failed to fetch journal: {:?}", err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Applies the steps of the entry selected by `query` and flips its
+    /// `undone` flag, all in one transaction.
+    async fn step(
+        &self,
+        query: &str,
+        undone: bool,
+    ) -> Result<Option<(JournalEntry, Vec<JournalOp>)>, Error> {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            let Some((id, label, created_at, _, Json(ops))) =
+                sqlx::query_as::<_, (i64, String, i64, bool, Json<Vec<JournalOp>>)>(query)
+                    .fetch_optional(&mut *tx)
+                    .await?
+            else {
+                return Ok(None);
+            };
+
+            for op in &ops {
+                apply(&mut tx, op).await?;
+            }
+            sqlx::query("UPDATE journal SET undone = ? WHERE id = ?")
+                .bind(undone)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            let entry = JournalEntry {
+                id,
+                label,
+                created_at,
+                undone,
+            };
+            Ok(Some((entry, ops)))
+        }
+        .await;
+
+        match result {
+            Ok(applied) => Ok(applied),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "JournalRepository::step",
+                    &format!(
+                        "Failed to {} journal entry: {:?}",
+                        if undone { "undo" } else { "redo" },
+                        err
+                    ),
+                );
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Applies one journal step.
+async fn apply(conn: &mut SqliteConnection, op: &JournalOp) -> Result<(), Error> {
+    match op {
+        JournalOp::SetFields { sounds } => {
+            for fields in sounds {
+                sqlx::query(
+                    "UPDATE sounds SET is_favorite = ?, tags = ?, rating = ?,
+                     collection_id = (SELECT id FROM collections WHERE id = ?) WHERE id = ?",
+                )
+                .bind(fields.is_favorite)
+                .bind(Json(&fields.tags))
+                .bind(fields.rating)
+                .bind(fields.collection_id)
+                .bind(fields.id)
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+        JournalOp::RestoreSounds { ids } => {
+            if !ids.is_empty() {
+                let mut builder =
+                    QueryBuilder::<Sqlite>::new("UPDATE sounds SET deleted_at = NULL WHERE id IN ");
+                push_ids(&mut builder, ids);
+                builder.build().execute(&mut *conn).await?;
+            }
+        }
+        JournalOp::DeleteSounds { ids, .. } => {
+            if !ids.is_empty() {
+                let mut builder = QueryBuilder::<Sqlite>::new("UPDATE sounds SET deleted_at = ");
+                builder
+                    .push_bind(unix_now())
+                    .push(" WHERE deleted_at IS NULL AND id IN ");
+                push_ids(&mut builder, ids);
+                builder.build().execute(&mut *conn).await?;
+            }
+        }
+        JournalOp::InsertCollection { id, name, members } => {
+            sqlx::query("INSERT INTO collections (id, name) VALUES (?, ?)")
+                .bind(id)
+                .bind(name)
+                .execute(&mut *conn)
+                .await?;
+            if !members.is_empty() {
+                let mut builder = QueryBuilder::<Sqlite>::new("UPDATE sounds SET collection_id = ");
+                builder.push_bind(*id).push(" WHERE id IN ");
+                push_ids(&mut builder, members);
+                builder.build().execute(&mut *conn).await?;
+            }
+        }
+        JournalOp::DeleteCollection { id } => {
+            sqlx::query("UPDATE sounds SET collection_id = NULL WHERE collection_id = ?")
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+            sqlx::query("DELETE FROM collections WHERE id = ?")
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::migrate;
+    use crate::db::sound::{SoundEdit, SoundRepository};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn repositories() -> (SoundRepository, JournalRepository) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        migrate(&pool, None).await.unwrap();
+        let pool = Arc::new(pool);
+        (
+            SoundRepository::new(Arc::clone(&pool), Arc::clone(&pool)),
+            JournalRepository::new(pool),
+        )
+    }
+
+    async fn fields(sounds: &SoundRepository, id: i64) -> SoundFields {
+        SoundFields::of(&sounds.get(id).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn undo_and_redo_round_trip_field_changes() {
+        let (sounds, journal) = repositories().await;
+        let id = sounds
+            .insert(Sound::new("kick", "/s/kick.wav"))
+            .await
+            .unwrap();
+        let old = fields(&sounds, id).await;
+        let new = SoundFields {
+            is_favorite: true,
+            tags: vec!["punchy".to_string()],
+            ..old.clone()
+        };
+        sounds.edit_many(&[id], &SoundEdit::Favorite).await.unwrap();
+        sounds
+            .edit_many(
+                &[id],
+                &SoundEdit::Tag {
+                    tag: "punchy".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        journal
+            .record(
+                "Edit kick",
+                vec![JournalOp::SetFields {
+                    sounds: vec![old.clone()],
+                }],
+                vec![JournalOp::SetFields {
+                    sounds: vec![new.clone()],
+                }],
+            )
+            .await
+            .unwrap();
+
+        let (entry, _) = journal.undo().await.unwrap().unwrap();
+        assert_eq!(entry.label, "Edit kick");
+        assert!(entry.undone);
+        assert_eq!(fields(&sounds, id).await, old);
+        assert!(journal.undo().await.unwrap().is_none());
+
+        let (entry, _) = journal.redo().await.unwrap().unwrap();
+        assert!(!entry.undone);
+        assert_eq!(fields(&sounds, id).await, new);
+        assert!(journal.redo().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn undo_and_redo_round_trip_deletions() {
+        let (sounds, journal) = repositories().await;
+        let id = sounds
+            .insert(Sound::new("kick", "/s/kick.wav"))
+            .await
+            .unwrap();
+        sounds.delete_many(&[id], &[]).await.unwrap();
+        journal
+            .record(
+                "Delete kick",
+                vec![JournalOp::RestoreSounds { ids: vec![id] }],
+                vec![JournalOp::DeleteSounds {
+                    ids: vec![id],
+                    trashed: vec![],
+                }],
+            )
+            .await
+            .unwrap();
+
+        journal.undo().await.unwrap().unwrap();
+        assert_eq!(sounds.get(id).await.unwrap().deleted_at, None);
+        journal.redo().await.unwrap().unwrap();
+        assert!(sounds.get(id).await.unwrap().deleted_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn recording_discards_undone_edits() {
+        let (_, journal) = repositories().await;
+        journal.record("First", vec![], vec![]).await.unwrap();
+        journal.record("Second", vec![], vec![]).await.unwrap();
+        journal.undo().await.unwrap().unwrap();
+        journal.record("Third", vec![], vec![]).await.unwrap();
+
+        let labels: Vec<String> = journal
+            .history()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.label)
+            .collect();
+        assert_eq!(labels, vec!["Third", "First"]);
+        assert!(journal.redo().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn deletions_recorded_before_trashed_files_still_load() {
+        let op: JournalOp =
+            serde_json::from_str(r#"{"op": "delete_sounds", "ids": [1, 2]}"#).unwrap();
+        match op {
+            JournalOp::DeleteSounds { ids, trashed } => {
+                assert_eq!(ids, vec![1, 2]);
+                assert!(trashed.is_empty());
+            }
+            other => panic!("unexpected op {:?}", other),
+        }
+    }
+}
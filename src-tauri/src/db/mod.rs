@@ -1,2 +1,7 @@
+pub mod backup;
+pub mod collection;
 pub mod connection;
+pub mod fingerprint;
+pub mod journal;
+pub mod schema;
 pub mod sound;
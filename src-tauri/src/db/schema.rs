@@ -0,0 +1,126 @@
+use crate::db::backup::create_backup;
+use crate::utils::logger::{log, LogLevel};
+use sqlx::{Error, Executor, SqlitePool};
+use std::path::Path;
+
+/// Ordered list of schema migrations.
+///
+/// The position of a migration in this list (starting at 1) is its version.
+/// The applied version is tracked with SQLite's `user_version` pragma, so
+/// new migrations must only ever be appended.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS sounds (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        path TEXT NOT NULL,
+        is_favorite BOOLEAN NOT NULL DEFAULT 0,
+        tags JSON NOT NULL DEFAULT '[]'
+    )",
+    "ALTER TABLE sounds ADD COLUMN lufs REAL;
+     ALTER TABLE sounds ADD COLUMN true_peak REAL;
+     ALTER TABLE sounds ADD COLUMN rms REAL;
+     ALTER TABLE sounds ADD COLUMN crest_factor REAL;",
+    "ALTER TABLE sounds ADD COLUMN trim_start REAL;
+     ALTER TABLE sounds ADD COLUMN trim_end REAL;",
+    "ALTER TABLE sounds ADD COLUMN duration REAL;
+     ALTER TABLE sounds ADD COLUMN bpm REAL;
+     ALTER TABLE sounds ADD COLUMN kind TEXT;
+     CREATE INDEX IF NOT EXISTS idx_sounds_kind ON sounds (kind);",
+    "ALTER TABLE sounds ADD COLUMN drum_class TEXT;
+     ALTER TABLE sounds ADD COLUMN drum_confidence REAL;
+     ALTER TABLE sounds ADD COLUMN auto_tag TEXT;
+     CREATE INDEX IF NOT EXISTS idx_sounds_drum_class ON sounds (drum_class);",
+    "CREATE TABLE IF NOT EXISTS fingerprints (
+        sound_id INTEGER PRIMARY KEY REFERENCES sounds (id) ON DELETE CASCADE,
+        vector BLOB NOT NULL
+    )",
+    "ALTER TABLE sounds ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0;
+     ALTER TABLE sounds ADD COLUMN last_played_at INTEGER;",
+    "ALTER TABLE sounds ADD COLUMN file_size INTEGER;
+     ALTER TABLE sounds ADD COLUMN modified_at INTEGER;
+     ALTER TABLE sounds ADD COLUMN content_hash TEXT;
+     CREATE INDEX IF NOT EXISTS idx_sounds_content_hash ON sounds (content_hash);",
+    "ALTER TABLE sounds ADD COLUMN status TEXT NOT NULL DEFAULT 'ok';
+     CREATE INDEX IF NOT EXISTS idx_sounds_status ON sounds (status);",
+    "CREATE INDEX IF NOT EXISTS idx_sounds_path ON sounds (path);",
+    "ALTER TABLE sounds ADD COLUMN added_at INTEGER;
+     ALTER TABLE sounds ADD COLUMN rating INTEGER;
+     UPDATE sounds SET added_at = CAST(strftime('%s', 'now') AS INTEGER);
+     CREATE INDEX IF NOT EXISTS idx_sounds_name ON sounds (name, id);
+     CREATE INDEX IF NOT EXISTS idx_sounds_added_at ON sounds (added_at, id);
+     CREATE INDEX IF NOT EXISTS idx_sounds_duration ON sounds (duration, id);
+     CREATE INDEX IF NOT EXISTS idx_sounds_bpm ON sounds (bpm, id);
+     CREATE INDEX IF NOT EXISTS idx_sounds_rating ON sounds (rating, id);
+     CREATE INDEX IF NOT EXISTS idx_sounds_play_count ON sounds (play_count, id);",
+    "CREATE TABLE IF NOT EXISTS collections (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE
+     );
+     ALTER TABLE sounds ADD COLUMN collection_id INTEGER REFERENCES collections (id) ON DELETE SET NULL;
+     CREATE INDEX IF NOT EXISTS idx_sounds_collection_id ON sounds (collection_id);",
+    "CREATE TABLE IF NOT EXISTS journal (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        label TEXT NOT NULL,
+        undo JSON NOT NULL,
+        redo JSON NOT NULL,
+        undone BOOLEAN NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL
+    )",
+    "ALTER TABLE sounds ADD COLUMN deleted_at INTEGER;
+     ALTER TABLE sounds ADD COLUMN trashed_path TEXT;
+     CREATE INDEX IF NOT EXISTS idx_sounds_deleted_at ON sounds (deleted_at);",
+];
+
+/// Brings the database schema up to the latest version.
+///
+/// Each pending migration runs in its own transaction together with the
+/// `user_version` bump, so a failed migration leaves the schema untouched.
+/// Before an existing database is migrated, it is backed up; if the backup
+/// fails, no migration is applied.
+///
+/// # Arguments
+///
+/// * `pool` - The SQLite connection pool to migrate.
+/// * `backup_dir` - Where to back up the database, or `None` to skip the backup.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+pub async fn migrate(pool: &SqlitePool, backup_dir: Option<&Path>) -> Result<(), Error> {
+    let (current,): (i64,) = sqlx::query_as("PRAGMA user_version")
+        .fetch_one(pool)
+        .await?;
+
+    if let Some(dir) = backup_dir {
+        if current > 0 && (current as usize) < MIGRATIONS.len() {
+            create_backup(pool, dir, &format!("pre-migration-v{}", current)).await?;
+        }
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index + 1;
+        log(
+            LogLevel::Info,
+            "schema::migrate",
+            &format!("Applying schema migration {}.", version),
+        );
+
+        // Executing through the `Executor` trait keeps this future `Send`,
+        // which `RawSql::execute` on a transaction does not.
+        let mut tx = pool.begin().await?;
+        tx.execute(sqlx::raw_sql(migration)).await?;
+        tx.execute(sqlx::raw_sql(&format!("PRAGMA user_version = {}", version)))
+            .await?;
+
+        if let Err(err) = tx.commit().await {
+            log(
+                LogLevel::Error,
+                "schema::migrate",
+                &format!("Failed to apply migration {}: {:?}", version, err),
+            );
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
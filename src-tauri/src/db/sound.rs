@@ -1,7 +1,20 @@
+use crate::db::journal::SoundFields;
 use crate::utils::logger::{log, LogLevel};
 use serde::{Deserialize, Serialize};
-use sqlx::{types::Json, Error, FromRow, SqlitePool};
+use sqlx::{types::Json, Error, FromRow, QueryBuilder, Sqlite, SqlitePool};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Column list selected for every `Sound` query, in `FromRow` order.
+const SOUND_COLUMNS: &str =
+    "id, name, path, is_favorite, tags, lufs, true_peak, rms, crest_factor, trim_start, trim_end, \
+     duration, bpm, kind, drum_class, drum_confidence, auto_tag, play_count, last_played_at, \
+     file_size, modified_at, content_hash, status, added_at, rating, collection_id, deleted_at, \
+     trashed_path";
+/// Number of sounds returned per page when no limit is given.
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+/// Largest number of sounds returned per page.
+pub const MAX_PAGE_SIZE: usize = 1000;
 
 /// Represents a sound entry in the database.
 ///
@@ -20,6 +33,337 @@ pub struct Sound {
     pub is_favorite: bool,
     /// A JSON array of tags associated with the sound.
     pub tags: Json<Vec<String>>,
+    /// Integrated loudness according to EBU R128, in LUFS.
+    pub lufs: Option<f64>,
+    /// Maximum true peak, in dBTP.
+    pub true_peak: Option<f64>,
+    /// RMS level across all channels, in dBFS.
+    pub rms: Option<f64>,
+    /// Ratio of sample peak to RMS level, in dB.
+    pub crest_factor: Option<f64>,
+    /// Suggested playback start that skips leading silence, in seconds.
+    pub trim_start: Option<f64>,
+    /// Suggested playback end that skips trailing silence, in seconds.
+    pub trim_end: Option<f64>,
+    /// Duration of the sound, in seconds.
+    pub duration: Option<f64>,
+    /// Tempo of the sound in beats per minute, if it is rhythmic.
+    pub bpm: Option<f64>,
+    /// Whether the sound is a one-shot or a loop.
+    pub kind: Option<SoundKind>,
+    /// The predicted drum type of a percussive one-shot.
+    pub drum_class: Option<DrumClass>,
+    /// Probability of the predicted drum type, from 0 to 1.
+    pub drum_confidence: Option<f64>,
+    /// The tag analysis added for the predicted drum type. Only this tag is
+    /// replaced when the sound is analysed again; tags the user added stay.
+    pub auto_tag: Option<String>,
+    /// How often the sound has been previewed.
+    pub play_count: i64,
+    /// When the sound was last previewed, as a Unix timestamp in seconds.
+    pub last_played_at: Option<i64>,
+    /// Size of the file when it was last analysed, in bytes.
+    pub file_size: Option<i64>,
+    /// Modification time of the file when it was last analysed, as a Unix
+    /// timestamp in seconds.
+    pub modified_at: Option<i64>,
+    /// BLAKE3 hash of the file contents when it was last analysed, as hex.
+    pub content_hash: Option<String>,
+    /// Whether the file could be read when the library was last checked.
+    pub status: SoundStatus,
+    /// When the sound was imported, as a Unix timestamp in seconds.
+    pub added_at: Option<i64>,
+    /// The user's rating from 1 to 5 stars.
+    pub rating: Option<i64>,
+    /// The collection the sound belongs to.
+    pub collection_id: Option<i64>,
+    /// When the sound was deleted, as a Unix timestamp in seconds. Deleted
+    /// sounds can be restored until they are purged.
+    pub deleted_at: Option<i64>,
+    /// Where the file was moved to when the sound was deleted along with it.
+    pub trashed_path: Option<String>,
+}
+
+/// The state of a sound's file as of the last library health check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SoundStatus {
+    /// The file exists and decodes.
+    #[default]
+    Ok,
+    /// The file does not exist.
+    Missing,
+    /// The file exists but cannot be opened.
+    Unreadable,
+    /// The file is zero bytes long.
+    Empty,
+    /// The file cannot be decoded as audio.
+    Corrupt,
+    /// The file is known to be unavailable, for example on a disconnected drive.
+    Offline,
+}
+
+/// Distinguishes single hits from material meant to be repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum SoundKind {
+    OneShot,
+    Loop,
+}
+
+/// Drum types a percussive one-shot can be classified as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum DrumClass {
+    Kick,
+    Snare,
+    Clap,
+    ClosedHat,
+    OpenHat,
+    Tom,
+    Perc,
+    #[serde(rename = "808")]
+    #[sqlx(rename = "808")]
+    Bass808,
+    Cymbal,
+}
+
+impl DrumClass {
+    /// Returns the tag added to sounds of this class.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            DrumClass::Kick => "kick",
+            DrumClass::Snare => "snare",
+            DrumClass::Clap => "clap",
+            DrumClass::ClosedHat => "closed hat",
+            DrumClass::OpenHat => "open hat",
+            DrumClass::Tom => "tom",
+            DrumClass::Perc => "perc",
+            DrumClass::Bass808 => "808",
+            DrumClass::Cymbal => "cymbal",
+        }
+    }
+}
+
+impl Sound {
+    /// Creates a new, not yet persisted sound entry without analysis data.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the sound file.
+    /// * `path` - The file system path where the sound is stored.
+    pub fn new(name: &str, path: &str) -> Self {
+        Self {
+            id: None,
+            name: name.to_string(),
+            path: path.to_string(),
+            is_favorite: false,
+            tags: Json(Vec::new()),
+            lufs: None,
+            true_peak: None,
+            rms: None,
+            crest_factor: None,
+            trim_start: None,
+            trim_end: None,
+            duration: None,
+            bpm: None,
+            kind: None,
+            drum_class: None,
+            drum_confidence: None,
+            auto_tag: None,
+            play_count: 0,
+            last_played_at: None,
+            file_size: None,
+            modified_at: None,
+            content_hash: None,
+            status: SoundStatus::Ok,
+            added_at: Some(unix_now()),
+            rating: None,
+            collection_id: None,
+            deleted_at: None,
+            trashed_path: None,
+        }
+    }
+}
+
+/// A change applied to many sounds in a single transaction.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SoundEdit {
+    /// Mark the sounds as favorites.
+    Favorite,
+    /// Remove the sounds from the favorites.
+    Unfavorite,
+    /// Add a tag to the sounds.
+    Tag { tag: String },
+    /// Remove a tag from the sounds.
+    Untag { tag: String },
+    /// Move the sounds to the library's trash, from where they can be restored.
+    Delete {
+        /// Also move the files to the desktop trash.
+        #[serde(default)]
+        trash_files: bool,
+        /// Delete files outright when they cannot be moved to the trash.
+        /// Files are never deleted unless this is set.
+        #[serde(default)]
+        delete_untrashable_files: bool,
+    },
+    /// Move the sounds into a collection, or out of every collection.
+    MoveToCollection { collection_id: Option<i64> },
+}
+
+/// The outcome of a batch operation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchSummary {
+    /// Number of distinct IDs the operation was requested for.
+    pub requested: usize,
+    /// Number of sounds that were changed.
+    pub changed: usize,
+    /// Number of sounds that already were in the requested state.
+    pub unchanged: usize,
+    /// Requested IDs that do not belong to any sound in the library.
+    pub not_found: Vec<i64>,
+    /// Sounds the operation failed for.
+    pub failed: Vec<BatchFailure>,
+}
+
+/// A sound a batch operation failed for.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchFailure {
+    /// The ID of the sound.
+    pub id: i64,
+    /// Why the operation failed.
+    pub error: String,
+}
+
+/// Fields a sound listing can be sorted by.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Name,
+    Lufs,
+    TruePeak,
+    Rms,
+    CrestFactor,
+    Duration,
+    Bpm,
+    DateAdded,
+    Rating,
+    PlayCount,
+}
+
+impl SortField {
+    /// Returns the column backing this sort field.
+    fn column(&self) -> &'static str {
+        match self {
+            SortField::Name => "name",
+            SortField::Lufs => "lufs",
+            SortField::TruePeak => "true_peak",
+            SortField::Rms => "rms",
+            SortField::CrestFactor => "crest_factor",
+            SortField::Duration => "duration",
+            SortField::Bpm => "bpm",
+            SortField::DateAdded => "added_at",
+            SortField::Rating => "rating",
+            SortField::PlayCount => "play_count",
+        }
+    }
+
+    /// Returns the value of this sort field for a sound.
+    fn value(&self, sound: &Sound) -> Option<SortValue> {
+        let number = |value: Option<f64>| value.map(SortValue::Number);
+        match self {
+            SortField::Name => Some(SortValue::Text(sound.name.clone())),
+            SortField::Lufs => number(sound.lufs),
+            SortField::TruePeak => number(sound.true_peak),
+            SortField::Rms => number(sound.rms),
+            SortField::CrestFactor => number(sound.crest_factor),
+            SortField::Duration => number(sound.duration),
+            SortField::Bpm => number(sound.bpm),
+            SortField::DateAdded => number(sound.added_at.map(|v| v as f64)),
+            SortField::Rating => number(sound.rating.map(|v| v as f64)),
+            SortField::PlayCount => Some(SortValue::Number(sound.play_count as f64)),
+        }
+    }
+}
+
+/// The value of a sort field stored in a cursor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortValue {
+    Number(f64),
+    Text(String),
+}
+
+/// Position after the last sound of a page, used to fetch the next page.
+///
+/// A cursor is only valid for the query and sort order it was returned for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundCursor {
+    /// The sort field value of the last sound, or `None` if it had none.
+    pub value: Option<SortValue>,
+    /// The ID of the last sound.
+    pub id: i64,
+}
+
+/// One page of a sorted sound listing.
+#[derive(Debug, Clone, Serialize)]
+pub struct SoundPage {
+    /// The sounds on this page.
+    pub sounds: Vec<Sound>,
+    /// Number of sounds matching the query across all pages.
+    pub total: i64,
+    /// The cursor of the next page, or `None` if this is the last page.
+    pub next_cursor: Option<SoundCursor>,
+}
+
+/// Filter and sort criteria for searching sounds.
+///
+/// Every filter is optional; unset filters match all sounds. Range filters
+/// never match sounds whose value has not been analysed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundQuery {
+    /// Case-insensitive substring matched against the sound name.
+    pub text: Option<String>,
+    /// Only return favorites when set to `true`.
+    pub favorites_only: bool,
+    /// Return deleted sounds instead of the library when set to `true`.
+    pub deleted: bool,
+    /// Only return sounds carrying this tag.
+    pub tag: Option<String>,
+    /// Only return sounds of this kind.
+    pub kind: Option<SoundKind>,
+    /// Only return sounds predicted as this drum type.
+    pub drum_class: Option<DrumClass>,
+    /// Only return sounds in this collection.
+    pub collection_id: Option<i64>,
+    /// Minimum duration, in seconds.
+    pub min_duration: Option<f64>,
+    /// Maximum duration, in seconds.
+    pub max_duration: Option<f64>,
+    /// Minimum tempo, in BPM.
+    pub min_bpm: Option<f64>,
+    /// Maximum tempo, in BPM.
+    pub max_bpm: Option<f64>,
+    /// Minimum integrated loudness, in LUFS.
+    pub min_lufs: Option<f64>,
+    /// Maximum integrated loudness, in LUFS.
+    pub max_lufs: Option<f64>,
+    /// Maximum true peak, in dBTP.
+    pub max_true_peak: Option<f64>,
+    /// Minimum crest factor, in dB.
+    pub min_crest_factor: Option<f64>,
+    /// Maximum crest factor, in dB.
+    pub max_crest_factor: Option<f64>,
+    /// The field to sort by.
+    pub sort_by: SortField,
+    /// Sort in descending instead of ascending order.
+    pub descending: bool,
 }
 
 /// Provides an interface for database operations on the `sounds` table.
@@ -28,6 +372,7 @@ pub struct Sound {
 /// It abstracts database interactions and ensures proper logging of errors.
 pub struct SoundRepository {
     pool: Arc<SqlitePool>,
+    read: Arc<SqlitePool>,
 }
 
 impl SoundRepository {
@@ -36,27 +381,31 @@ impl SoundRepository {
     /// # Arguments
     ///
     /// * `pool` - A reference-counted SQLite connection pool.
+    /// * `read` - The pool used for queries, which may refuse writes.
     ///
     /// # Returns
     ///
     /// A new `SoundRepository` instance.
-    pub fn new(pool: Arc<SqlitePool>) -> Self {
-        Self { pool }
+    pub fn new(pool: Arc<SqlitePool>, read: Arc<SqlitePool>) -> Self {
+        Self { pool, read }
     }
 
-    /// Checks if a sound with the given name exists in the database.
+    /// Checks if a sound for the given file exists in the database.
+    ///
+    /// Deleted sounds count as well, so that importing their folder again
+    /// does not add a second sound for a file that can still be restored.
     ///
     /// # Arguments
     ///
-    /// * `name` - The name of the sound to check.
+    /// * `path` - The path of the audio file to check.
     ///
     /// # Returns
     ///
-    /// A `Result` indicating whether the sound exists (`true`) or not (`false`).
-    pub async fn exists(&self, name: &str) -> Result<bool, sqlx::Error> {
-        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM sounds WHERE name = ?")
-            .bind(name)
-            .fetch_one(self.pool.as_ref())
+    /// A `Result` indicating whether a sound has this path (`true`) or not (`false`).
+    pub async fn exists(&self, path: &str) -> Result<bool, sqlx::Error> {
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM sounds WHERE path = ?")
+            .bind(path)
+            .fetch_one(&*self.read)
             .await?;
 
         Ok(row.0 > 0)
@@ -72,11 +421,12 @@ impl SoundRepository {
     ///
     /// A `Result` containing the `Sound` struct if found, or an error if not.
     pub async fn get(&self, id: i64) -> Result<Sound, Error> {
-        let result = sqlx::query_as::<_, Sound>(
-            "SELECT id, name, path, is_favorite, tags FROM sounds WHERE id = ?",
-        )
+        let result = sqlx::query_as::<_, Sound>(&format!(
+            "SELECT {} FROM sounds WHERE id = ?",
+            SOUND_COLUMNS
+        ))
         .bind(id)
-        .fetch_one(&*self.pool)
+        .fetch_one(&*self.read)
         .await;
 
         match result {
@@ -126,6 +476,182 @@ impl SoundRepository {
         }
     }
 
+    /// Stores the analysis results of a sound entry.
+    ///
+    /// The tags are not overwritten, so tags added while the sound was
+    /// analysed are kept. Only the previous auto-tag is removed and the new
+    /// one added.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - A reference to the `Sound` struct containing the analysis values.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn update_analysis(&self, sound: &Sound) -> Result<(), Error> {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(
+                "UPDATE sounds SET tags = (SELECT json_group_array(value) FROM json_each(sounds.tags)
+                 WHERE value != sounds.auto_tag)
+                 WHERE id = ? AND auto_tag IS NOT NULL AND auto_tag IS NOT ?",
+            )
+            .bind(sound.id)
+            .bind(&sound.auto_tag)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                "UPDATE sounds SET tags = json_insert(tags, '$[#]', ?1)
+                 WHERE id = ?2 AND ?1 IS NOT NULL
+                 AND NOT EXISTS (SELECT 1 FROM json_each(sounds.tags) WHERE value = ?1)",
+            )
+            .bind(&sound.auto_tag)
+            .bind(sound.id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                "UPDATE sounds SET lufs = ?, true_peak = ?, rms = ?, crest_factor = ?,
+                 trim_start = ?, trim_end = ?, duration = ?, bpm = ?, kind = ?,
+                 drum_class = ?, drum_confidence = ?, auto_tag = ? WHERE id = ?",
+            )
+            .bind(sound.lufs)
+            .bind(sound.true_peak)
+            .bind(sound.rms)
+            .bind(sound.crest_factor)
+            .bind(sound.trim_start)
+            .bind(sound.trim_end)
+            .bind(sound.duration)
+            .bind(sound.bpm)
+            .bind(sound.kind)
+            .bind(sound.drum_class)
+            .bind(sound.drum_confidence)
+            .bind(&sound.auto_tag)
+            .bind(sound.id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::update_analysis",
+                    &format!(
+                        "Failed to update analysis of sound with ID {:?}: {:?}",
+                        sound.id, err
+                    ),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Stores the size, modification time and content hash of a sound's file.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - A reference to the `Sound` struct containing the file state.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn update_file_state(&self, sound: &Sound) -> Result<(), Error> {
+        let result = sqlx::query(
+            "UPDATE sounds SET file_size = ?, modified_at = ?, content_hash = ? WHERE id = ?",
+        )
+        .bind(sound.file_size)
+        .bind(sound.modified_at)
+        .bind(&sound.content_hash)
+        .bind(sound.id)
+        .execute(&*self.pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::update_file_state",
+                    &format!(
+                        "Failed to update file state of sound with ID {:?}: {:?}",
+                        sound.id, err
+                    ),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Sets the status of several sound entries at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the sounds to update.
+    /// * `status` - The new status.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn update_status(&self, ids: &[i64], status: SoundStatus) -> Result<(), Error> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new("UPDATE sounds SET status = ");
+        builder.push_bind(status).push(" WHERE id IN ");
+        push_ids(&mut builder, ids);
+
+        let result = builder.build().execute(&*self.pool).await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::update_status",
+                    &format!("Failed to update status of sounds {:?}: {:?}", ids, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Increments the play count of a sound and records the play time.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the sound that was played.
+    /// * `played_at` - The time of the play, as a Unix timestamp in seconds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn record_play(&self, id: i64, played_at: i64) -> Result<(), Error> {
+        let result = sqlx::query(
+            "UPDATE sounds SET play_count = play_count + 1, last_played_at = ? WHERE id = ?",
+        )
+        .bind(played_at)
+        .bind(id)
+        .execute(&*self.pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::record_play",
+                    &format!("Failed to record play of sound with ID {}: {:?}", id, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
     /// Inserts a new sound entry into the database.
     ///
     /// # Arguments
@@ -136,14 +662,35 @@ impl SoundRepository {
     ///
     /// A `Result` containing the newly assigned ID or an error if the operation fails.
     pub async fn insert(&self, sound: Sound) -> Result<i64, Error> {
-        let result =
-            sqlx::query("INSERT INTO sounds (name, path, is_favorite, tags) VALUES (?, ?, ?, ?)")
-                .bind(&sound.name)
-                .bind(&sound.path)
-                .bind(sound.is_favorite)
-                .bind(&sound.tags)
-                .execute(&*self.pool)
-                .await;
+        let result = sqlx::query(
+            "INSERT INTO sounds (name, path, is_favorite, tags, lufs, true_peak, rms, crest_factor,
+             trim_start, trim_end, duration, bpm, kind, drum_class, drum_confidence,
+             auto_tag, file_size, modified_at, content_hash, added_at, rating)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&sound.name)
+        .bind(&sound.path)
+        .bind(sound.is_favorite)
+        .bind(&sound.tags)
+        .bind(sound.lufs)
+        .bind(sound.true_peak)
+        .bind(sound.rms)
+        .bind(sound.crest_factor)
+        .bind(sound.trim_start)
+        .bind(sound.trim_end)
+        .bind(sound.duration)
+        .bind(sound.bpm)
+        .bind(sound.kind)
+        .bind(sound.drum_class)
+        .bind(sound.drum_confidence)
+        .bind(&sound.auto_tag)
+        .bind(sound.file_size)
+        .bind(sound.modified_at)
+        .bind(&sound.content_hash)
+        .bind(sound.added_at)
+        .bind(sound.rating)
+        .execute(&*self.pool)
+        .await;
 
         match result {
             Ok(res) => Ok(res.last_insert_rowid()),
@@ -158,16 +705,18 @@ impl SoundRepository {
         }
     }
 
-    /// Retrieves all sound entries from the database.
+    /// Retrieves all sound entries from the database, except deleted ones.
     ///
     /// # Returns
     ///
     /// A `Result` containing a vector of `Sound` structs or an error if the query fails.
     pub async fn get_all(&self) -> Result<Vec<Sound>, Error> {
-        let result =
-            sqlx::query_as::<_, Sound>("SELECT id, name, path, is_favorite, tags FROM sounds")
-                .fetch_all(&*self.pool)
-                .await;
+        let result = sqlx::query_as::<_, Sound>(&format!(
+            "SELECT {} FROM sounds WHERE deleted_at IS NULL",
+            SOUND_COLUMNS
+        ))
+        .fetch_all(&*self.read)
+        .await;
 
         match result {
             Ok(sounds) => Ok(sounds),
@@ -182,17 +731,523 @@ impl SoundRepository {
         }
     }
 
-    /// Deletes a sound entry from the database by its ID.
+    /// Retrieves the sounds whose file lies below a directory, except
+    /// deleted ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory, or a single imported file.
+    /// * `offline` - Whether to retrieve the sounds marked offline or all
+    ///   others.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the matching `Sound` structs.
+    pub async fn get_below(&self, dir: &str, offline: bool) -> Result<Vec<Sound>, Error> {
+        let prefix = if dir.ends_with(std::path::MAIN_SEPARATOR) {
+            dir.to_string()
+        } else {
+            format!("{}{}", dir, std::path::MAIN_SEPARATOR)
+        };
+        let result = sqlx::query_as::<_, Sound>(&format!(
+            "SELECT {} FROM sounds WHERE deleted_at IS NULL AND (status = ?) = ?
+             AND (path = ? OR substr(path, 1, ?) = ?)",
+            SOUND_COLUMNS
+        ))
+        .bind(SoundStatus::Offline)
+        .bind(offline)
+        .bind(dir)
+        .bind(prefix.chars().count() as i64)
+        .bind(&prefix)
+        .fetch_all(&*self.read)
+        .await;
+
+        match result {
+            Ok(sounds) => Ok(sounds),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::get_below",
+                    &format!("Failed to fetch sounds below '{}': {:?}", dir, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Retrieves the sound entries with the given IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the sounds. Unknown IDs are skipped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Sound` structs ordered by ID.
+    pub async fn get_many(&self, ids: &[i64]) -> Result<Vec<Sound>, Error> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM sounds WHERE id IN ",
+            SOUND_COLUMNS
+        ));
+        push_ids(&mut builder, ids);
+        builder.push(" ORDER BY id");
+
+        let result = builder
+            .build_query_as::<Sound>()
+            .fetch_all(&*self.read)
+            .await;
+
+        match result {
+            Ok(sounds) => Ok(sounds),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::get_many",
+                    &format!("Failed to fetch sounds {:?}: {:?}", ids, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Searches sound entries matching the given filters.
+    ///
+    /// Sounds without a value for the sort field are always listed last.
     ///
     /// # Arguments
     ///
-    /// * `id` - The ID of the sound to be deleted.
+    /// * `query` - The filter and sort criteria.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the matching `Sound` structs in sorted order.
+    pub async fn search(&self, query: &SoundQuery) -> Result<Vec<Sound>, Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM sounds WHERE 1 = 1",
+            SOUND_COLUMNS
+        ));
+
+        push_filters(&mut builder, query);
+
+        let column = query.sort_by.column();
+        builder.push(format!(
+            " ORDER BY {} IS NULL, {} {}, id",
+            column,
+            column,
+            if query.descending { "DESC" } else { "ASC" }
+        ));
+
+        let result = builder
+            .build_query_as::<Sound>()
+            .fetch_all(&*self.read)
+            .await;
+
+        match result {
+            Ok(sounds) => Ok(sounds),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::search",
+                    &format!("Failed to search sounds: {:?}", err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Lists one page of the sounds matching the given filters.
+    ///
+    /// Pages are sorted like `search` and use keyset pagination: each page
+    /// continues after the cursor returned with the previous one, so pages
+    /// are fetched in constant time no matter how deep the listing goes.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The filter and sort criteria.
+    /// * `cursor` - The cursor of the page to fetch, or `None` for the first page.
+    /// * `limit` - The maximum number of sounds on the page.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the page and the total number of matches.
+    pub async fn list(
+        &self,
+        query: &SoundQuery,
+        cursor: Option<&SoundCursor>,
+        limit: usize,
+    ) -> Result<SoundPage, Error> {
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let column = query.sort_by.column();
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM sounds WHERE 1 = 1");
+        push_filters(&mut count, query);
+
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM sounds WHERE 1 = 1",
+            SOUND_COLUMNS
+        ));
+        push_filters(&mut builder, query);
+        if let Some(cursor) = cursor {
+            match &cursor.value {
+                Some(value) => {
+                    let comparison = if query.descending { "<" } else { ">" };
+                    builder.push(format!(
+                        " AND ({} IS NULL OR {} {} ",
+                        column, column, comparison
+                    ));
+                    push_sort_value(&mut builder, value);
+                    builder.push(format!(" OR ({} = ", column));
+                    push_sort_value(&mut builder, value);
+                    builder.push(" AND id > ").push_bind(cursor.id).push("))");
+                }
+                None => {
+                    builder
+                        .push(format!(" AND {} IS NULL AND id > ", column))
+                        .push_bind(cursor.id);
+                }
+            }
+        }
+        builder.push(format!(
+            " ORDER BY {} IS NULL, {} {}, id LIMIT ",
+            column,
+            column,
+            if query.descending { "DESC" } else { "ASC" }
+        ));
+        builder.push_bind(limit as i64 + 1);
+
+        let result = async {
+            let (total,): (i64,) = count.build_query_as().fetch_one(&*self.read).await?;
+            let sounds = builder
+                .build_query_as::<Sound>()
+                .fetch_all(&*self.read)
+                .await?;
+            Ok::<_, Error>((total, sounds))
+        }
+        .await;
+
+        match result {
+            Ok((total, mut sounds)) => {
+                let next_cursor = if sounds.len() > limit {
+                    sounds.truncate(limit);
+                    sounds.last().and_then(|last| {
+                        Some(SoundCursor {
+                            value: query.sort_by.value(last),
+                            id: last.id?,
+                        })
+                    })
+                } else {
+                    None
+                };
+                Ok(SoundPage {
+                    sounds,
+                    total,
+                    next_cursor,
+                })
+            }
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::list",
+                    &format!("Failed to list sounds: {:?}", err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Sets or clears the rating of a sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the sound.
+    /// * `rating` - The rating from 1 to 5, or `None` to clear it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn update_rating(&self, id: i64, rating: Option<i64>) -> Result<(), Error> {
+        let result = sqlx::query("UPDATE sounds SET rating = ? WHERE id = ?")
+            .bind(rating)
+            .bind(id)
+            .execute(&*self.pool)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::update_rating",
+                    &format!("Failed to rate sound with ID {}: {:?}", id, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Overwrites the editable fields of many sounds in one transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `sounds` - The new fields, each naming the sound they belong to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    pub async fn update_fields(&self, sounds: &[SoundFields]) -> Result<(), Error> {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            for fields in sounds {
+                sqlx::query(
+                    "UPDATE sounds SET is_favorite = ?, tags = ?, rating = ?, collection_id = ?
+                     WHERE id = ?",
+                )
+                .bind(fields.is_favorite)
+                .bind(Json(&fields.tags))
+                .bind(fields.rating)
+                .bind(fields.collection_id)
+                .bind(fields.id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::update_fields",
+                    &format!("Failed to update {} sounds: {:?}", sounds.len(), err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Applies the same change to many sounds in one transaction.
+    ///
+    /// Either every sound is changed or, if any statement fails, none is.
+    /// Deleted sounds are left alone and reported as not found.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the sounds to change. Duplicates are ignored.
+    /// * `edit` - The change to apply.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing how many sounds were changed, left as they
+    /// were, or not found.
+    pub async fn edit_many(&self, ids: &[i64], edit: &SoundEdit) -> Result<BatchSummary, Error> {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(BatchSummary::default());
+        }
+
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+
+            let mut builder = QueryBuilder::<Sqlite>::new(
+                "SELECT id FROM sounds WHERE deleted_at IS NULL AND id IN ",
+            );
+            push_ids(&mut builder, &ids);
+            builder.push(" ORDER BY id");
+            let existing: Vec<i64> = builder
+                .build_query_scalar()
+                .fetch_all(&mut *tx)
+                .await?;
+
+            let mut builder = QueryBuilder::<Sqlite>::new("");
+            match edit {
+                SoundEdit::Favorite | SoundEdit::Unfavorite => {
+                    let favorite = matches!(edit, SoundEdit::Favorite);
+                    builder
+                        .push("UPDATE sounds SET is_favorite = ")
+                        .push_bind(favorite)
+                        .push(" WHERE is_favorite != ")
+                        .push_bind(favorite);
+                }
+                SoundEdit::Tag { tag } => {
+                    builder
+                        .push("UPDATE sounds SET tags = json_insert(tags, '$[#]', ")
+                        .push_bind(tag.clone())
+                        .push(") WHERE NOT EXISTS (SELECT 1 FROM json_each(sounds.tags) WHERE value = ")
+                        .push_bind(tag.clone())
+                        .push(")");
+                }
+                SoundEdit::Untag { tag } => {
+                    builder
+                        .push("UPDATE sounds SET tags = (SELECT json_group_array(value) FROM json_each(sounds.tags) WHERE value != ")
+                        .push_bind(tag.clone())
+                        .push(") WHERE EXISTS (SELECT 1 FROM json_each(sounds.tags) WHERE value = ")
+                        .push_bind(tag.clone())
+                        .push(")");
+                }
+                SoundEdit::Delete { .. } => {
+                    builder
+                        .push("UPDATE sounds SET deleted_at = ")
+                        .push_bind(unix_now())
+                        .push(" WHERE deleted_at IS NULL");
+                }
+                SoundEdit::MoveToCollection { collection_id } => {
+                    builder
+                        .push("UPDATE sounds SET collection_id = ")
+                        .push_bind(*collection_id)
+                        .push(" WHERE collection_id IS NOT ")
+                        .push_bind(*collection_id);
+                }
+            }
+            if !matches!(edit, SoundEdit::Delete { .. }) {
+                builder.push(" AND deleted_at IS NULL");
+            }
+            builder.push(" AND id IN ");
+            push_ids(&mut builder, &ids);
+            let changed = builder.build().execute(&mut *tx).await?.rows_affected() as usize;
+
+            tx.commit().await?;
+            Ok::<_, Error>((existing, changed))
+        }
+        .await;
+
+        match result {
+            Ok((existing, changed)) => Ok(BatchSummary {
+                requested: ids.len(),
+                changed,
+                unchanged: existing.len() - changed,
+                not_found: ids
+                    .iter()
+                    .filter(|id| existing.binary_search(id).is_err())
+                    .copied()
+                    .collect(),
+                failed: vec![],
+            }),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::edit_many",
+                    &format!("Failed to apply {:?} to sounds {:?}: {:?}", edit, ids, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Brings deleted sounds back into the library.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the sounds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the IDs of the sounds that were deleted before.
+    pub async fn restore(&self, ids: &[i64]) -> Result<Vec<i64>, Error> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "UPDATE sounds SET deleted_at = NULL WHERE deleted_at IS NOT NULL AND id IN ",
+        );
+        push_ids(&mut builder, ids);
+        builder.push(" RETURNING id");
+
+        let result = builder.build_query_scalar().fetch_all(&*self.pool).await;
+
+        match result {
+            Ok(restored) => Ok(restored),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::restore",
+                    &format!("Failed to restore sounds {:?}: {:?}", ids, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Moves sounds to the library's trash, storing where their files were
+    /// moved to in the same transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the sounds. Sounds already deleted are skipped.
+    /// * `trashed` - The IDs of sounds whose file was moved to the desktop
+    ///   trash, with the path inside the trash.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing how many sounds were deleted.
+    pub async fn delete_many(
+        &self,
+        ids: &[i64],
+        trashed: &[(i64, String)],
+    ) -> Result<usize, Error> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            let mut builder = QueryBuilder::<Sqlite>::new("UPDATE sounds SET deleted_at = ");
+            builder
+                .push_bind(unix_now())
+                .push(" WHERE deleted_at IS NULL AND id IN ");
+            push_ids(&mut builder, ids);
+            let deleted = builder.build().execute(&mut *tx).await?.rows_affected() as usize;
+
+            for (id, trashed_path) in trashed {
+                sqlx::query("UPDATE sounds SET trashed_path = ? WHERE id = ?")
+                    .bind(trashed_path)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            Ok::<_, Error>(deleted)
+        }
+        .await;
+
+        match result {
+            Ok(deleted) => Ok(deleted),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::delete_many",
+                    &format!("Failed to delete sounds {:?}: {:?}", ids, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Stores where a sound's file was moved to the trash.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the sound.
+    /// * `trashed_path` - The path in the trash, or `None` once the file is back.
     ///
     /// # Returns
     ///
     /// A `Result` indicating success or failure.
-    pub async fn delete(&self, id: i64) -> Result<(), Error> {
-        let result = sqlx::query("DELETE FROM sounds WHERE id = ?")
+    pub async fn update_trashed_path(
+        &self,
+        id: i64,
+        trashed_path: Option<&str>,
+    ) -> Result<(), Error> {
+        let result = sqlx::query("UPDATE sounds SET trashed_path = ? WHERE id = ?")
+            .bind(trashed_path)
             .bind(id)
             .execute(&*self.pool)
             .await;
@@ -202,11 +1257,251 @@ impl SoundRepository {
             Err(err) => {
                 log(
                     LogLevel::Error,
-                    "SoundRepository::delete",
-                    &format!("Failed to delete sound with ID {}: {:?}", id, err),
+                    "SoundRepository::update_trashed_path",
+                    &format!("Failed to update trashed path of sound {}: {:?}", id, err),
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Permanently removes sounds that were deleted before a point in time.
+    ///
+    /// Only database rows are removed; files stay wherever they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `deleted_before` - The cutoff, as a Unix timestamp in seconds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the IDs of the removed sounds.
+    pub async fn purge(&self, deleted_before: i64) -> Result<Vec<i64>, Error> {
+        let result = async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(
+                "DELETE FROM fingerprints WHERE sound_id IN
+                 (SELECT id FROM sounds WHERE deleted_at < ?)",
+            )
+            .bind(deleted_before)
+            .execute(&mut *tx)
+            .await?;
+            let purged: Vec<i64> =
+                sqlx::query_scalar("DELETE FROM sounds WHERE deleted_at < ? RETURNING id")
+                    .bind(deleted_before)
+                    .fetch_all(&mut *tx)
+                    .await?;
+            tx.commit().await?;
+            Ok::<_, Error>(purged)
+        }
+        .await;
+
+        match result {
+            Ok(purged) => Ok(purged),
+            Err(err) => {
+                log(
+                    LogLevel::Error,
+                    "SoundRepository::purge",
+                    &format!("Failed to purge deleted sounds: {:?}", err),
                 );
                 Err(err)
             }
         }
     }
 }
+
+/// Appends the filter conditions of a query.
+fn push_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &SoundQuery) {
+    builder.push(if query.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(text) = query.text.as_deref().filter(|t| !t.is_empty()) {
+        builder
+            .push(" AND name LIKE ")
+            .push_bind(format!("%{}%", text));
+    }
+    if query.favorites_only {
+        builder.push(" AND is_favorite = 1");
+    }
+    if let Some(tag) = &query.tag {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM json_each(sounds.tags) WHERE value = ")
+            .push_bind(tag.clone())
+            .push(")");
+    }
+    if let Some(kind) = query.kind {
+        builder.push(" AND kind = ").push_bind(kind);
+    }
+    if let Some(drum_class) = query.drum_class {
+        builder.push(" AND drum_class = ").push_bind(drum_class);
+    }
+    if let Some(collection_id) = query.collection_id {
+        builder
+            .push(" AND collection_id = ")
+            .push_bind(collection_id);
+    }
+    push_range(builder, "duration", query.min_duration, query.max_duration);
+    push_range(builder, "bpm", query.min_bpm, query.max_bpm);
+    push_range(builder, "lufs", query.min_lufs, query.max_lufs);
+    push_range(builder, "true_peak", None, query.max_true_peak);
+    push_range(
+        builder,
+        "crest_factor",
+        query.min_crest_factor,
+        query.max_crest_factor,
+    );
+}
+
+/// Returns the current time as a Unix timestamp in seconds.
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Appends a parenthesised list of IDs as bound parameters.
+pub(crate) fn push_ids(builder: &mut QueryBuilder<'_, Sqlite>, ids: &[i64]) {
+    builder.push("(");
+    let mut separated = builder.separated(", ");
+    for id in ids {
+        separated.push_bind(*id);
+    }
+    separated.push_unseparated(")");
+}
+
+/// Appends a sort value as a bound parameter.
+fn push_sort_value(builder: &mut QueryBuilder<'_, Sqlite>, value: &SortValue) {
+    match value {
+        SortValue::Number(number) => builder.push_bind(*number),
+        SortValue::Text(text) => builder.push_bind(text.clone()),
+    };
+}
+
+/// Appends an inclusive range condition on a numeric column.
+fn push_range(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    column: &str,
+    min: Option<f64>,
+    max: Option<f64>,
+) {
+    if let Some(min) = min {
+        builder.push(format!(" AND {} >= ", column)).push_bind(min);
+    }
+    if let Some(max) = max {
+        builder.push(format!(" AND {} <= ", column)).push_bind(max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::migrate;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn repository() -> SoundRepository {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        migrate(&pool, None).await.unwrap();
+        let pool = Arc::new(pool);
+        SoundRepository::new(Arc::clone(&pool), pool)
+    }
+
+    /// Inserts sounds with the given tempos and returns their IDs.
+    async fn insert_with_bpm(repo: &SoundRepository, tempos: &[Option<f64>]) -> Vec<i64> {
+        let mut ids = vec![];
+        for (index, bpm) in tempos.iter().enumerate() {
+            let mut sound = Sound::new(&format!("sound {}", index), &format!("/s/{}.wav", index));
+            sound.bpm = *bpm;
+            ids.push(repo.insert(sound).await.unwrap());
+        }
+        ids
+    }
+
+    /// Fetches every page of a listing and returns the IDs in order.
+    async fn list_all(repo: &SoundRepository, query: &SoundQuery, limit: usize) -> Vec<i64> {
+        let mut ids = vec![];
+        let mut cursor = None;
+        loop {
+            let page = repo.list(query, cursor.as_ref(), limit).await.unwrap();
+            assert_eq!(page.total, 6);
+            ids.extend(page.sounds.iter().filter_map(|sound| sound.id));
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return ids,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_follow_the_sort_order_with_ties_and_missing_values() {
+        let repo = repository().await;
+        let ids = insert_with_bpm(
+            &repo,
+            &[
+                Some(120.0),
+                None,
+                Some(90.0),
+                Some(120.0),
+                None,
+                Some(140.0),
+            ],
+        )
+        .await;
+        let mut query = SoundQuery {
+            sort_by: SortField::Bpm,
+            ..Default::default()
+        };
+
+        // Ties are ordered by ID and sounds without a value come last.
+        let ascending = vec![ids[2], ids[0], ids[3], ids[5], ids[1], ids[4]];
+        for limit in 1..=6 {
+            assert_eq!(list_all(&repo, &query, limit).await, ascending);
+        }
+
+        query.descending = true;
+        let descending = vec![ids[5], ids[0], ids[3], ids[2], ids[1], ids[4]];
+        for limit in 1..=6 {
+            assert_eq!(list_all(&repo, &query, limit).await, descending);
+        }
+    }
+
+    #[test]
+    fn cursors_round_trip_through_json() {
+        let cursor = SoundCursor {
+            value: Some(SortValue::Text("kick".to_string())),
+            id: 3,
+        };
+        let json = serde_json::to_string(&cursor).unwrap();
+        assert_eq!(serde_json::from_str::<SoundCursor>(&json).unwrap(), cursor);
+
+        let number: SoundCursor = serde_json::from_str(r#"{"value": 120.5, "id": 1}"#).unwrap();
+        assert_eq!(number.value, Some(SortValue::Number(120.5)));
+    }
+
+    #[tokio::test]
+    async fn batch_edits_skip_deleted_sounds() {
+        let repo = repository().await;
+        let ids = insert_with_bpm(&repo, &[None, None]).await;
+        let delete = SoundEdit::Delete {
+            trash_files: false,
+            delete_untrashable_files: false,
+        };
+        repo.edit_many(&ids[..1], &delete).await.unwrap();
+
+        let summary = repo.edit_many(&ids, &SoundEdit::Favorite).await.unwrap();
+        assert_eq!(summary.changed, 1);
+        assert_eq!(summary.unchanged, 0);
+        assert_eq!(summary.not_found, vec![ids[0]]);
+        assert!(!repo.get(ids[0]).await.unwrap().is_favorite);
+
+        let summary = repo.edit_many(&ids, &delete).await.unwrap();
+        assert_eq!(summary.changed, 1);
+        assert_eq!(summary.not_found, vec![ids[0]]);
+    }
+}
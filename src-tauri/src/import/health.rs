@@ -0,0 +1,74 @@
+use crate::analysis::stream::AudioStream;
+use crate::db::sound::SoundStatus;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::ErrorKind;
+
+/// Files checked at the same time by a library health check.
+pub const HEALTH_CHECK_CONCURRENCY: usize = 8;
+
+/// A sound whose file has a problem.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthIssue {
+    /// The ID of the sound.
+    pub id: i64,
+    /// The name of the sound.
+    pub name: String,
+    /// The path of the sound's file.
+    pub path: String,
+    /// What is wrong with the file.
+    pub status: SoundStatus,
+}
+
+/// The result of checking every file in the library.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HealthReport {
+    /// Number of sounds checked.
+    pub checked: usize,
+    /// Sounds whose file is not `Ok`, ordered by path.
+    pub issues: Vec<HealthIssue>,
+}
+
+/// What to do with the sounds selected from a health report.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HealthAction {
+    /// Delete the sounds from the library.
+    Remove,
+    /// Look for the files below another directory.
+    Relink { old_root: String, new_root: String },
+    /// Keep the sounds, but mark them as unavailable.
+    MarkOffline,
+}
+
+/// Checks whether a file exists, can be read and decodes as audio.
+///
+/// Only the first chunk is decoded, so damage further into a file is not
+/// detected. This blocks, so it must only be called from blocking threads.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file.
+///
+/// # Returns
+///
+/// The status of the file.
+pub fn check_file(path: &str) -> SoundStatus {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => return SoundStatus::Missing,
+        Err(_) => return SoundStatus::Unreadable,
+    };
+    if metadata.len() == 0 {
+        return SoundStatus::Empty;
+    }
+    if File::open(path).is_err() {
+        return SoundStatus::Unreadable;
+    }
+
+    let mut chunk = Vec::new();
+    match AudioStream::open(path).and_then(|mut stream| stream.read_chunk(&mut chunk)) {
+        Ok(frames) if frames > 0 => SoundStatus::Ok,
+        _ => SoundStatus::Corrupt,
+    }
+}
@@ -1,102 +1,1133 @@
 use crate::utils::logger::{log, LogLevel};
-use futures::future::join_all;
-use sqlx::types::Json;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::fs;
+use tokio::task::JoinHandle;
 
+use crate::analysis::analyzer::{analyze_file, Analysis};
+use crate::analysis::decoder::decode_file;
+use crate::analysis::encoder::{trim, write_wav};
+use crate::analysis::silence::{detect_silence, SILENCE_THRESHOLD_DB};
+use crate::analysis::stream::AudioStream;
 use crate::cache::cache_module::Cache;
-use crate::db::sound::{Sound, SoundRepository};
+use crate::cache::content::modified_secs;
+use crate::db::sound::{BatchFailure, BatchSummary, Sound, SoundRepository, SoundStatus};
+use crate::import::health::{check_file, HealthIssue, HealthReport, HEALTH_CHECK_CONCURRENCY};
+use crate::import::scanner::{scan_audio_files, ImportFilter, ScannedFile};
+use crate::import::volume::{locate, rebase, volume_of, Volume};
+use crate::settings::manager::{ImportPath, SettingsManager};
+use crate::similarity::index::FingerprintIndex;
+
+/// Version of cached analysis results. Bump it whenever `Analysis` changes
+/// so that results computed by older versions are recomputed.
+const ANALYSIS_CACHE_VERSION: u32 = 1;
+/// Largest duration difference between a missing sound and a moved file
+/// that still counts as the same recording, in seconds.
+const DURATION_TOLERANCE_SECS: f64 = 0.01;
+
+/// Callback notified when sounds went offline or came back.
+pub type LibraryListener = Box<dyn Fn() + Send + Sync>;
+
+/// Callback receiving import progress updates.
+pub type ProgressListener = Box<dyn Fn(&ImportProgress) + Send + Sync>;
+
+/// Progress of an import or rescan, reported after every file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    /// The import path being scanned.
+    pub root: String,
+    /// The file that was just processed.
+    pub path: String,
+    /// Number of files processed so far.
+    pub processed: usize,
+    /// Number of files found below the import path.
+    pub total: usize,
+}
+
+/// What a rescan changed in the library.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RescanSummary {
+    /// New files that were imported.
+    pub added: usize,
+    /// Known files whose contents changed and were analysed again.
+    pub updated: usize,
+    /// Missing sounds whose file was found at a new location.
+    pub relocated: usize,
+    /// Sounds whose file is gone and that were flagged as missing.
+    pub missing: usize,
+}
+
+/// The result of rescanning a single file.
+enum ScanOutcome {
+    Added,
+    Updated,
+    Unchanged,
+}
 
 pub struct Importer {
     repo: Arc<SoundRepository>,
     pub cache: Arc<Cache>,
+    pub fingerprints: Arc<FingerprintIndex>,
+    settings: Arc<SettingsManager>,
+    /// The name of the library the importer belongs to, whose import paths
+    /// it reads and updates.
+    library: String,
+    listener: Mutex<Option<ProgressListener>>,
 }
 
 impl Importer {
-    pub fn new(repo: Arc<SoundRepository>, cache: Arc<Cache>) -> Arc<Self> {
+    pub fn new(
+        repo: Arc<SoundRepository>,
+        cache: Arc<Cache>,
+        fingerprints: Arc<FingerprintIndex>,
+        settings: Arc<SettingsManager>,
+        library: String,
+    ) -> Arc<Self> {
         log(LogLevel::Info, "Importer::new", "Initializing Importer.");
-        Arc::new(Self { repo, cache })
+        Arc::new(Self {
+            repo,
+            cache,
+            fingerprints,
+            settings,
+            library,
+            listener: Mutex::new(None),
+        })
     }
 
-    pub async fn import_sound(self: &Arc<Self>, name: &str, path: &str) -> Result<(), String> {
-        if self.cache.get_cached_sound(name).await.is_some() {
-            return Ok(());
-        }
+    /// Registers the callback that receives import progress updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The callback, replacing any previous one.
+    pub fn set_progress_listener(&self, listener: ProgressListener) {
+        *self.listener.lock().unwrap() = Some(listener);
+    }
 
+    pub async fn import_sound(self: &Arc<Self>, name: &str, path: &str) -> Result<bool, String> {
         if self
             .repo
-            .exists(name)
+            .exists(path)
             .await
             .map_err(|e| format!("DB check failed: {}", e))?
         {
-            return Ok(());
+            return Ok(false);
         }
 
         if !Path::new(path).exists() {
-            return Ok(());
+            return Ok(false);
+        }
+
+        let mut sound = Sound::new(name, path);
+        let fingerprint = match self.analyze(path).await {
+            Ok(analysis) => {
+                analysis.apply_to(&mut sound, &self.settings.get().analysis);
+                analysis.fingerprint
+            }
+            Err(e) => {
+                log(
+                    LogLevel::Warn,
+                    "Importer::import_sound",
+                    &format!("Skipping analysis of '{}': {}", name, e),
+                );
+                None
+            }
+        };
+
+        if let Err(e) = self.record_file_state(&mut sound).await {
+            log(LogLevel::Warn, "Importer::import_sound", &e);
+        }
+
+        match self.repo.insert(sound).await {
+            Ok(id) => {
+                if let Some(vector) = fingerprint {
+                    self.fingerprints.insert(id, vector).await?;
+                }
+                Ok(true)
+            }
+            Err(e) => {
+                log(
+                    LogLevel::Error,
+                    "Importer::import_sound",
+                    &format!("Error importing '{}': {}", name, e),
+                );
+                Ok(false)
+            }
+        }
+    }
+
+    pub async fn analyze_sound(&self, id: i64) -> Result<Sound, String> {
+        let mut sound = self
+            .repo
+            .get(id)
+            .await
+            .map_err(|e| format!("Error fetching sound: {}", e))?;
+
+        self.reanalyze(&mut sound).await?;
+        Ok(sound)
+    }
+
+    /// Analyses many sounds again, a few at a time.
+    ///
+    /// Each sound is stored as soon as its analysis finishes, so a failure
+    /// only affects that sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the sounds. Duplicates are ignored.
+    ///
+    /// # Returns
+    ///
+    /// A summary of the analysed, missing and failed sounds.
+    pub async fn reanalyze_many(&self, ids: &[i64]) -> BatchSummary {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let outcomes: Vec<(i64, Result<(), Option<String>>)> = stream::iter(ids.iter().copied())
+            .map(|id| async move {
+                let outcome = match self.repo.get(id).await {
+                    Ok(mut sound) => self.reanalyze(&mut sound).await.map_err(Some),
+                    Err(sqlx::Error::RowNotFound) => Err(None),
+                    Err(e) => Err(Some(format!("Error fetching sound: {}", e))),
+                };
+                (id, outcome)
+            })
+            .buffer_unordered(self.settings.get().import.concurrency)
+            .collect()
+            .await;
+
+        let mut summary = BatchSummary {
+            requested: ids.len(),
+            ..Default::default()
+        };
+        for (id, outcome) in outcomes {
+            match outcome {
+                Ok(()) => summary.changed += 1,
+                Err(None) => summary.not_found.push(id),
+                Err(Some(error)) => summary.failed.push(BatchFailure { id, error }),
+            }
         }
+        summary.not_found.sort_unstable();
+        summary.failed.sort_by_key(|failure| failure.id);
+        summary
+    }
 
-        let sound = Sound {
-            id: None,
-            name: name.to_string(),
-            path: path.to_string(),
-            is_favorite: false,
-            tags: Json(Vec::new()),
+    /// Checks whether a sound's file changed since it was analysed.
+    ///
+    /// Files whose size or modification time differ are hashed. If the
+    /// contents changed, or no hash was stored to compare with, cached data
+    /// of the old contents is dropped and the sound is analysed again;
+    /// otherwise only the stored file state is updated. Missing files are
+    /// left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - The stored sound.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the up-to-date sound, or an error message.
+    pub async fn refresh(&self, mut sound: Sound) -> Result<Sound, String> {
+        let Ok(metadata) = fs::metadata(&sound.path).await else {
+            return Ok(sound);
         };
+        if sound.file_size == Some(metadata.len() as i64)
+            && sound.modified_at == Some(modified_secs(&metadata))
+        {
+            return Ok(sound);
+        }
 
-        if let Err(e) = self.repo.insert(sound).await {
+        let hash = self.cache.content_hash(&sound.path).await?;
+        match sound.content_hash.clone() {
+            Some(previous) if previous == hash => {
+                self.record_file_state(&mut sound).await?;
+                self.repo
+                    .update_file_state(&sound)
+                    .await
+                    .map_err(|e| format!("Error storing file state: {}", e))?;
+            }
+            previous => {
+                log(
+                    LogLevel::Info,
+                    "Importer::refresh",
+                    &format!("'{}' changed on disk, analysing it again", sound.path),
+                );
+                if let Some(previous) = previous {
+                    self.cache.invalidate(&previous).await;
+                }
+                self.reanalyze(&mut sound).await?;
+            }
+        }
+
+        Ok(sound)
+    }
+
+    /// Analyses a stored sound and updates its row and fingerprint.
+    async fn reanalyze(&self, sound: &mut Sound) -> Result<(), String> {
+        let id = sound
+            .id
+            .ok_or_else(|| format!("'{}' is not stored", sound.name))?;
+
+        let analysis = self.analyze(&sound.path).await?;
+        analysis.apply_to(sound, &self.settings.get().analysis);
+        self.record_file_state(sound).await?;
+        self.repo
+            .update_analysis(sound)
+            .await
+            .map_err(|e| format!("Error storing analysis: {}", e))?;
+        self.repo
+            .update_file_state(sound)
+            .await
+            .map_err(|e| format!("Error storing file state: {}", e))?;
+
+        match analysis.fingerprint {
+            Some(vector) => self.fingerprints.insert(id, vector).await?,
+            None => self.fingerprints.remove(id).await?,
+        }
+
+        Ok(())
+    }
+
+    /// Reads the current size, modification time and content hash of a sound's file.
+    async fn record_file_state(&self, sound: &mut Sound) -> Result<(), String> {
+        let metadata = fs::metadata(&sound.path)
+            .await
+            .map_err(|e| format!("Error reading '{}': {}", sound.path, e))?;
+        sound.content_hash = Some(self.cache.content_hash(&sound.path).await?);
+        sound.file_size = Some(metadata.len() as i64);
+        sound.modified_at = Some(modified_secs(&metadata));
+        Ok(())
+    }
+
+    /// Analyses a file, reusing the cached result for identical contents.
+    ///
+    /// Results are cached per minimum tempo confidence, the only setting
+    /// that changes them.
+    async fn analyze(&self, path: &str) -> Result<Analysis, String> {
+        let settings = self.settings.get().analysis;
+        let key = match self.cache.content_key(path).await {
+            Ok(content) => Some(format!(
+                "{}-analysis-v{}-{}",
+                content, ANALYSIS_CACHE_VERSION, settings.min_tempo_confidence
+            )),
+            Err(e) => {
+                log(LogLevel::Warn, "Importer::analyze", &e);
+                None
+            }
+        };
+
+        if let Some(key) = &key {
+            if let Some(analysis) = self.cache.get_json(key).await {
+                return Ok(analysis);
+            }
+        }
+
+        let analysis = analyze_file(path, self.cache.buffers(), settings).await?;
+        if let Some(key) = key {
+            self.cache.put_json(key, &analysis, true).await;
+        }
+        Ok(analysis)
+    }
+
+    pub async fn export_trimmed(self: &Arc<Self>, id: i64) -> Result<String, String> {
+        let sound = self
+            .repo
+            .get(id)
+            .await
+            .map_err(|e| format!("Error fetching sound: {}", e))?;
+        let sound = self.refresh(sound).await?;
+
+        let source_path = sound.path.clone();
+        let base = format!("{}_trimmed", sound.name);
+        let (name, target) = tokio::task::spawn_blocking(move || {
+            let audio = decode_file(&source_path)?;
+            let points = detect_silence(&audio, SILENCE_THRESHOLD_DB)
+                .ok_or_else(|| format!("'{}' is entirely silent", source_path))?;
+            let (start, end) = points.frame_range(audio.sample_rate, audio.frames());
+
+            // Claim a file name no other file has, so earlier exports and
+            // unrelated files are never overwritten.
+            let source = Path::new(&source_path);
+            let mut name = base.clone();
+            let mut attempt = 1;
+            let target = loop {
+                let target = source.with_file_name(format!("{}.wav", name));
+                match std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&target)
+                {
+                    Ok(_) => break target.to_string_lossy().into_owned(),
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        attempt += 1;
+                        name = format!("{}-{}", base, attempt);
+                    }
+                    Err(e) => return Err(format!("Error creating '{}': {}", target.display(), e)),
+                }
+            };
+            if let Err(e) = write_wav(&target, &trim(&audio, start, end)) {
+                let _ = std::fs::remove_file(&target);
+                return Err(e);
+            }
+            Ok((name, target))
+        })
+        .await
+        .map_err(|e| format!("Trim task failed: {}", e))??;
+
+        log(
+            LogLevel::Info,
+            "Importer::export_trimmed",
+            &format!("Wrote trimmed copy of '{}' to '{}'", sound.name, target),
+        );
+
+        self.import_sound(&name, &target).await?;
+        Ok(target)
+    }
+
+    pub async fn import_directory(
+        self: &Arc<Self>,
+        options: &ImportPath,
+    ) -> Result<RescanSummary, String> {
+        self.rescan(std::slice::from_ref(options)).await
+    }
+
+    /// Brings the library in line with the files below import paths.
+    ///
+    /// Volumes are checked first and moved files are relocated. Then every
+    /// path is walked: new files are imported, known files that changed are
+    /// analysed again, and sounds whose file is gone are flagged as missing.
+    /// Import paths that do not exist right now are skipped. Progress is
+    /// reported to the listener after every file.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The import paths to scan.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a summary of the changes, or an error message.
+    pub async fn rescan(self: &Arc<Self>, entries: &[ImportPath]) -> Result<RescanSummary, String> {
+        self.sync_volumes().await?;
+        let mut summary = RescanSummary {
+            relocated: self.relocate_missing(entries).await?.len(),
+            ..RescanSummary::default()
+        };
+
+        let known: HashMap<String, Sound> = self
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?
+            .into_iter()
+            .map(|sound| (sound.path.clone(), sound))
+            .collect();
+
+        for entry in entries {
+            if !Path::new(&entry.path).exists() {
+                continue;
+            }
+            let filter = ImportFilter::new(entry)?;
+            let files = scan_audio_files(entry).await?;
+            let found: HashSet<String> = files.iter().map(|file| file.path.clone()).collect();
+            let total = files.len();
+            let processed = AtomicUsize::new(0);
+
+            let outcomes: Vec<ScanOutcome> = stream::iter(files)
+                .map(|file| {
+                    let existing = known.get(&file.path).cloned();
+                    let processed = &processed;
+                    async move {
+                        let outcome = self.rescan_file(&file, existing).await;
+                        self.report(&ImportProgress {
+                            root: entry.path.clone(),
+                            path: file.path,
+                            processed: processed.fetch_add(1, Ordering::Relaxed) + 1,
+                            total,
+                        });
+                        outcome
+                    }
+                })
+                .buffer_unordered(self.settings.get().import.concurrency)
+                .collect()
+                .await;
+            for outcome in outcomes {
+                match outcome {
+                    ScanOutcome::Added => summary.added += 1,
+                    ScanOutcome::Updated => summary.updated += 1,
+                    ScanOutcome::Unchanged => {}
+                }
+            }
+
+            let gone: Vec<i64> = known
+                .values()
+                .filter(|sound| {
+                    matches!(sound.status, SoundStatus::Ok | SoundStatus::Empty)
+                        && !found.contains(&sound.path)
+                        && filter.accepts(Path::new(&sound.path))
+                        && !Path::new(&sound.path).exists()
+                })
+                .filter_map(|sound| sound.id)
+                .collect();
+            if !gone.is_empty() {
+                self.repo
+                    .update_status(&gone, SoundStatus::Missing)
+                    .await
+                    .map_err(|e| format!("Error updating status: {}", e))?;
+                summary.missing += gone.len();
+            }
+        }
+
+        log(
+            LogLevel::Info,
+            "Importer::rescan",
+            &format!(
+                "Rescanned {} import paths: {} added, {} updated, {} relocated, {} missing",
+                entries.len(),
+                summary.added,
+                summary.updated,
+                summary.relocated,
+                summary.missing
+            ),
+        );
+        Ok(summary)
+    }
+
+    /// Imports a new file or refreshes a known one.
+    async fn rescan_file(
+        self: &Arc<Self>,
+        file: &ScannedFile,
+        existing: Option<Sound>,
+    ) -> ScanOutcome {
+        let result = match existing {
+            Some(mut sound) => {
+                let previous = sound.content_hash.clone();
+                if let Err(e) = self.mark_available(&mut sound).await {
+                    log(LogLevel::Warn, "Importer::rescan_file", &e);
+                }
+                self.refresh(sound).await.map(|sound| {
+                    if previous.is_some() && sound.content_hash != previous {
+                        ScanOutcome::Updated
+                    } else {
+                        ScanOutcome::Unchanged
+                    }
+                })
+            }
+            None => self
+                .import_sound(&file.name, &file.path)
+                .await
+                .map(|added| {
+                    if added {
+                        ScanOutcome::Added
+                    } else {
+                        ScanOutcome::Unchanged
+                    }
+                }),
+        };
+
+        result.unwrap_or_else(|e| {
             log(
-                LogLevel::Error,
-                "Importer::import_sound",
-                &format!("Error importing '{}': {}", name, e),
+                LogLevel::Warn,
+                "Importer::rescan_file",
+                &format!("Error scanning '{}': {}", file.path, e),
             );
-        } else {
-            self.cache
-                .cache_sound(name.to_string(), path.to_string())
-                .await;
+            ScanOutcome::Unchanged
+        })
+    }
+
+    fn report(&self, progress: &ImportProgress) {
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener(progress);
         }
+    }
 
-        Ok(())
+    /// Finds the new location of sounds whose files are missing.
+    ///
+    /// Audio files below the given roots that are not in the library yet are
+    /// matched to missing sounds by size and content hash. Sounds stored
+    /// before content hashes were recorded are matched by file name and
+    /// duration instead. Matched sounds keep their ID, tags and favourite
+    /// status; only their name and path change.
+    ///
+    /// # Arguments
+    ///
+    /// * `roots` - The import paths to look for moved files in.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the relocated sounds, or an error message.
+    pub async fn relocate_missing(&self, roots: &[ImportPath]) -> Result<Vec<Sound>, String> {
+        let sounds = self
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        let known: HashSet<String> = sounds.iter().map(|sound| sound.path.clone()).collect();
+        let mut missing: Vec<Sound> = sounds
+            .into_iter()
+            .filter(|sound| {
+                sound.status != SoundStatus::Offline && !Path::new(&sound.path).exists()
+            })
+            .collect();
+
+        let mut relocated = vec![];
+        for root in roots {
+            if missing.is_empty() {
+                break;
+            }
+            let files = match scan_audio_files(root).await {
+                Ok(files) => files,
+                Err(e) => {
+                    log(LogLevel::Warn, "Importer::relocate_missing", &e);
+                    continue;
+                }
+            };
+
+            for file in files {
+                if known.contains(&file.path) {
+                    continue;
+                }
+                let Some(index) = self.find_moved(&missing, &file).await else {
+                    continue;
+                };
+
+                let mut sound = missing.swap_remove(index);
+                log(
+                    LogLevel::Info,
+                    "Importer::relocate_missing",
+                    &format!("'{}' moved to '{}'", sound.path, file.path),
+                );
+                sound.name = file.name;
+                sound.path = file.path;
+                self.store_location(&mut sound).await?;
+                relocated.push(sound);
+                if missing.is_empty() {
+                    break;
+                }
+            }
+        }
+
+        Ok(relocated)
     }
 
-    pub async fn import_directory(self: &Arc<Self>, root_path: &str) -> Result<(), String> {
-        let mut stack = vec![root_path.to_string()];
-        let mut tasks = vec![];
+    /// Points missing sounds below a directory to the same relative path
+    /// below another directory.
+    ///
+    /// Only sounds whose file exists at the new location are changed. Sounds
+    /// whose contents differ there are analysed again. Files that already
+    /// belong to another sound, for example because the new folder was
+    /// imported before, are left alone and the missing sound is unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_root` - The directory the missing files used to be in.
+    /// * `new_root` - The directory the files are in now.
+    /// * `ids` - The sounds to relink, or `None` for all of them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the relinked sounds, or an error message.
+    pub async fn relink(
+        &self,
+        old_root: &str,
+        new_root: &str,
+        ids: Option<&[i64]>,
+    ) -> Result<Vec<Sound>, String> {
+        let sounds = self
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?
+            .into_iter()
+            .filter(|sound| ids.is_none_or(|ids| sound.id.is_some_and(|id| ids.contains(&id))));
 
-        while let Some(dir_path) = stack.pop() {
-            let mut entries = fs::read_dir(&dir_path)
+        let mut relinked = vec![];
+        let mut taken = 0;
+        for mut sound in sounds {
+            let old_path = Path::new(&sound.path);
+            if old_path.exists() {
+                continue;
+            }
+            let Ok(relative) = old_path.strip_prefix(old_root) else {
+                continue;
+            };
+            let new_path = Path::new(new_root).join(relative);
+            if !new_path.is_file() {
+                continue;
+            }
+            let new_path = new_path.to_string_lossy().into_owned();
+            if self
+                .repo
+                .exists(&new_path)
                 .await
-                .map_err(|e| format!("Error reading directory '{}': {}", dir_path, e))?;
+                .map_err(|e| format!("DB check failed: {}", e))?
+            {
+                taken += 1;
+                continue;
+            }
 
-            while let Some(entry) = entries
-                .next_entry()
+            sound.path = new_path;
+            self.repo
+                .update(&sound)
                 .await
-                .map_err(|e| format!("Error iterating directory '{}': {}", dir_path, e))?
+                .map_err(|e| format!("Error updating sound: {}", e))?;
+            self.mark_available(&mut sound).await?;
+            relinked.push(self.refresh(sound).await?);
+        }
+
+        log(
+            LogLevel::Info,
+            "Importer::relink",
+            &format!(
+                "Relinked {} sounds from '{}' to '{}', skipped {} already in the library",
+                relinked.len(),
+                old_root,
+                new_root,
+                taken
+            ),
+        );
+        Ok(relinked)
+    }
+
+    /// Returns the index of the missing sound a newly found file is a moved copy of.
+    async fn find_moved(&self, missing: &[Sound], file: &ScannedFile) -> Option<usize> {
+        let size = fs::metadata(&file.path).await.ok()?.len() as i64;
+        let candidates: Vec<usize> = (0..missing.len())
+            .filter(|&i| missing[i].file_size.is_none_or(|known| known == size))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        if candidates
+            .iter()
+            .any(|&i| missing[i].content_hash.is_some())
+        {
+            let hash = self.cache.content_hash(&file.path).await.ok()?;
+            if let Some(&index) = candidates
+                .iter()
+                .find(|&&i| missing[i].content_hash.as_deref() == Some(hash.as_str()))
             {
-                let path = entry.path();
-
-                if path.is_dir() {
-                    stack.push(path.to_string_lossy().into_owned());
-                } else if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                    if ext.eq_ignore_ascii_case("mp3") || ext.eq_ignore_ascii_case("wav") {
-                        let name = path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-                        let path_str = path.to_string_lossy().into_owned();
-                        tasks.push({
-                            let importer = Arc::clone(self);
-                            async move { importer.import_sound(&name, &path_str).await }
-                        });
-                    }
-                }
+                return Some(index);
             }
         }
 
-        join_all(tasks).await;
+        let legacy: Vec<usize> = candidates
+            .into_iter()
+            .filter(|&i| missing[i].content_hash.is_none() && missing[i].name == file.name)
+            .collect();
+        if legacy.is_empty() {
+            return None;
+        }
+        let duration = probe_duration(&file.path).await?;
+        legacy.into_iter().find(|&i| {
+            missing[i]
+                .duration
+                .is_some_and(|known| (known - duration).abs() < DURATION_TOLERANCE_SECS)
+        })
+    }
 
+    /// Stores the new name and path of a sound along with its file state.
+    async fn store_location(&self, sound: &mut Sound) -> Result<(), String> {
+        self.record_file_state(sound).await?;
+        self.repo
+            .update(sound)
+            .await
+            .map_err(|e| format!("Error updating sound: {}", e))?;
+        self.repo
+            .update_file_state(sound)
+            .await
+            .map_err(|e| format!("Error storing file state: {}", e))?;
+        self.mark_available(sound).await
+    }
+
+    /// Resets the status of a sound whose file was found again.
+    async fn mark_available(&self, sound: &mut Sound) -> Result<(), String> {
+        if let (Some(id), false) = (sound.id, sound.status == SoundStatus::Ok) {
+            self.repo
+                .update_status(&[id], SoundStatus::Ok)
+                .await
+                .map_err(|e| format!("Error updating status: {}", e))?;
+            sound.status = SoundStatus::Ok;
+        }
         Ok(())
     }
+
+    /// Marks sounds offline or online depending on whether the volumes of
+    /// the import paths are attached.
+    ///
+    /// Sounds on an absent volume are marked offline and kept. When the
+    /// volume is attached again they are marked available, and if it is now
+    /// mounted somewhere else their paths are moved to the new mount point.
+    /// Import paths without a known volume are assigned one.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing whether any sound changed, or an error message.
+    pub async fn sync_volumes(&self) -> Result<bool, String> {
+        let volumes = self.settings.import_volumes(&self.library);
+        let mut changed = false;
+
+        for root in self.settings.get_import_paths(&self.library) {
+            let Some(volume) = volumes.get(&root) else {
+                if let Some(volume) = volume_of(&root) {
+                    self.settings
+                        .set_import_volume(&self.library, &root, volume);
+                }
+                continue;
+            };
+
+            let Some(mount_point) = locate(volume) else {
+                let ids = self.sounds_below(&root, false, |_| true).await?;
+                if !ids.is_empty() {
+                    log(
+                        LogLevel::Info,
+                        "Importer::sync_volumes",
+                        &format!(
+                            "Volume of '{}' is absent, marking {} sounds offline",
+                            root,
+                            ids.len()
+                        ),
+                    );
+                    self.repo
+                        .update_status(&ids, SoundStatus::Offline)
+                        .await
+                        .map_err(|e| format!("Error updating status: {}", e))?;
+                    changed = true;
+                }
+                continue;
+            };
+
+            // The volume may also still be mounted at its old place, or
+            // another one mounted there, so the import path only moves when
+            // the old one is gone and the new one exists.
+            let mut root = root;
+            let moved = rebase(&root, &volume.mount_point, &mount_point).filter(|new_root| {
+                mount_point != volume.mount_point
+                    && !Path::new(&root).exists()
+                    && Path::new(new_root).exists()
+            });
+            if let Some(new_root) = moved {
+                log(
+                    LogLevel::Info,
+                    "Importer::sync_volumes",
+                    &format!("'{}' is now mounted at '{}'", root, new_root),
+                );
+                self.relink(&root, &new_root, None).await?;
+                self.settings
+                    .replace_import_path(&self.library, &root, new_root.clone());
+                self.settings.set_import_volume(
+                    &self.library,
+                    &new_root,
+                    Volume {
+                        mount_point,
+                        uuid: volume.uuid.clone(),
+                    },
+                );
+                root = new_root;
+                changed = true;
+            }
+
+            let ids = self
+                .sounds_below(&root, true, |sound| Path::new(&sound.path).exists())
+                .await?;
+            if !ids.is_empty() {
+                log(
+                    LogLevel::Info,
+                    "Importer::sync_volumes",
+                    &format!("Reactivating {} sounds below '{}'", ids.len(), root),
+                );
+                self.repo
+                    .update_status(&ids, SoundStatus::Ok)
+                    .await
+                    .map_err(|e| format!("Error updating status: {}", e))?;
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Starts the background task that keeps sound availability in sync
+    /// with attached volumes.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - Called whenever sounds went offline or came back.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the task, which runs until it is aborted.
+    pub fn start_volume_watch(self: &Arc<Self>, listener: LibraryListener) -> JoinHandle<()> {
+        let importer = Arc::clone(self);
+        let poll_interval = Duration::from_secs(self.settings.get().import.volume_poll_secs);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                match importer.sync_volumes().await {
+                    Ok(true) => listener(),
+                    Ok(false) => {}
+                    Err(e) => log(LogLevel::Warn, "Importer::start_volume_watch", &e),
+                }
+            }
+        })
+    }
+
+    /// Returns the IDs of the sounds below a directory that match a condition.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory.
+    /// * `offline` - Whether to look at the sounds marked offline or the others.
+    /// * `condition` - Further narrows down the sounds.
+    async fn sounds_below(
+        &self,
+        root: &str,
+        offline: bool,
+        condition: impl Fn(&Sound) -> bool,
+    ) -> Result<Vec<i64>, String> {
+        let sounds = self
+            .repo
+            .get_below(root, offline)
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+        Ok(sounds
+            .iter()
+            .filter(|sound| condition(sound))
+            .filter_map(|sound| sound.id)
+            .collect())
+    }
+
+    /// Checks the file of every sound and stores the results.
+    ///
+    /// Sounds marked offline stay offline while their file is missing.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the report, or an error message.
+    pub async fn check_health(&self) -> Result<HealthReport, String> {
+        let sounds = self
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error fetching sounds: {}", e))?;
+
+        let checked: Vec<(Sound, SoundStatus)> = stream::iter(sounds)
+            .map(|sound| async move {
+                let path = sound.path.clone();
+                let status = tokio::task::spawn_blocking(move || check_file(&path))
+                    .await
+                    .unwrap_or(SoundStatus::Unreadable);
+                (sound, status)
+            })
+            .buffer_unordered(HEALTH_CHECK_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut report = HealthReport {
+            checked: checked.len(),
+            issues: vec![],
+        };
+        let mut changes: HashMap<SoundStatus, Vec<i64>> = HashMap::new();
+        for (sound, mut status) in checked {
+            let Some(id) = sound.id else {
+                continue;
+            };
+            if sound.status == SoundStatus::Offline && status == SoundStatus::Missing {
+                status = SoundStatus::Offline;
+            }
+            if status != sound.status {
+                changes.entry(status).or_default().push(id);
+            }
+            if status != SoundStatus::Ok {
+                report.issues.push(HealthIssue {
+                    id,
+                    name: sound.name,
+                    path: sound.path,
+                    status,
+                });
+            }
+        }
+
+        for (status, ids) in changes {
+            self.repo
+                .update_status(&ids, status)
+                .await
+                .map_err(|e| format!("Error updating status: {}", e))?;
+        }
+        report.issues.sort_by(|a, b| a.path.cmp(&b.path));
+
+        log(
+            LogLevel::Info,
+            "Importer::check_health",
+            &format!(
+                "Checked {} sounds, {} with problems",
+                report.checked,
+                report.issues.len()
+            ),
+        );
+        Ok(report)
+    }
+
+    /// Marks sounds selected from a health report as offline.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the selected sounds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of sounds changed, or an error message.
+    pub async fn mark_offline(&self, ids: &[i64]) -> Result<usize, String> {
+        self.repo
+            .update_status(ids, SoundStatus::Offline)
+            .await
+            .map_err(|e| format!("Error updating status: {}", e))?;
+        Ok(ids.len())
+    }
+}
+
+/// Reads the duration of an audio file from its header.
+async fn probe_duration(path: &str) -> Option<f64> {
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || AudioStream::open(&path).ok()?.duration())
+        .await
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::cache_module::CacheCapacity;
+    use crate::db::fingerprint::FingerprintRepository;
+    use crate::db::schema::migrate;
+    use crate::db::sound::SoundEdit;
+    use crate::settings::manager::DEFAULT_LIBRARY;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::path::PathBuf;
+
+    /// Creates an importer over an in-memory database.
+    async fn importer(dir: &Path) -> (Arc<Importer>, Arc<SoundRepository>) {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        migrate(&pool, None).await.unwrap();
+        let pool = Arc::new(pool);
+
+        let repo = Arc::new(SoundRepository::new(Arc::clone(&pool), Arc::clone(&pool)));
+        let cache = Arc::new(Cache::new(
+            dir.join("cache"),
+            CacheCapacity::from_megabytes(16, 0),
+        ));
+        let fingerprints =
+            FingerprintIndex::load(FingerprintRepository::new(Arc::clone(&pool), pool))
+                .await
+                .unwrap();
+        let importer = Importer::new(
+            Arc::clone(&repo),
+            cache,
+            Arc::new(fingerprints),
+            Arc::new(SettingsManager::load(dir.join("config.json"))),
+            DEFAULT_LIBRARY.to_string(),
+        );
+        (importer, repo)
+    }
+
+    /// Writes a short mono WAV file, creating its directory.
+    fn write_tone(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for n in 0..4410 {
+            let t = n as f32 / 44100.0;
+            let sample = (2.0 * std::f32::consts::PI * 440.0 * t).sin() * 8000.0;
+            writer.write_sample(sample as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    fn path_str(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    #[tokio::test]
+    async fn relink_skips_files_already_in_the_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let (importer, repo) = importer(dir.path()).await;
+        let old_root = dir.path().join("old");
+        let new_root = dir.path().join("new");
+        let moved: PathBuf = new_root.join("kicks/kick.wav");
+        let imported: PathBuf = new_root.join("snare.wav");
+        write_tone(&moved);
+        write_tone(&imported);
+
+        let kick = repo
+            .insert(Sound::new(
+                "kick.wav",
+                &path_str(&old_root.join("kicks/kick.wav")),
+            ))
+            .await
+            .unwrap();
+        let snare = repo
+            .insert(Sound::new(
+                "snare.wav",
+                &path_str(&old_root.join("snare.wav")),
+            ))
+            .await
+            .unwrap();
+        repo.insert(Sound::new("snare.wav", &path_str(&imported)))
+            .await
+            .unwrap();
+
+        let relinked = importer
+            .relink(&path_str(&old_root), &path_str(&new_root), None)
+            .await
+            .unwrap();
+
+        assert_eq!(relinked.len(), 1);
+        assert_eq!(relinked[0].id, Some(kick));
+        assert_eq!(repo.get(kick).await.unwrap().path, path_str(&moved));
+        assert_eq!(
+            repo.get(snare).await.unwrap().path,
+            path_str(&old_root.join("snare.wav"))
+        );
+    }
+
+    #[tokio::test]
+    async fn rescans_leave_deleted_sounds_to_be_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let (importer, repo) = importer(dir.path()).await;
+        let root = dir.path().join("library");
+        write_tone(&root.join("kick.wav"));
+        let entries = [ImportPath::new(path_str(&root))];
+
+        assert_eq!(importer.rescan(&entries).await.unwrap().added, 1);
+        let id = repo.get_all().await.unwrap()[0].id.unwrap();
+        let delete = SoundEdit::Delete {
+            trash_files: false,
+            delete_untrashable_files: false,
+        };
+        repo.edit_many(&[id], &delete).await.unwrap();
+
+        assert_eq!(importer.rescan(&entries).await.unwrap().added, 0);
+        assert!(repo.get_all().await.unwrap().is_empty());
+
+        assert_eq!(repo.restore(&[id]).await.unwrap(), vec![id]);
+        let sounds = repo.get_all().await.unwrap();
+        assert_eq!(sounds.len(), 1);
+        assert_eq!(sounds[0].id, Some(id));
+    }
 }
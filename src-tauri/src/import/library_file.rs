@@ -0,0 +1,425 @@
+use crate::db::sound::{Sound, SoundStatus};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Version of the library file layout. Files written by newer versions are
+/// rejected.
+pub const LIBRARY_FILE_VERSION: u32 = 1;
+
+/// Columns written to CSV library files, in order.
+const CSV_COLUMNS: [&str; 7] = [
+    "path",
+    "name",
+    "content_hash",
+    "is_favorite",
+    "rating",
+    "tags",
+    "collection",
+];
+
+/// Separates the tags within the `tags` column of a CSV library file. Tags
+/// containing it are split in two when the file is read back.
+const CSV_TAG_SEPARATOR: char = ';';
+
+/// The file formats a library can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryFormat {
+    /// Every stored field of each sound, including analysis results.
+    Json,
+    /// One row per sound with only the curated fields, for spreadsheets.
+    Csv,
+}
+
+impl LibraryFormat {
+    /// Picks the format of a library file from its extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// `Csv` for `.csv` files and `Json` for everything else.
+    pub fn of_file(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => LibraryFormat::Csv,
+            _ => LibraryFormat::Json,
+        }
+    }
+}
+
+/// A sound as stored in a library file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    /// The sound. Its path is relative to the import root containing it,
+    /// and fields only meaningful in the exporting database are cleared.
+    #[serde(flatten)]
+    pub sound: Sound,
+    /// The name of the collection the sound belongs to.
+    #[serde(default)]
+    pub collection: Option<String>,
+}
+
+impl LibraryEntry {
+    /// Prepares a stored sound for export.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound` - The stored sound.
+    /// * `roots` - The import roots of the library.
+    /// * `collection` - The name of the sound's collection.
+    pub fn export(mut sound: Sound, roots: &[String], collection: Option<String>) -> Self {
+        sound.path = relative_path(&sound.path, roots);
+        sound.id = None;
+        sound.status = SoundStatus::Ok;
+        sound.collection_id = None;
+        sound.deleted_at = None;
+        sound.trashed_path = None;
+        Self { sound, collection }
+    }
+}
+
+/// The contents of a library file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryFile {
+    /// The layout version the file was written with.
+    pub version: u32,
+    /// When the file was written, as a Unix timestamp in seconds.
+    pub exported_at: i64,
+    /// The names of all collections, including empty ones.
+    pub collections: Vec<String>,
+    /// The exported sounds.
+    pub sounds: Vec<LibraryEntry>,
+}
+
+impl LibraryFile {
+    /// Serialises the library in the given format.
+    ///
+    /// CSV files only keep the curated fields of each sound and the
+    /// collections that have sounds.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the file contents, or an error message.
+    pub fn write(&self, format: LibraryFormat) -> Result<String, String> {
+        match format {
+            LibraryFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| format!("Error serialising library: {}", e)),
+            LibraryFormat::Csv => {
+                let mut out = String::new();
+                push_csv_row(&mut out, CSV_COLUMNS);
+                for entry in &self.sounds {
+                    let sound = &entry.sound;
+                    let tags = sound.tags.0.join(&format!("{} ", CSV_TAG_SEPARATOR));
+                    let rating = sound.rating.map(|r| r.to_string()).unwrap_or_default();
+                    push_csv_row(
+                        &mut out,
+                        [
+                            sound.path.as_str(),
+                            sound.name.as_str(),
+                            sound.content_hash.as_deref().unwrap_or_default(),
+                            if sound.is_favorite { "true" } else { "false" },
+                            rating.as_str(),
+                            tags.as_str(),
+                            entry.collection.as_deref().unwrap_or_default(),
+                        ],
+                    );
+                }
+                Ok(out)
+            }
+        }
+    }
+
+    /// Parses a library file.
+    ///
+    /// CSV files need a header row with at least a `path` column. Other
+    /// columns may be missing or in any order, and unknown ones are ignored,
+    /// so files edited in a spreadsheet can be read back.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The contents of the file.
+    /// * `format` - The format of the file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the library, or an error message describing
+    /// the first problem found.
+    pub fn parse(contents: &str, format: LibraryFormat) -> Result<Self, String> {
+        let library = match format {
+            LibraryFormat::Json => serde_json::from_str::<LibraryFile>(contents)
+                .map_err(|e| format!("Invalid library file: {}", e))?,
+            LibraryFormat::Csv => parse_csv_library(contents)?,
+        };
+        if library.version > LIBRARY_FILE_VERSION {
+            return Err(format!(
+                "The library file was written by a newer version (format {})",
+                library.version
+            ));
+        }
+        Ok(library)
+    }
+}
+
+/// The outcome of importing a library file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LibraryImportSummary {
+    /// Number of sounds in the file.
+    pub entries: usize,
+    /// Entries matched to a sound in the library by relative path.
+    pub matched_by_path: usize,
+    /// Entries matched to a sound in the library by content hash.
+    pub matched_by_hash: usize,
+    /// Entries whose file was found below an import root and imported.
+    pub imported: usize,
+    /// Number of sounds whose favorite flag, tags, rating or collection changed.
+    pub updated: usize,
+    /// Number of collections that did not exist yet.
+    pub created_collections: usize,
+    /// Paths of the entries no local file was found for.
+    pub unmatched: Vec<String>,
+}
+
+/// Returns the path of a file relative to the innermost import root
+/// containing it, with `/` as separator.
+///
+/// # Arguments
+///
+/// * `path` - The absolute path of the file.
+/// * `roots` - The import roots.
+///
+/// # Returns
+///
+/// The relative path, or the full path if no root contains the file.
+pub fn relative_path(path: &str, roots: &[String]) -> String {
+    roots
+        .iter()
+        .filter_map(|root| Path::new(path).strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+        .unwrap_or(Path::new(path))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Reads a CSV library file into library entries.
+fn parse_csv_library(contents: &str) -> Result<LibraryFile, String> {
+    let mut rows = parse_csv(contents)?.into_iter();
+    let header = rows.next().ok_or("The library file is empty")?;
+    let columns: HashMap<String, usize> = header
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_lowercase(), index))
+        .collect();
+    if !columns.contains_key("path") {
+        return Err("The library file has no 'path' column".to_string());
+    }
+
+    let mut sounds = vec![];
+    for (index, row) in rows.enumerate() {
+        let number = index + 2;
+        let field = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|&column| row.get(column))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let Some(path) = field("path") else {
+            continue;
+        };
+
+        let name = field("name").map(str::to_string).unwrap_or_else(|| {
+            Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.to_string())
+        });
+        let mut sound = Sound::new(&name, path);
+        sound.added_at = None;
+        sound.content_hash = field("content_hash").map(str::to_lowercase);
+        sound.is_favorite = match field("is_favorite").map(str::to_lowercase).as_deref() {
+            None | Some("false" | "no" | "0") => false,
+            Some("true" | "yes" | "1") => true,
+            Some(other) => {
+                return Err(format!("Row {}: invalid is_favorite '{}'", number, other));
+            }
+        };
+        sound.rating = match field("rating") {
+            None => None,
+            Some(value) => match value.parse::<i64>() {
+                Ok(rating) if (1..=5).contains(&rating) => Some(rating),
+                _ => return Err(format!("Row {}: invalid rating '{}'", number, value)),
+            },
+        };
+        sound.tags = Json(
+            field("tags")
+                .unwrap_or_default()
+                .split(CSV_TAG_SEPARATOR)
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        );
+
+        sounds.push(LibraryEntry {
+            sound,
+            collection: field("collection").map(str::to_string),
+        });
+    }
+
+    let mut collections: Vec<String> = sounds
+        .iter()
+        .filter_map(|entry| entry.collection.clone())
+        .collect();
+    collections.sort();
+    collections.dedup();
+    Ok(LibraryFile {
+        version: LIBRARY_FILE_VERSION,
+        exported_at: 0,
+        collections,
+        sounds,
+    })
+}
+
+/// Appends a row of CSV fields, quoting fields where needed.
+fn push_csv_row<'a>(out: &mut String, fields: impl IntoIterator<Item = &'a str>) {
+    for (index, field) in fields.into_iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+/// Splits CSV text into rows of fields as described by RFC 4180, skipping
+/// empty lines.
+fn parse_csv(contents: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("The library file ends inside a quoted field".to_string());
+    }
+    row.push(field);
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_handles_quotes_and_line_endings() {
+        let rows =
+            parse_csv("\u{feff}a,\"b, c\",\"say \"\"hi\"\"\"\r\n\r\n\"multi\nline\",,x\n").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a", "b, c", "say \"hi\""],
+                vec!["multi\nline", "", "x"],
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_csv_rejects_unterminated_quotes() {
+        assert!(parse_csv("a,\"b\n").is_err());
+    }
+
+    #[test]
+    fn relative_path_uses_the_innermost_root() {
+        let roots = vec!["/samples".to_string(), "/samples/drums".to_string()];
+        assert_eq!(relative_path("/samples/drums/kick.wav", &roots), "kick.wav");
+        assert_eq!(relative_path("/samples/fx/rise.wav", &roots), "fx/rise.wav");
+        assert_eq!(
+            relative_path("/other/snare.wav", &roots),
+            "/other/snare.wav"
+        );
+    }
+
+    #[test]
+    fn csv_library_round_trips_curated_fields() {
+        let mut sound = Sound::new("kick", "/samples/drums/kick.wav");
+        sound.is_favorite = true;
+        sound.rating = Some(4);
+        sound.tags = Json(vec!["punchy".to_string(), "808".to_string()]);
+        sound.content_hash = Some("abc".to_string());
+        let roots = vec!["/samples".to_string()];
+        let library = LibraryFile {
+            version: LIBRARY_FILE_VERSION,
+            exported_at: 0,
+            collections: vec!["Drums, mostly".to_string()],
+            sounds: vec![LibraryEntry::export(
+                sound,
+                &roots,
+                Some("Drums, mostly".to_string()),
+            )],
+        };
+
+        let csv = library.write(LibraryFormat::Csv).unwrap();
+        let parsed = LibraryFile::parse(&csv, LibraryFormat::Csv).unwrap();
+
+        assert_eq!(parsed.collections, vec!["Drums, mostly"]);
+        let entry = &parsed.sounds[0];
+        assert_eq!(entry.sound.path, "drums/kick.wav");
+        assert_eq!(entry.sound.name, "kick");
+        assert!(entry.sound.is_favorite);
+        assert_eq!(entry.sound.rating, Some(4));
+        assert_eq!(entry.sound.tags.0, vec!["punchy", "808"]);
+        assert_eq!(entry.sound.content_hash.as_deref(), Some("abc"));
+        assert_eq!(entry.collection.as_deref(), Some("Drums, mostly"));
+    }
+
+    #[test]
+    fn csv_library_needs_a_path_column_and_valid_values() {
+        assert!(LibraryFile::parse("name\nkick\n", LibraryFormat::Csv).is_err());
+        assert!(LibraryFile::parse("path,rating\nkick.wav,9\n", LibraryFormat::Csv).is_err());
+        let parsed =
+            LibraryFile::parse("PATH,extra\nloops/pad.wav,x\n", LibraryFormat::Csv).unwrap();
+        assert_eq!(parsed.sounds[0].sound.name, "pad");
+    }
+}
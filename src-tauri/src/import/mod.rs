@@ -1 +1,5 @@
+pub mod health;
 pub mod importer;
+pub mod library_file;
+pub mod scanner;
+pub mod volume;
@@ -0,0 +1,318 @@
+use crate::settings::manager::ImportPath;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// File extensions recognised as importable audio, compared case-insensitively.
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav"];
+
+/// An audio file found while scanning a directory.
+#[derive(Debug, Clone)]
+pub struct ScannedFile {
+    /// The file name without its extension.
+    pub name: String,
+    /// The full path of the file.
+    pub path: String,
+}
+
+/// Returns whether a path has an importable audio extension.
+///
+/// # Arguments
+///
+/// * `path` - The path to check.
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// Glob patterns split by whether they match names or relative paths.
+struct Patterns {
+    names: GlobSet,
+    paths: GlobSet,
+    empty: bool,
+}
+
+impl Patterns {
+    fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns {
+            let invalid = |e: globset::Error| format!("Invalid pattern '{}': {}", pattern, e);
+            if pattern.contains('/') {
+                paths.add(
+                    GlobBuilder::new(pattern.trim_start_matches('/'))
+                        .literal_separator(true)
+                        .build()
+                        .map_err(invalid)?,
+                );
+            } else {
+                names.add(Glob::new(pattern).map_err(invalid)?);
+            }
+        }
+
+        Ok(Self {
+            names: names.build().map_err(|e| e.to_string())?,
+            paths: paths.build().map_err(|e| e.to_string())?,
+            empty: patterns.is_empty(),
+        })
+    }
+
+    fn matches(&self, relative: &str) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        self.names.is_match(name) || self.paths.is_match(relative)
+    }
+}
+
+/// Decides which files below an import path are imported.
+pub struct ImportFilter {
+    root: PathBuf,
+    include: Patterns,
+    exclude: Patterns,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    include_hidden: bool,
+}
+
+impl ImportFilter {
+    /// Compiles the rules of an import path.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The import path and its rules.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the filter, or an error message if a pattern is invalid.
+    pub fn new(options: &ImportPath) -> Result<Self, String> {
+        Ok(Self {
+            root: PathBuf::from(&options.path),
+            include: Patterns::new(&options.include)?,
+            exclude: Patterns::new(&options.exclude)?,
+            max_depth: options.max_depth,
+            follow_symlinks: options.follow_symlinks,
+            include_hidden: options.include_hidden,
+        })
+    }
+
+    /// Returns whether a file would be imported by a scan of this path.
+    ///
+    /// Symbolic links along the path are not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    pub fn accepts(&self, path: &Path) -> bool {
+        if path == self.root {
+            return is_audio_file(path);
+        }
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let mut prefix = String::new();
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        for (depth, component) in components.iter().enumerate() {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(component);
+
+            let is_file = depth + 1 == components.len();
+            if !self.visible(component) || self.exclude.matches(&prefix) {
+                return false;
+            }
+            if !is_file && self.max_depth.is_some_and(|max| depth + 1 > max) {
+                return false;
+            }
+        }
+
+        is_audio_file(path) && (self.include.empty || self.include.matches(&prefix))
+    }
+
+    fn visible(&self, name: &str) -> bool {
+        self.include_hidden || !name.starts_with('.')
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+/// Collects the audio files below an import path according to its rules.
+///
+/// # Arguments
+///
+/// * `options` - The import path and its rules.
+///
+/// # Returns
+///
+/// A `Result` containing the files found, or an error message if a pattern
+/// is invalid or a directory cannot be read.
+pub async fn scan_audio_files(options: &ImportPath) -> Result<Vec<ScannedFile>, String> {
+    let filter = ImportFilter::new(options)?;
+    let mut files = vec![];
+
+    if fs::metadata(&filter.root)
+        .await
+        .is_ok_and(|metadata| metadata.is_file())
+    {
+        if filter.accepts(&filter.root) {
+            files.push(scanned(&filter.root));
+        }
+        return Ok(files);
+    }
+
+    let mut visited = HashSet::new();
+    if let Ok(root) = fs::canonicalize(&filter.root).await {
+        visited.insert(root);
+    }
+    let mut stack = vec![(filter.root.clone(), 0)];
+
+    while let Some((dir_path, depth)) = stack.pop() {
+        let mut entries = fs::read_dir(&dir_path)
+            .await
+            .map_err(|e| format!("Error reading directory '{}': {}", dir_path.display(), e))?;
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("Error iterating directory '{}': {}", dir_path.display(), e))?
+        {
+            let path = entry.path();
+            if !filter.visible(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+
+            let Ok(mut file_type) = entry.file_type().await else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                if !filter.follow_symlinks {
+                    continue;
+                }
+                match fs::metadata(&path).await {
+                    Ok(metadata) => file_type = metadata.file_type(),
+                    Err(_) => continue,
+                }
+            }
+
+            let relative = filter.relative(&path);
+            if filter.exclude.matches(&relative) {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                if filter.max_depth.is_some_and(|max| depth + 1 > max) {
+                    continue;
+                }
+                if filter.follow_symlinks {
+                    let Ok(canonical) = fs::canonicalize(&path).await else {
+                        continue;
+                    };
+                    if !visited.insert(canonical) {
+                        continue;
+                    }
+                }
+                stack.push((path, depth + 1));
+            } else if is_audio_file(&path)
+                && (filter.include.empty || filter.include.matches(&relative))
+            {
+                files.push(scanned(&path));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn scanned(path: &Path) -> ScannedFile {
+    ScannedFile {
+        name: path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        path: path.to_string_lossy().into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(configure: impl FnOnce(&mut ImportPath)) -> ImportFilter {
+        let mut options = ImportPath::new("/samples".to_string());
+        configure(&mut options);
+        ImportFilter::new(&options).unwrap()
+    }
+
+    fn accepts(filter: &ImportFilter, path: &str) -> bool {
+        filter.accepts(Path::new(path))
+    }
+
+    #[test]
+    fn accepts_audio_files_below_the_root() {
+        let filter = filter(|_| {});
+        assert!(accepts(&filter, "/samples/kick.wav"));
+        assert!(accepts(&filter, "/samples/drums/snare.MP3"));
+        assert!(!accepts(&filter, "/samples/readme.txt"));
+        assert!(!accepts(&filter, "/elsewhere/kick.wav"));
+    }
+
+    #[test]
+    fn skips_hidden_files_and_folders_unless_enabled() {
+        let hidden = filter(|_| {});
+        assert!(!accepts(&hidden, "/samples/.cache/kick.wav"));
+        assert!(!accepts(&hidden, "/samples/.kick.wav"));
+
+        let shown = filter(|options| options.include_hidden = true);
+        assert!(accepts(&shown, "/samples/.cache/kick.wav"));
+    }
+
+    #[test]
+    fn name_patterns_match_anywhere_and_path_patterns_from_the_root() {
+        let filter = filter(|options| {
+            options.include = vec!["*kick*".to_string(), "loops/*.wav".to_string()];
+            options.exclude = vec!["old".to_string()];
+        });
+        assert!(accepts(&filter, "/samples/drums/big kick.wav"));
+        assert!(accepts(&filter, "/samples/loops/pad.wav"));
+        assert!(!accepts(&filter, "/samples/loops/more/pad.wav"));
+        assert!(!accepts(&filter, "/samples/old/kick.wav"));
+        assert!(!accepts(&filter, "/samples/snare.wav"));
+    }
+
+    #[test]
+    fn limits_the_folder_depth() {
+        let filter = filter(|options| options.max_depth = Some(1));
+        assert!(accepts(&filter, "/samples/kick.wav"));
+        assert!(accepts(&filter, "/samples/drums/kick.wav"));
+        assert!(!accepts(&filter, "/samples/drums/808/kick.wav"));
+    }
+
+    #[test]
+    fn a_file_root_accepts_itself() {
+        let options = ImportPath::new("/samples/kick.wav".to_string());
+        let filter = ImportFilter::new(&options).unwrap();
+        assert!(accepts(&filter, "/samples/kick.wav"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        let mut options = ImportPath::new("/samples".to_string());
+        options.include = vec!["[kick".to_string()];
+        assert!(ImportFilter::new(&options).is_err());
+    }
+}
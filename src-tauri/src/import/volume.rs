@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The volume an import path lives on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Volume {
+    /// Where the volume was mounted when the path was imported.
+    pub mount_point: String,
+    /// The filesystem UUID, if the platform reports one.
+    pub uuid: Option<String>,
+}
+
+/// A currently mounted filesystem.
+#[derive(Debug, Clone)]
+struct Mount {
+    mount_point: PathBuf,
+    uuid: Option<String>,
+}
+
+/// Finds the volume a path lives on.
+///
+/// # Arguments
+///
+/// * `path` - An absolute path.
+///
+/// # Returns
+///
+/// `Some` with the volume whose mount point is the longest prefix of the
+/// path, or `None` if no mounted volume contains it.
+pub fn volume_of(path: &str) -> Option<Volume> {
+    let path = Path::new(path);
+    mounts()
+        .into_iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+        .map(|mount| Volume {
+            mount_point: mount.mount_point.to_string_lossy().into_owned(),
+            uuid: mount.uuid,
+        })
+}
+
+/// Finds where a volume is mounted now.
+///
+/// Volumes with a UUID are found wherever they are mounted, preferring
+/// their original mount point when they are mounted more than once. Volumes
+/// without one are only found at their original mount point.
+///
+/// # Arguments
+///
+/// * `volume` - The volume to look for.
+///
+/// # Returns
+///
+/// `Some` with the current mount point, or `None` if the volume is absent.
+pub fn locate(volume: &Volume) -> Option<String> {
+    locate_in(volume, mounts())
+}
+
+/// Finds where a volume is mounted among the given filesystems.
+fn locate_in(volume: &Volume, mounts: Vec<Mount>) -> Option<String> {
+    let found = match &volume.uuid {
+        Some(uuid) => mounts
+            .into_iter()
+            .filter(|mount| mount.uuid.as_ref() == Some(uuid))
+            .min_by_key(|mount| mount.mount_point != Path::new(&volume.mount_point)),
+        None => mounts
+            .into_iter()
+            .find(|mount| mount.mount_point == Path::new(&volume.mount_point)),
+    };
+    found.map(|mount| mount.mount_point.to_string_lossy().into_owned())
+}
+
+/// Moves a path from one mount point to another.
+///
+/// # Arguments
+///
+/// * `path` - A path below `from`.
+/// * `from` - The old mount point.
+/// * `to` - The new mount point.
+///
+/// # Returns
+///
+/// `Some` with the path below `to`, or `None` if `path` is not below `from`.
+pub fn rebase(path: &str, from: &str, to: &str) -> Option<String> {
+    let relative = Path::new(path).strip_prefix(from).ok()?;
+    Some(Path::new(to).join(relative).to_string_lossy().into_owned())
+}
+
+/// Lists the mounted filesystems with their UUIDs from `/proc/self/mounts`
+/// and `/dev/disk/by-uuid`.
+#[cfg(target_os = "linux")]
+fn mounts() -> Vec<Mount> {
+    use std::collections::HashMap;
+    use std::fs;
+
+    let uuids: HashMap<PathBuf, String> = fs::read_dir("/dev/disk/by-uuid")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let device = fs::canonicalize(entry.path()).ok()?;
+            Some((device, entry.file_name().to_string_lossy().into_owned()))
+        })
+        .collect();
+
+    let Ok(table) = fs::read_to_string("/proc/self/mounts") else {
+        return vec![Mount {
+            mount_point: PathBuf::from("/"),
+            uuid: None,
+        }];
+    };
+
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = unescape_mount_field(fields.next()?);
+            let mount_point = unescape_mount_field(fields.next()?);
+            let uuid = fs::canonicalize(&device)
+                .ok()
+                .and_then(|device| uuids.get(&device).cloned());
+            Some(Mount {
+                mount_point: PathBuf::from(mount_point),
+                uuid,
+            })
+        })
+        .collect()
+}
+
+/// Decodes the octal escapes (`\040` for a space) used in the mount table.
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => out.push(byte as char),
+                Err(_) => {
+                    out.push(c);
+                    out.push_str(&code);
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Lists the root volume and everything mounted below `/Volumes`.
+#[cfg(target_os = "macos")]
+fn mounts() -> Vec<Mount> {
+    let mut mounts = vec![Mount {
+        mount_point: PathBuf::from("/"),
+        uuid: None,
+    }];
+    mounts.extend(
+        std::fs::read_dir("/Volumes")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| Mount {
+                mount_point: entry.path(),
+                uuid: None,
+            }),
+    );
+    mounts
+}
+
+/// Lists the drive letters that currently exist.
+#[cfg(windows)]
+fn mounts() -> Vec<Mount> {
+    (b'A'..=b'Z')
+        .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+        .filter(|root| root.exists())
+        .map(|mount_point| Mount {
+            mount_point,
+            uuid: None,
+        })
+        .collect()
+}
+
+/// Treats the filesystem root as the only volume.
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn mounts() -> Vec<Mount> {
+    vec![Mount {
+        mount_point: PathBuf::from("/"),
+        uuid: None,
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mount(mount_point: &str, uuid: Option<&str>) -> Mount {
+        Mount {
+            mount_point: PathBuf::from(mount_point),
+            uuid: uuid.map(str::to_string),
+        }
+    }
+
+    fn volume(mount_point: &str, uuid: Option<&str>) -> Volume {
+        Volume {
+            mount_point: mount_point.to_string(),
+            uuid: uuid.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn rebase_moves_paths_below_the_old_mount_point() {
+        assert_eq!(
+            rebase("/media/old/drums/kick.wav", "/media/old", "/media/new"),
+            Some(
+                Path::new("/media/new/drums/kick.wav")
+                    .to_string_lossy()
+                    .into_owned()
+            )
+        );
+        assert_eq!(
+            rebase("/media/older/kick.wav", "/media/old", "/media/new"),
+            None
+        );
+    }
+
+    #[test]
+    fn locate_follows_the_uuid_to_a_new_mount_point() {
+        let mounts = vec![mount("/", None), mount("/media/usb1", Some("abcd"))];
+        assert_eq!(
+            locate_in(&volume("/media/usb", Some("abcd")), mounts),
+            Some("/media/usb1".to_string())
+        );
+    }
+
+    #[test]
+    fn locate_prefers_the_recorded_mount_point() {
+        let mounts = vec![
+            mount("/media/copy", Some("abcd")),
+            mount("/media/usb", Some("abcd")),
+        ];
+        assert_eq!(
+            locate_in(&volume("/media/usb", Some("abcd")), mounts),
+            Some("/media/usb".to_string())
+        );
+    }
+
+    #[test]
+    fn locate_without_uuid_only_matches_the_recorded_mount_point() {
+        let mounts = vec![mount("/", None), mount("/media/usb1", None)];
+        assert_eq!(locate_in(&volume("/media/usb", None), mounts.clone()), None);
+        assert_eq!(locate_in(&volume("/", None), mounts), Some("/".to_string()));
+    }
+
+    #[test]
+    fn locate_reports_absent_volumes() {
+        let mounts = vec![mount("/", Some("root"))];
+        assert_eq!(locate_in(&volume("/media/usb", Some("abcd")), mounts), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn mount_fields_are_unescaped() {
+        assert_eq!(
+            unescape_mount_field(r"/media/My\040Drive"),
+            "/media/My Drive"
+        );
+        assert_eq!(unescape_mount_field("/plain"), "/plain");
+    }
+}
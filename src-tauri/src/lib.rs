@@ -1,76 +1,144 @@
-use dirs::config_dir;
 use std::fs;
 use std::sync::Arc;
-use tauri::Builder;
+use tauri::{Builder, Emitter};
 use utils::logger::Logger;
 
+mod analysis;
 mod api;
 mod cache;
 mod db;
 mod import;
+mod playback;
 mod settings;
+mod similarity;
 mod utils;
 
 use crate::utils::logger::{log, LogLevel};
+use crate::utils::paths::{config_path, data_dir};
 use api::handlers::{
-    delete_sound, get_imported_paths, get_sounds, import_directory, import_sound, recache_sounds,
-    remove_imported_path, toggle_favorite, Api,
+    analyze_sound, check_integrity, clear_cache, create_backup, create_collection, create_library,
+    delete_collection, delete_sound, edit_sounds, export_library, export_trimmed_sound,
+    find_similar, get_cache_stats, get_collections, get_history, get_import_path_options,
+    get_imported_paths, get_playback_status, get_preview_target, get_settings, get_sounds,
+    get_trash_retention_days, get_waveform, import_directory, import_library, import_sound,
+    library_health, list_backups, list_libraries, play_sound, purge_deleted_sounds,
+    reanalyze_sounds, recache_sounds, redo, relink_sounds, remove_imported_path, rescan,
+    resolve_health_issues, restore_backup, restore_sounds, search_sounds, seek_playback,
+    set_cache_capacity, set_import_path_options, set_playback_looping, set_playback_volume,
+    set_preview_target, set_rating, set_trash_retention_days, stop_playback, switch_library,
+    toggle_favorite, undo, update_settings, Api,
 };
-use cache::cache_module::Cache;
-use db::connection::DatabasePool;
-use db::sound::SoundRepository;
-use import::importer::Importer;
+use api::library::{Library, LibraryListeners};
+use playback::engine::PlaybackEngine;
+use playback::output::OutputKind;
+use settings::manager::SettingsManager;
 
 #[tokio::main]
 pub async fn run() {
     Logger::clear_file();
 
-    let app_data_path = config_dir().expect("Failed to get AppData directory");
-    let db_path = app_data_path.join("soundlab").join("database.db");
-
-    if !db_path.parent().unwrap().exists() {
-        fs::create_dir_all(db_path.parent().unwrap()).expect("Failed to create directory");
-    }
-
-    let db_pool = Arc::new(
-        DatabasePool::new(db_path.to_str().expect("Invalid path"))
-            .await
-            .expect("Database connection failed"),
+    let data_dir = data_dir();
+    log(
+        LogLevel::Info,
+        "run",
+        &format!(
+            "Using data directory '{}' ({:?})",
+            data_dir.path.display(),
+            data_dir.source
+        ),
     );
+    fs::create_dir_all(&data_dir.path).expect("Failed to create data directory");
 
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS sounds (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            path TEXT NOT NULL,
-            is_favorite BOOLEAN NOT NULL DEFAULT 0, 
-            tags JSON NOT NULL DEFAULT '[]'      
-        )",
-    )
-    .execute(&*db_pool.get_db())
-    .await
-    .expect("Failed to create sounds table");
+    let settings = Arc::new(SettingsManager::load(config_path()));
+    let library = Library::open(&settings, &settings.active_library())
+        .await
+        .expect("Failed to open library");
 
-    let sound_repo = Arc::new(SoundRepository::new(db_pool.get_db()));
-    let cache = Arc::new(Cache::new(100));
-    let importer = Arc::new(Importer::new(sound_repo.clone(), cache.clone()));
+    let player = PlaybackEngine::new(OutputKind::from_env(), library.importer.cache.clone());
+    let playback = settings.get().playback;
+    player.set_volume(playback.volume);
+    player.set_looping(playback.looping);
+    player.start_status_updates();
 
-    let api = Arc::new(Api::new(sound_repo.clone(), Arc::clone(&importer)));
+    let api = Api::new(settings, library, Arc::clone(&player));
 
     log(LogLevel::Info, "run", "Starting application");
 
     Builder::default()
         .plugin(tauri_plugin_dialog::init())
-        .manage((*api).clone())
+        .manage(api.clone())
+        .setup(move |app| {
+            let handle = app.handle().clone();
+            player.set_listener(Box::new(move |status| {
+                let _ = handle.emit("playback-status", status);
+            }));
+            let progress_handle = app.handle().clone();
+            let changed_handle = app.handle().clone();
+            api.set_library_listeners(LibraryListeners {
+                progress: Arc::new(move |progress| {
+                    let _ = progress_handle.emit("import-progress", progress);
+                }),
+                changed: Arc::new(move || {
+                    let _ = changed_handle.emit("library-changed", ());
+                }),
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             import_sound,
             import_directory,
             get_sounds,
+            search_sounds,
+            analyze_sound,
+            get_waveform,
+            export_trimmed_sound,
+            find_similar,
             toggle_favorite,
+            set_rating,
+            edit_sounds,
+            reanalyze_sounds,
+            get_collections,
+            create_collection,
+            delete_collection,
+            export_library,
+            import_library,
+            list_libraries,
+            create_library,
+            switch_library,
+            undo,
+            redo,
+            get_history,
+            restore_sounds,
+            purge_deleted_sounds,
+            get_trash_retention_days,
+            set_trash_retention_days,
+            create_backup,
+            list_backups,
+            restore_backup,
+            check_integrity,
             delete_sound,
             get_imported_paths,
+            get_import_path_options,
+            set_import_path_options,
             remove_imported_path,
-            recache_sounds
+            recache_sounds,
+            rescan,
+            relink_sounds,
+            library_health,
+            resolve_health_issues,
+            get_cache_stats,
+            set_cache_capacity,
+            clear_cache,
+            play_sound,
+            stop_playback,
+            seek_playback,
+            set_playback_volume,
+            set_playback_looping,
+            get_playback_status,
+            get_preview_target,
+            set_preview_target,
+            get_settings,
+            update_settings
         ])
         .run(tauri::generate_context!())
         .expect("Error starting Tauri application");
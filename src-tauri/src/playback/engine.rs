@@ -0,0 +1,624 @@
+use crate::analysis::decoder::DecodedAudio;
+use crate::analysis::stream::AudioStream;
+use crate::cache::buffer_pool::BufferPool;
+use crate::cache::cache_module::Cache;
+use crate::playback::output::{open_output, OutputHandle, OutputKind, RenderFn};
+use crate::playback::streaming::{Reopen, StreamedSource};
+use crate::playback::stretch::{StretchParams, TimeStretcher};
+use crate::utils::logger::{log, LogLevel};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Interval between playback status notifications while playing.
+const STATUS_INTERVAL: Duration = Duration::from_millis(50);
+/// Sounds longer than this are streamed rather than decoded up front, in seconds.
+const STREAM_MIN_SECS: f64 = 30.0;
+
+/// Callback receiving playback status updates.
+pub type StatusListener = Box<dyn Fn(&PlaybackStatus) + Send + Sync>;
+
+/// A snapshot of the player state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlaybackStatus {
+    /// The ID of the sound being played, if any.
+    pub sound_id: Option<i64>,
+    /// Whether audio is currently playing.
+    pub playing: bool,
+    /// Current playback position, in seconds.
+    pub position: f64,
+    /// Duration of the current sound, in seconds.
+    pub duration: f64,
+    /// Output volume, from 0 to 1.
+    pub volume: f32,
+    /// Whether the current sound restarts when it reaches its end.
+    pub looping: bool,
+    /// Tempo change and transposition applied to the current sound, if any.
+    pub stretch: Option<StretchParams>,
+}
+
+/// A sound loaded for playback, converted to the output format.
+struct Voice {
+    sound_id: i64,
+    source: Source,
+}
+
+/// Where the frames of a voice come from.
+enum Source {
+    /// A sound decoded into memory, optionally stretched.
+    Decoded {
+        audio: Arc<DecodedAudio>,
+        position: usize,
+        stretch: Option<(StretchParams, TimeStretcher)>,
+    },
+    /// A long sound decoded in the background while it plays.
+    Streamed(StreamedSource),
+}
+
+impl Voice {
+    /// Fills an interleaved buffer with the next frames, returning `false` at the end.
+    fn render(&mut self, looping: bool, channels: usize, out: &mut [f32]) -> bool {
+        let (audio, position) = match &mut self.source {
+            Source::Streamed(stream) => return stream.render(looping, out),
+            Source::Decoded {
+                audio,
+                stretch: Some((_, stretcher)),
+                ..
+            } => return stretcher.render(audio, looping, out),
+            Source::Decoded {
+                audio, position, ..
+            } => (audio, position),
+        };
+
+        let frames = audio.frames();
+        for frame in out.chunks_exact_mut(channels) {
+            if *position >= frames {
+                if looping && frames > 0 {
+                    *position = 0;
+                } else {
+                    return false;
+                }
+            }
+
+            let start = *position * channels;
+            frame.copy_from_slice(&audio.samples[start..start + channels]);
+            *position += 1;
+        }
+        true
+    }
+
+    /// Returns the position in the source, in frames.
+    fn position(&self) -> usize {
+        match &self.source {
+            Source::Decoded {
+                stretch: Some((_, stretcher)),
+                ..
+            } => stretcher.position(),
+            Source::Decoded { position, .. } => *position,
+            Source::Streamed(stream) => stream.position(),
+        }
+    }
+
+    /// Returns the length of the source, in frames.
+    fn frames(&self) -> usize {
+        match &self.source {
+            Source::Decoded { audio, .. } => audio.frames(),
+            Source::Streamed(stream) => stream.frames(),
+        }
+    }
+
+    /// Returns the stretch applied to the source, if any.
+    fn stretch(&self) -> Option<StretchParams> {
+        match &self.source {
+            Source::Decoded {
+                stretch: Some((params, _)),
+                ..
+            } => Some(*params),
+            _ => None,
+        }
+    }
+
+    /// Moves to a position in the source, clamped to its length.
+    ///
+    /// Streamed sources cannot move in place; for them the stream to open
+    /// at the new position is returned instead.
+    fn seek(&mut self, frame: usize) -> Option<Reopen> {
+        let frame = frame.min(self.frames());
+        match &mut self.source {
+            Source::Decoded {
+                position, stretch, ..
+            } => {
+                *position = frame;
+                if let Some((_, stretcher)) = stretch.as_mut() {
+                    stretcher.seek(frame);
+                }
+                None
+            }
+            Source::Streamed(stream) => Some(stream.reopen_at(frame)),
+        }
+    }
+}
+
+/// Player state shared between the engine and the output thread.
+struct PlayerState {
+    voice: Option<Voice>,
+    volume: f32,
+    looping: bool,
+    sample_rate: u32,
+    channels: usize,
+}
+
+impl PlayerState {
+    /// Fills an interleaved output buffer with the next frames of the voice.
+    fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        let channels = self.channels.max(1);
+        let Some(voice) = self.voice.as_mut() else {
+            return;
+        };
+
+        if !voice.render(self.looping, channels, out) {
+            self.voice = None;
+        }
+        for sample in out.iter_mut() {
+            *sample *= self.volume;
+        }
+    }
+
+    fn status(&self) -> PlaybackStatus {
+        let rate = self.sample_rate.max(1) as f64;
+        PlaybackStatus {
+            sound_id: self.voice.as_ref().map(|v| v.sound_id),
+            playing: self.voice.is_some(),
+            position: self
+                .voice
+                .as_ref()
+                .map_or(0.0, |v| v.position() as f64 / rate),
+            duration: self
+                .voice
+                .as_ref()
+                .map_or(0.0, |v| v.frames() as f64 / rate),
+            volume: self.volume,
+            looping: self.looping,
+            stretch: self.voice.as_ref().and_then(Voice::stretch),
+        }
+    }
+}
+
+/// Plays sound previews through an audio output.
+///
+/// Sounds are decoded and converted to the output format before playback
+/// starts, so the first frame is rendered in the very next output buffer.
+/// Decoded sounds are kept in the memory tier of the cache, so replaying a
+/// sound skips decoding. Sounds longer than `STREAM_MIN_SECS` are streamed
+/// instead, unless they are stretched. The output is opened lazily on the
+/// first `play` call. Positions and durations are always given in the time
+/// of the original sound, even when it is stretched.
+pub struct PlaybackEngine {
+    kind: OutputKind,
+    cache: Mutex<Arc<Cache>>,
+    buffers: Arc<BufferPool>,
+    output: Mutex<Option<OutputHandle>>,
+    state: Arc<Mutex<PlayerState>>,
+    listener: Arc<Mutex<Option<StatusListener>>>,
+}
+
+impl PlaybackEngine {
+    /// Creates a new playback engine.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The output playback should be sent to.
+    /// * `cache` - The cache holding decoded sounds and the pool streamed
+    ///   sounds take their chunk buffers from.
+    pub fn new(kind: OutputKind, cache: Arc<Cache>) -> Arc<Self> {
+        log(
+            LogLevel::Info,
+            "PlaybackEngine::new",
+            &format!("Initializing playback engine with {:?} output.", kind),
+        );
+        Arc::new(Self {
+            kind,
+            buffers: cache.buffers(),
+            cache: Mutex::new(cache),
+            output: Mutex::new(None),
+            state: Arc::new(Mutex::new(PlayerState {
+                voice: None,
+                volume: 1.0,
+                looping: false,
+                sample_rate: 0,
+                channels: 0,
+            })),
+            listener: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Registers the callback that receives playback status updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The callback, replacing any previous one.
+    pub fn set_listener(&self, listener: StatusListener) {
+        *self.listener.lock().unwrap() = Some(listener);
+    }
+
+    /// Replaces the cache decoded sounds are kept in, for example when
+    /// another library is opened. Streamed sounds keep taking their chunk
+    /// buffers from the pool of the first cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The new cache.
+    pub fn set_cache(&self, cache: Arc<Cache>) {
+        *self.cache.lock().unwrap() = cache;
+    }
+
+    /// Starts the background task that reports the playback position.
+    ///
+    /// Updates are sent while a sound plays and once more when it stops.
+    pub fn start_status_updates(self: &Arc<Self>) {
+        let state = self.state.clone();
+        let listener = self.listener.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATUS_INTERVAL);
+            let mut was_playing = false;
+            loop {
+                interval.tick().await;
+                let status = state.lock().unwrap().status();
+                if status.playing || was_playing {
+                    if let Some(listener) = listener.lock().unwrap().as_ref() {
+                        listener(&status);
+                    }
+                }
+                was_playing = status.playing;
+            }
+        });
+    }
+
+    /// Opens the output if needed and returns its format.
+    fn ensure_output(&self) -> Result<(u32, usize), String> {
+        let mut output = self.output.lock().unwrap();
+        if let Some(handle) = output.as_ref() {
+            return Ok((handle.sample_rate, handle.channels));
+        }
+
+        let state = self.state.clone();
+        let render: RenderFn = Arc::new(move |buffer: &mut [f32]| {
+            if let Ok(mut state) = state.lock() {
+                state.render(buffer);
+            } else {
+                buffer.fill(0.0);
+            }
+        });
+
+        let handle = open_output(&self.kind, render)?;
+        let format = (handle.sample_rate, handle.channels);
+        {
+            let mut state = self.state.lock().unwrap();
+            state.sample_rate = format.0;
+            state.channels = format.1;
+        }
+        *output = Some(handle);
+        Ok(format)
+    }
+
+    /// Plays a sound from its beginning, replacing any sound playing.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_id` - The ID of the sound, reported in status updates.
+    /// * `path` - The path of the audio file.
+    /// * `stretch` - The tempo change and transposition to apply, if any.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new status, or an error message if the
+    /// output cannot be opened or the file cannot be decoded.
+    pub async fn play(
+        &self,
+        sound_id: i64,
+        path: &str,
+        stretch: Option<StretchParams>,
+    ) -> Result<PlaybackStatus, String> {
+        let (sample_rate, channels) = self.ensure_output()?;
+
+        let looping = self.state.lock().unwrap().looping;
+        let buffers = Arc::clone(&self.buffers);
+        let cache = Arc::clone(&self.cache.lock().unwrap());
+
+        let key = match cache.content_key(path).await {
+            Ok(content) => Some(format!("{}-preview-{}-{}", content, sample_rate, channels)),
+            Err(e) => {
+                log(LogLevel::Warn, "PlaybackEngine::play", &e);
+                None
+            }
+        };
+        let cached = match &key {
+            Some(key) => cache.get_audio(key).await.map(Arc::new),
+            None => None,
+        };
+        let was_cached = cached.is_some();
+
+        let path = path.to_string();
+        let source = tokio::task::spawn_blocking(move || {
+            let audio = match cached {
+                Some(audio) => audio,
+                None => {
+                    let stream = AudioStream::open(&path)?;
+                    let long = stream.duration().is_some_and(|secs| secs > STREAM_MIN_SECS);
+                    if long && stretch.is_none() {
+                        drop(stream);
+                        return StreamedSource::open(
+                            &path,
+                            0,
+                            sample_rate,
+                            channels,
+                            looping,
+                            buffers,
+                        )
+                        .map(Source::Streamed);
+                    }
+                    Arc::new(stream.read_all()?.converted(sample_rate, channels))
+                }
+            };
+
+            Ok(Source::Decoded {
+                audio,
+                position: 0,
+                stretch: stretch
+                    .map(|params| (params, TimeStretcher::new(sample_rate, channels, params))),
+            })
+        })
+        .await
+        .map_err(|e| format!("Decode task failed: {}", e))??;
+
+        // The source may hold a stream receiver, which must not be kept
+        // across the await below.
+        let decoded = match &source {
+            Source::Decoded { audio, .. } if !was_cached => Some(Arc::clone(audio)),
+            _ => None,
+        };
+        if let (Some(key), Some(audio)) = (key, decoded) {
+            cache.put_audio(key, &audio, false).await;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.voice = Some(Voice { sound_id, source });
+        Ok(state.status())
+    }
+
+    /// Stops playback.
+    pub fn stop(&self) -> PlaybackStatus {
+        let mut state = self.state.lock().unwrap();
+        state.voice = None;
+        state.status()
+    }
+
+    /// Moves the playback position of the current sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The new position, in seconds. It is clamped to the sound.
+    pub fn seek(&self, position: f64) -> PlaybackStatus {
+        let (sound_id, reopen) = {
+            let mut state = self.state.lock().unwrap();
+            let frame = (position.max(0.0) * state.sample_rate as f64) as usize;
+            let Some(voice) = state.voice.as_mut() else {
+                return state.status();
+            };
+            match voice.seek(frame) {
+                Some(reopen) => (voice.sound_id, reopen),
+                None => return state.status(),
+            }
+        };
+
+        // The file is opened without the lock, so the output thread keeps
+        // playing the old position until the new stream is swapped in.
+        let stream = match reopen.open(Arc::clone(&self.buffers)) {
+            Ok(stream) => stream,
+            Err(e) => {
+                log(LogLevel::Error, "PlaybackEngine::seek", &e);
+                return self.status();
+            }
+        };
+        let mut state = self.state.lock().unwrap();
+        if let Some(Voice {
+            sound_id: current_id,
+            source: Source::Streamed(current),
+        }) = state.voice.as_mut()
+        {
+            // Another sound may have started while the file was opened.
+            if *current_id == sound_id && current.path() == stream.path() {
+                *current = stream;
+            }
+        }
+        state.status()
+    }
+
+    /// Sets the output volume.
+    ///
+    /// # Arguments
+    ///
+    /// * `volume` - The volume from 0 to 1. Values outside are clamped.
+    pub fn set_volume(&self, volume: f32) -> PlaybackStatus {
+        let mut state = self.state.lock().unwrap();
+        state.volume = volume.clamp(0.0, 1.0);
+        state.status()
+    }
+
+    /// Enables or disables looping of the current and future sounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `looping` - Whether sounds restart when they reach their end.
+    pub fn set_looping(&self, looping: bool) -> PlaybackStatus {
+        let mut state = self.state.lock().unwrap();
+        state.looping = looping;
+        state.status()
+    }
+
+    /// Returns the current playback status.
+    pub fn status(&self) -> PlaybackStatus {
+        self.state.lock().unwrap().status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::cache_module::CacheCapacity;
+    use std::path::Path;
+
+    /// Writes a stereo 48 kHz float WAV holding a constant level.
+    fn write_wav(path: &Path, seconds: f64, level: f32) {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for _ in 0..(seconds * 48000.0) as usize * 2 {
+            writer.write_sample(level).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    /// Creates an engine whose cache lives in a temporary directory.
+    fn new_engine(kind: OutputKind, dir: &Path) -> Arc<PlaybackEngine> {
+        let cache = Cache::new(dir.join("cache"), CacheCapacity::from_megabytes(64, 0));
+        PlaybackEngine::new(kind, Arc::new(cache))
+    }
+
+    async fn sleep(millis: u64) {
+        tokio::time::sleep(Duration::from_millis(millis)).await;
+    }
+
+    #[tokio::test]
+    async fn plays_seeks_and_stops() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.wav");
+        write_wav(&path, 1.0, 0.5);
+
+        let engine = new_engine(OutputKind::Null, dir.path());
+        let status = engine.play(7, path.to_str().unwrap(), None).await.unwrap();
+        assert_eq!(status.sound_id, Some(7));
+        assert!(status.playing);
+        assert!((status.duration - 1.0).abs() < 1e-9);
+
+        sleep(150).await;
+        let first = engine.status().position;
+        sleep(100).await;
+        let second = engine.status().position;
+        assert!(first > 0.0 && second > first, "{} then {}", first, second);
+
+        assert_eq!(engine.seek(5.0).position, 1.0);
+        assert_eq!(engine.seek(-1.0).position, 0.0);
+        assert!((engine.seek(0.25).position - 0.25).abs() < 1e-9);
+
+        let status = engine.stop();
+        assert!(!status.playing);
+        assert_eq!(status.sound_id, None);
+        assert_eq!(status.position, 0.0);
+    }
+
+    #[tokio::test]
+    async fn loops_past_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("loop.wav");
+        write_wav(&path, 0.5, 0.5);
+
+        let engine = new_engine(OutputKind::Null, dir.path());
+        engine.set_looping(true);
+        engine.play(1, path.to_str().unwrap(), None).await.unwrap();
+        engine.seek(0.45);
+        sleep(200).await;
+
+        let status = engine.status();
+        assert!(status.playing);
+        assert!(status.looping);
+        assert!(status.position < 0.45, "position {}", status.position);
+
+        engine.set_looping(false);
+        engine.seek(0.45);
+        sleep(200).await;
+        assert!(!engine.status().playing);
+    }
+
+    #[tokio::test]
+    async fn streams_long_sounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.wav");
+        write_wav(&path, STREAM_MIN_SECS + 1.0, 0.25);
+
+        let engine = new_engine(OutputKind::Null, dir.path());
+        let status = engine.play(3, path.to_str().unwrap(), None).await.unwrap();
+        assert!(status.playing);
+        assert!((status.duration - (STREAM_MIN_SECS + 1.0)).abs() < 1e-9);
+
+        sleep(100).await;
+        assert!(engine.status().position > 0.0);
+
+        let status = engine.seek(20.0);
+        assert!((status.position - 20.0).abs() < 0.1, "{}", status.position);
+        sleep(100).await;
+        assert!(engine.status().position > 20.0);
+
+        engine.seek(STREAM_MIN_SECS + 0.9);
+        sleep(300).await;
+        assert!(!engine.status().playing);
+    }
+
+    #[tokio::test]
+    async fn replays_from_the_memory_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hit.wav");
+        write_wav(&path, 0.2, 0.5);
+
+        let cache = Arc::new(Cache::new(
+            dir.path().join("cache"),
+            CacheCapacity::from_megabytes(64, 0),
+        ));
+        let engine = PlaybackEngine::new(OutputKind::Null, Arc::clone(&cache));
+        engine.play(1, path.to_str().unwrap(), None).await.unwrap();
+        assert_eq!(cache.stats().await.memory_entries, 1);
+        assert_eq!(cache.stats().await.memory_hits, 0);
+
+        engine.play(1, path.to_str().unwrap(), None).await.unwrap();
+        assert_eq!(cache.stats().await.memory_hits, 1);
+    }
+
+    #[test]
+    fn clamps_the_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = new_engine(OutputKind::Null, dir.path());
+        assert_eq!(engine.set_volume(2.0).volume, 1.0);
+        assert_eq!(engine.set_volume(-0.5).volume, 0.0);
+        assert_eq!(engine.set_volume(0.3).volume, 0.3);
+    }
+
+    #[tokio::test]
+    async fn renders_into_the_file_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("hit.wav");
+        let output = dir.path().join("out.wav");
+        write_wav(&input, 0.1, 0.8);
+
+        let engine = new_engine(OutputKind::File(output.clone()), dir.path());
+        engine.set_volume(0.5);
+        engine.play(1, input.to_str().unwrap(), None).await.unwrap();
+        sleep(300).await;
+        assert!(!engine.status().playing);
+        // Dropping the engine closes the output and finalizes the file.
+        drop(engine);
+
+        let mut reader = hound::WavReader::open(&output).unwrap();
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.spec().sample_rate, 48000);
+        let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        let audible: Vec<f32> = samples.into_iter().filter(|&s| s != 0.0).collect();
+        assert_eq!(audible.len(), 4800 * 2);
+        assert!(audible.iter().all(|&s| s == 0.4));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod engine;
+pub mod output;
+pub mod streaming;
+pub mod stretch;
@@ -0,0 +1,255 @@
+use crate::utils::logger::{log, LogLevel};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Sample rate of the null and file sinks, in Hz.
+const VIRTUAL_SAMPLE_RATE: u32 = 48000;
+/// Channel count of the null and file sinks.
+const VIRTUAL_CHANNELS: usize = 2;
+/// Frames rendered per block by the null and file sinks.
+const VIRTUAL_BLOCK_FRAMES: usize = 512;
+
+/// Environment variable that selects the output, see `OutputKind::from_env`.
+const OUTPUT_ENV: &str = "SOUNDLAB_AUDIO_OUTPUT";
+
+/// Callback that fills an interleaved output buffer with audio.
+pub type RenderFn = Arc<dyn Fn(&mut [f32]) + Send + Sync>;
+
+/// Where rendered audio is sent.
+#[derive(Debug, Clone)]
+pub enum OutputKind {
+    /// The default audio device of the system.
+    Device,
+    /// Discards audio while keeping real-time pacing, for headless use.
+    Null,
+    /// Writes audio to a 32-bit float WAV file in real time.
+    File(PathBuf),
+}
+
+impl OutputKind {
+    /// Reads the output kind from the `SOUNDLAB_AUDIO_OUTPUT` variable.
+    ///
+    /// `null` selects the null sink and `file:<path>` the file sink. Any
+    /// other value, or no value, selects the system device.
+    pub fn from_env() -> Self {
+        match std::env::var(OUTPUT_ENV) {
+            Ok(value) if value == "null" => OutputKind::Null,
+            Ok(value) => match value.strip_prefix("file:") {
+                Some(path) => OutputKind::File(PathBuf::from(path)),
+                None => OutputKind::Device,
+            },
+            Err(_) => OutputKind::Device,
+        }
+    }
+}
+
+/// A running audio output.
+///
+/// The output runs on its own thread, which keeps platform streams that
+/// cannot cross threads out of the async runtime. Dropping the handle
+/// stops the output and waits for the thread to finish.
+pub struct OutputHandle {
+    /// Sample rate the output expects, in Hz.
+    pub sample_rate: u32,
+    /// Number of interleaved channels the output expects.
+    pub channels: usize,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for OutputHandle {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Opens an audio output that pulls audio from a render callback.
+///
+/// If the system device cannot be opened, playback falls back to the null
+/// sink so that previews keep working on headless machines.
+///
+/// # Arguments
+///
+/// * `kind` - The kind of output to open.
+/// * `render` - The callback producing audio.
+///
+/// # Returns
+///
+/// A `Result` containing the running output, or an error message.
+pub fn open_output(kind: &OutputKind, render: RenderFn) -> Result<OutputHandle, String> {
+    match kind {
+        OutputKind::Device => open_device(render.clone()).or_else(|e| {
+            log(
+                LogLevel::Warn,
+                "output::open_output",
+                &format!("Falling back to null output: {}", e),
+            );
+            open_virtual(None, render)
+        }),
+        OutputKind::Null => open_virtual(None, render),
+        OutputKind::File(path) => open_virtual(Some(path.clone()), render),
+    }
+}
+
+/// Runs a null or file sink on a thread paced by the wall clock.
+fn open_virtual(path: Option<PathBuf>, render: RenderFn) -> Result<OutputHandle, String> {
+    let mut writer = match &path {
+        Some(path) => Some(
+            hound::WavWriter::create(
+                path,
+                hound::WavSpec {
+                    channels: VIRTUAL_CHANNELS as u16,
+                    sample_rate: VIRTUAL_SAMPLE_RATE,
+                    bits_per_sample: 32,
+                    sample_format: hound::SampleFormat::Float,
+                },
+            )
+            .map_err(|e| format!("Error creating '{}': {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let stop = shutdown.clone();
+    let period = Duration::from_secs_f64(VIRTUAL_BLOCK_FRAMES as f64 / VIRTUAL_SAMPLE_RATE as f64);
+
+    let thread = thread::spawn(move || {
+        let mut buffer = vec![0.0f32; VIRTUAL_BLOCK_FRAMES * VIRTUAL_CHANNELS];
+        let mut deadline = Instant::now();
+
+        while !stop.load(Ordering::Relaxed) {
+            render(&mut buffer);
+            if let Some(writer) = writer.as_mut() {
+                for &sample in &buffer {
+                    if writer.write_sample(sample).is_err() {
+                        return;
+                    }
+                }
+            }
+
+            deadline += period;
+            thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        if let Some(writer) = writer {
+            let _ = writer.finalize();
+        }
+    });
+
+    Ok(OutputHandle {
+        sample_rate: VIRTUAL_SAMPLE_RATE,
+        channels: VIRTUAL_CHANNELS,
+        shutdown,
+        thread: Some(thread),
+    })
+}
+
+/// Opens the default system audio device.
+#[cfg(feature = "native-audio")]
+fn open_device(render: RenderFn) -> Result<OutputHandle, String> {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SampleFormat, SizedSample};
+    use std::sync::mpsc;
+
+    fn build<T: SizedSample + FromSample<f32>>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        render: RenderFn,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError> {
+        let mut scratch = Vec::new();
+        device.build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                scratch.resize(data.len(), 0.0f32);
+                render(&mut scratch);
+                for (out, &sample) in data.iter_mut().zip(&scratch) {
+                    *out = T::from_sample(sample);
+                }
+            },
+            |err| {
+                log(
+                    LogLevel::Error,
+                    "output::open_device",
+                    &format!("Audio stream error: {}", err),
+                )
+            },
+            None,
+        )
+    }
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let stop = shutdown.clone();
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(u32, usize), String>>();
+
+    let thread = thread::spawn(move || {
+        let opened = (|| {
+            let device = cpal::default_host()
+                .default_output_device()
+                .ok_or_else(|| "No audio output device available".to_string())?;
+            let supported = device
+                .default_output_config()
+                .map_err(|e| format!("Error querying output config: {}", e))?;
+            let format = supported.sample_format();
+            let config: cpal::StreamConfig = supported.into();
+
+            let stream = match format {
+                SampleFormat::F32 => build::<f32>(&device, &config, render),
+                SampleFormat::I16 => build::<i16>(&device, &config, render),
+                SampleFormat::U16 => build::<u16>(&device, &config, render),
+                SampleFormat::I32 => build::<i32>(&device, &config, render),
+                other => return Err(format!("Unsupported sample format {:?}", other)),
+            }
+            .map_err(|e| format!("Error opening output stream: {}", e))?;
+            stream
+                .play()
+                .map_err(|e| format!("Error starting output stream: {}", e))?;
+
+            Ok((stream, config.sample_rate.0, config.channels as usize))
+        })();
+
+        match opened {
+            Ok((stream, sample_rate, channels)) => {
+                let _ = ready_tx.send(Ok((sample_rate, channels)));
+                while !stop.load(Ordering::Relaxed) {
+                    thread::park_timeout(Duration::from_millis(50));
+                }
+                drop(stream);
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+            }
+        }
+    });
+
+    let (sample_rate, channels) = ready_rx
+        .recv()
+        .map_err(|_| "Audio output thread exited unexpectedly".to_string())??;
+
+    log(
+        LogLevel::Info,
+        "output::open_device",
+        &format!(
+            "Opened audio device at {} Hz with {} channels.",
+            sample_rate, channels
+        ),
+    );
+
+    Ok(OutputHandle {
+        sample_rate,
+        channels,
+        shutdown,
+        thread: Some(thread),
+    })
+}
+
+/// Reports that native audio support was not compiled in.
+#[cfg(not(feature = "native-audio"))]
+fn open_device(_render: RenderFn) -> Result<OutputHandle, String> {
+    Err("Native audio support is disabled".to_string())
+}
@@ -0,0 +1,285 @@
+use crate::analysis::decoder::FormatConverter;
+use crate::analysis::stream::AudioStream;
+use crate::cache::buffer_pool::{BufferPool, PooledBuffer};
+use crate::utils::logger::{log, LogLevel};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Converted chunks decoded ahead of the playback position.
+const QUEUE_CHUNKS: usize = 4;
+/// How often the decode thread checks whether to loop after the end.
+const END_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Data sent from the decode thread.
+enum Message {
+    /// A chunk of audio converted to the output format, starting at an output frame.
+    Chunk { start: usize, samples: PooledBuffer },
+    /// The end of the file was reached.
+    End,
+}
+
+/// Flags shared with the decode thread.
+#[derive(Default)]
+struct Control {
+    looping: AtomicBool,
+    closed: AtomicBool,
+}
+
+/// A sound decoded on a background thread while it plays.
+///
+/// Only a few chunks are buffered ahead of the playback position, so long
+/// stems and field recordings play without being loaded into memory. At the
+/// end of the file the decode thread waits until looping is enabled or the
+/// source is dropped.
+pub struct StreamedSource {
+    path: String,
+    sample_rate: u32,
+    channels: usize,
+    frames: usize,
+    control: Arc<Control>,
+    receiver: Receiver<Message>,
+    current: Option<(usize, PooledBuffer)>,
+    offset: usize,
+    position: usize,
+}
+
+impl Drop for StreamedSource {
+    fn drop(&mut self) {
+        self.control.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl StreamedSource {
+    /// Starts streaming a file converted to the output format.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the audio file.
+    /// * `start` - The output frame to start at.
+    /// * `sample_rate` - The output sample rate, in Hz.
+    /// * `channels` - The output channel count.
+    /// * `looping` - Whether the file restarts when it reaches its end.
+    /// * `buffers` - The pool chunk buffers are taken from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the source, or an error message if the file
+    /// cannot be opened.
+    pub fn open(
+        path: &str,
+        start: usize,
+        sample_rate: u32,
+        channels: usize,
+        looping: bool,
+        buffers: Arc<BufferPool>,
+    ) -> Result<Self, String> {
+        let mut stream = AudioStream::open(path)?;
+        let ratio = stream.sample_rate() as f64 / sample_rate as f64;
+        let frames = stream
+            .duration()
+            .map_or(0, |secs| (secs * sample_rate as f64) as usize);
+        let start = start.min(frames);
+        if start > 0 {
+            stream.seek((start as f64 * ratio) as u64)?;
+        }
+
+        let control = Arc::new(Control::default());
+        control.looping.store(looping, Ordering::Relaxed);
+        let (sender, receiver) = sync_channel(QUEUE_CHUNKS);
+        let thread_control = Arc::clone(&control);
+        let thread_path = path.to_string();
+
+        thread::spawn(move || {
+            let mut converter = FormatConverter::new(
+                stream.sample_rate(),
+                stream.channels(),
+                sample_rate,
+                channels,
+            );
+            let mut decoded = Vec::new();
+            let mut position = start;
+
+            loop {
+                let read = match stream.read_chunk(&mut decoded) {
+                    Ok(read) => read,
+                    Err(e) => {
+                        log(LogLevel::Error, "StreamedSource::open", &e);
+                        return;
+                    }
+                };
+
+                let mut samples = buffers.acquire();
+                if read > 0 {
+                    converter.push(&decoded, &mut samples);
+                } else {
+                    converter.finish(&mut samples);
+                }
+
+                let frames = samples.len() / channels;
+                if frames > 0 {
+                    let chunk = Message::Chunk {
+                        start: position,
+                        samples,
+                    };
+                    if sender.send(chunk).is_err() {
+                        return;
+                    }
+                    position += frames;
+                }
+
+                if read == 0 {
+                    if sender.send(Message::End).is_err() {
+                        return;
+                    }
+                    while !thread_control.looping.load(Ordering::Relaxed) {
+                        if thread_control.closed.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        thread::sleep(END_POLL_INTERVAL);
+                    }
+                    if let Err(e) = stream.seek(0) {
+                        log(
+                            LogLevel::Error,
+                            "StreamedSource::open",
+                            &format!("Error looping '{}': {}", thread_path, e),
+                        );
+                        return;
+                    }
+                    converter = FormatConverter::new(
+                        stream.sample_rate(),
+                        stream.channels(),
+                        sample_rate,
+                        channels,
+                    );
+                    position = 0;
+                }
+            }
+        });
+
+        Ok(Self {
+            path: path.to_string(),
+            sample_rate,
+            channels,
+            frames,
+            control,
+            receiver,
+            current: None,
+            offset: 0,
+            position: start,
+        })
+    }
+
+    /// Describes how to open this stream again at another position.
+    ///
+    /// Opening reads the file, so a seek takes this description out of the
+    /// player state and opens the new stream without holding its lock.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The output frame to continue from.
+    pub fn reopen_at(&self, frame: usize) -> Reopen {
+        Reopen {
+            path: self.path.clone(),
+            start: frame,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            looping: self.control.looping.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the path of the streamed file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the total length in output frames, or zero if unknown.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns the current position in output frames.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Fills an interleaved buffer with the next frames.
+    ///
+    /// If the decode thread falls behind, the rest of the buffer is left
+    /// silent and playback continues with the next call.
+    ///
+    /// # Arguments
+    ///
+    /// * `looping` - Whether the file restarts when it reaches its end.
+    /// * `out` - The buffer to fill.
+    ///
+    /// # Returns
+    ///
+    /// `false` once the end of the file has been played without looping.
+    pub fn render(&mut self, looping: bool, out: &mut [f32]) -> bool {
+        self.control.looping.store(looping, Ordering::Relaxed);
+
+        for frame in out.chunks_exact_mut(self.channels) {
+            while self
+                .current
+                .as_ref()
+                .is_none_or(|(_, samples)| self.offset * self.channels >= samples.len())
+            {
+                match self.receiver.try_recv() {
+                    Ok(Message::Chunk { start, samples }) => {
+                        self.current = Some((start, samples));
+                        self.offset = 0;
+                    }
+                    Ok(Message::End) if looping => self.current = None,
+                    Ok(Message::End) | Err(TryRecvError::Disconnected) => return false,
+                    Err(TryRecvError::Empty) => return true,
+                }
+            }
+
+            let Some((start, samples)) = self.current.as_ref() else {
+                return true;
+            };
+            let index = self.offset * self.channels;
+            frame.copy_from_slice(&samples[index..index + self.channels]);
+            self.offset += 1;
+            self.position = start + self.offset;
+        }
+
+        true
+    }
+}
+
+/// A stream to open in place of another one, created by
+/// `StreamedSource::reopen_at`.
+pub struct Reopen {
+    path: String,
+    start: usize,
+    sample_rate: u32,
+    channels: usize,
+    looping: bool,
+}
+
+impl Reopen {
+    /// Opens the stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffers` - The pool chunk buffers are taken from.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the source, or an error message if the file
+    /// cannot be opened.
+    pub fn open(self, buffers: Arc<BufferPool>) -> Result<StreamedSource, String> {
+        StreamedSource::open(
+            &self.path,
+            self.start,
+            self.sample_rate,
+            self.channels,
+            self.looping,
+            buffers,
+        )
+    }
+}
@@ -0,0 +1,397 @@
+use crate::analysis::decoder::DecodedAudio;
+use crate::analysis::key::MusicalKey;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// Length of a WSOLA analysis frame, in seconds.
+const FRAME_SECS: f64 = 0.046;
+/// Largest shift searched around the nominal analysis position, in seconds.
+const TOLERANCE_SECS: f64 = 0.012;
+/// Step between compared samples and candidate shifts during the search.
+const SEARCH_STRIDE: usize = 2;
+/// Slowest and fastest tempo ratios a preview can be stretched by.
+const TEMPO_RANGE: (f64, f64) = (0.25, 4.0);
+
+/// Tempo change and transposition applied to a preview.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StretchParams {
+    /// Playback speed relative to the original; 2 plays twice as fast.
+    pub tempo: f64,
+    /// Transposition, in semitones.
+    pub semitones: i32,
+}
+
+impl StretchParams {
+    /// Computes the stretch that brings a sound to a target tempo and key.
+    ///
+    /// Either part is skipped when the source or the target is unknown.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_bpm` - The detected tempo of the sound.
+    /// * `target_bpm` - The tempo to play at.
+    /// * `source_key` - The key of the sound.
+    /// * `target_key` - The key to transpose to.
+    ///
+    /// # Returns
+    ///
+    /// The parameters, or `None` if the sound would play unchanged.
+    pub fn for_target(
+        source_bpm: Option<f64>,
+        target_bpm: Option<f64>,
+        source_key: Option<MusicalKey>,
+        target_key: Option<MusicalKey>,
+    ) -> Option<Self> {
+        let tempo = match (source_bpm, target_bpm) {
+            (Some(source), Some(target)) if source > 0.0 && target > 0.0 => {
+                (target / source).clamp(TEMPO_RANGE.0, TEMPO_RANGE.1)
+            }
+            _ => 1.0,
+        };
+        let semitones = match (source_key, target_key) {
+            (Some(source), Some(target)) => source.semitones_to(&target),
+            _ => 0,
+        };
+
+        if (tempo - 1.0).abs() < 1e-6 && semitones == 0 {
+            None
+        } else {
+            Some(Self { tempo, semitones })
+        }
+    }
+
+    /// Returns the frequency ratio of the transposition.
+    fn pitch(&self) -> f64 {
+        2f64.powf(self.semitones as f64 / 12.0)
+    }
+}
+
+/// Real-time time stretcher and pitch shifter for a decoded sound.
+///
+/// Tempo is changed with WSOLA: windowed frames are read from the source at
+/// a rate set by the tempo and overlap-added at a fixed rate, each frame
+/// shifted slightly so that its waveform lines up with the previous one.
+/// Transposition resamples the WSOLA output, with the WSOLA rate compensated
+/// so that the overall tempo stays as requested.
+pub struct TimeStretcher {
+    channels: usize,
+    frame: usize,
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    speed: f64,
+    pitch: f64,
+    analysis: f64,
+    previous: Option<isize>,
+    overlap: Vec<f32>,
+    ready: VecDeque<f32>,
+    phase: f64,
+}
+
+impl TimeStretcher {
+    /// Creates a stretcher for audio in a given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The sample rate of the audio, in Hz.
+    /// * `channels` - The number of interleaved channels.
+    /// * `params` - The tempo change and transposition to apply.
+    pub fn new(sample_rate: u32, channels: usize, params: StretchParams) -> Self {
+        let hop = ((FRAME_SECS * sample_rate as f64) as usize / 2).max(1);
+        let frame = hop * 2;
+        let window = (0..frame)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / frame as f64).cos()) as f32)
+            .collect();
+        let pitch = params.pitch();
+
+        Self {
+            channels: channels.max(1),
+            frame,
+            hop,
+            tolerance: (TOLERANCE_SECS * sample_rate as f64) as usize,
+            window,
+            speed: params.tempo / pitch,
+            pitch,
+            analysis: 0.0,
+            previous: None,
+            overlap: vec![0.0; hop * channels.max(1)],
+            ready: VecDeque::new(),
+            phase: 0.0,
+        }
+    }
+
+    /// Returns the current position in the source, in frames.
+    pub fn position(&self) -> usize {
+        self.analysis as usize
+    }
+
+    /// Moves to a position in the source and clears all pending audio.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The new position in the source, in frames.
+    pub fn seek(&mut self, frame: usize) {
+        self.analysis = frame as f64;
+        self.previous = None;
+        self.overlap.fill(0.0);
+        self.ready.clear();
+        self.phase = 0.0;
+    }
+
+    /// Fills an interleaved buffer with stretched audio.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio` - The source audio, in the same format as the buffer.
+    /// * `looping` - Whether the source wraps around at its end.
+    /// * `out` - The buffer to fill; frames after the end are left untouched.
+    ///
+    /// # Returns
+    ///
+    /// `false` once the end of the source has been reached without looping.
+    pub fn render(&mut self, audio: &DecodedAudio, looping: bool, out: &mut [f32]) -> bool {
+        let channels = self.channels;
+        let frames = audio.frames();
+        if frames == 0 {
+            return false;
+        }
+
+        for frame in out.chunks_exact_mut(channels) {
+            while self.ready.len() < 2 * channels {
+                if !looping && self.analysis >= frames as f64 {
+                    return false;
+                }
+                self.process_hop(audio, looping);
+            }
+
+            let t = self.phase as f32;
+            for (channel, out) in frame.iter_mut().enumerate() {
+                let current = self.ready[channel];
+                let next = self.ready[channels + channel];
+                *out = current + (next - current) * t;
+            }
+
+            self.phase += self.pitch;
+            while self.phase >= 1.0 {
+                if self.ready.len() < 2 * channels {
+                    if !looping && self.analysis >= frames as f64 {
+                        return false;
+                    }
+                    self.process_hop(audio, looping);
+                }
+                self.ready.drain(..channels);
+                self.phase -= 1.0;
+            }
+        }
+
+        true
+    }
+
+    /// Reads one frame from the source, aligns it and overlap-adds it.
+    fn process_hop(&mut self, audio: &DecodedAudio, looping: bool) {
+        let channels = self.channels;
+        let nominal = self.analysis.round() as isize;
+        let start = match self.previous {
+            Some(previous) => {
+                self.best_start(audio, looping, nominal, previous + self.hop as isize)
+            }
+            None => nominal,
+        };
+
+        for i in 0..self.frame {
+            let weight = self.window[i];
+            for channel in 0..channels {
+                let value = sample(audio, looping, start + i as isize, channel) * weight;
+                if i < self.hop {
+                    self.ready
+                        .push_back(self.overlap[i * channels + channel] + value);
+                } else {
+                    self.overlap[(i - self.hop) * channels + channel] = value;
+                }
+            }
+        }
+
+        self.previous = Some(start);
+        self.analysis += self.hop as f64 * self.speed;
+
+        let frames = audio.frames();
+        if looping && self.analysis >= frames as f64 {
+            self.analysis -= frames as f64;
+            self.previous = Some(start - frames as isize);
+        }
+    }
+
+    /// Finds the start near `nominal` whose waveform best continues `natural`.
+    ///
+    /// Candidates are scored by normalised cross-correlation of their mono
+    /// mix against the natural continuation of the previous frame.
+    fn best_start(
+        &self,
+        audio: &DecodedAudio,
+        looping: bool,
+        nominal: isize,
+        natural: isize,
+    ) -> isize {
+        let tolerance = self.tolerance as isize;
+        let reference: Vec<f32> = (0..self.hop)
+            .step_by(SEARCH_STRIDE)
+            .map(|i| mono_sample(audio, looping, natural + i as isize, self.channels))
+            .collect();
+
+        let mut best = nominal;
+        let mut best_score = f32::MIN;
+        for shift in (-tolerance..=tolerance).step_by(SEARCH_STRIDE) {
+            let candidate = nominal + shift;
+            let mut dot = 0.0f32;
+            let mut energy = 0.0f32;
+            for (index, &expected) in reference.iter().enumerate() {
+                let value = mono_sample(
+                    audio,
+                    looping,
+                    candidate + (index * SEARCH_STRIDE) as isize,
+                    self.channels,
+                );
+                dot += value * expected;
+                energy += value * value;
+            }
+
+            let score = dot / (energy.sqrt() + 1e-9);
+            if score > best_score {
+                best_score = score;
+                best = candidate;
+            }
+        }
+
+        best
+    }
+}
+
+/// Reads a sample from the source, wrapping when looping and silent outside otherwise.
+fn sample(audio: &DecodedAudio, looping: bool, index: isize, channel: usize) -> f32 {
+    let frames = audio.frames() as isize;
+    let index = if looping {
+        index.rem_euclid(frames)
+    } else if (0..frames).contains(&index) {
+        index
+    } else {
+        return 0.0;
+    };
+    audio.samples[index as usize * audio.channels + channel]
+}
+
+/// Reads the mono mix of a frame from the source.
+fn mono_sample(audio: &DecodedAudio, looping: bool, index: isize, channels: usize) -> f32 {
+    (0..channels)
+        .map(|channel| sample(audio, looping, index, channel))
+        .sum::<f32>()
+        / channels as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    /// A mono 220 Hz sine at half scale.
+    fn sine(seconds: f64) -> DecodedAudio {
+        let samples = (0..(seconds * SAMPLE_RATE as f64) as usize)
+            .map(|n| (0.5 * (2.0 * PI * 220.0 * n as f64 / SAMPLE_RATE as f64).sin()) as f32)
+            .collect();
+        DecodedAudio {
+            samples,
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+        }
+    }
+
+    /// Renders frame by frame until the end of the source.
+    fn render_all(audio: &DecodedAudio, params: StretchParams) -> Vec<f32> {
+        let mut stretcher = TimeStretcher::new(SAMPLE_RATE, 1, params);
+        let mut output = Vec::new();
+        let mut frame = [0.0f32];
+        while stretcher.render(audio, false, &mut frame) {
+            output.push(frame[0]);
+        }
+        output
+    }
+
+    #[test]
+    fn doubling_the_tempo_halves_the_length() {
+        let output = render_all(
+            &sine(2.0),
+            StretchParams {
+                tempo: 2.0,
+                semitones: 0,
+            },
+        );
+        let hop = (FRAME_SECS * SAMPLE_RATE as f64) as usize / 2;
+        let expected = SAMPLE_RATE as usize;
+        assert!(
+            output.len().abs_diff(expected) <= hop,
+            "rendered {} frames",
+            output.len()
+        );
+
+        // Overlapping Hann windows sum to one, so the level is kept.
+        let peak = output[hop * 4..output.len() - hop * 4]
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((peak - 0.5).abs() < 0.05, "peak {}", peak);
+    }
+
+    #[test]
+    fn transposing_keeps_the_length() {
+        let output = render_all(
+            &sine(0.5),
+            StretchParams {
+                tempo: 1.0,
+                semitones: 12,
+            },
+        );
+        let hop = (FRAME_SECS * SAMPLE_RATE as f64) as usize / 2;
+        assert!(
+            output.len().abs_diff(SAMPLE_RATE as usize / 2) <= 2 * hop,
+            "rendered {} frames",
+            output.len()
+        );
+    }
+
+    #[test]
+    fn looping_never_ends() {
+        let audio = sine(0.5);
+        let mut stretcher = TimeStretcher::new(
+            SAMPLE_RATE,
+            1,
+            StretchParams {
+                tempo: 4.0,
+                semitones: 0,
+            },
+        );
+        let mut buffer = vec![0.0f32; SAMPLE_RATE as usize / 4];
+        for _ in 0..4 {
+            assert!(stretcher.render(&audio, true, &mut buffer));
+        }
+        assert!(stretcher.position() < audio.frames());
+    }
+
+    #[test]
+    fn targets_a_tempo() {
+        let params = StretchParams::for_target(Some(120.0), Some(240.0), None, None).unwrap();
+        assert_eq!(params.tempo, 2.0);
+        assert_eq!(params.semitones, 0);
+
+        let clamped = StretchParams::for_target(Some(60.0), Some(600.0), None, None).unwrap();
+        assert_eq!(clamped.tempo, 4.0);
+
+        assert_eq!(
+            StretchParams::for_target(Some(120.0), Some(120.0), None, None),
+            None
+        );
+        assert_eq!(
+            StretchParams::for_target(None, Some(120.0), None, None),
+            None
+        );
+    }
+}
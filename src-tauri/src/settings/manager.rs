@@ -1,123 +1,1070 @@
+use crate::analysis::key::MusicalKey;
+use crate::db::sound::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::import::volume::Volume;
 use crate::utils::logger::{log, LogLevel};
-use dirs::config_dir;
-use lazy_static::lazy_static;
+use crate::utils::paths::library_dir;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
-lazy_static! {
-    static ref SETTINGS: Mutex<Config> = Mutex::new(Config::load());
+/// Version of the settings file layout. Files without a version were
+/// written before the layout was versioned and count as version 1.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Tempo and key that synced previews are played at.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PreviewTarget {
+    /// Target tempo in beats per minute.
+    pub bpm: Option<f64>,
+    /// Target key, written like `F#m` or `Bb major`.
+    pub key: Option<String>,
+}
+
+/// Folders below an import path skipped by default.
+const DEFAULT_EXCLUDES: &[&str] = &["__MACOSX"];
+
+/// An imported folder and the rules for which files below it are imported.
+///
+/// Patterns without a `/` match file and folder names anywhere below the
+/// path; patterns with one match the path relative to it, where `*` stays
+/// within a folder and `**` crosses folders.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "ImportPathEntry")]
+pub struct ImportPath {
+    /// The imported folder or file.
+    pub path: String,
+    /// Only files matching one of these patterns are imported. Empty means all audio files.
+    pub include: Vec<String>,
+    /// Files and folders matching one of these patterns are skipped.
+    pub exclude: Vec<String>,
+    /// How many folder levels below the path are scanned. `None` means no limit.
+    pub max_depth: Option<usize>,
+    /// Whether symbolic links are followed. Links that would revisit a folder are skipped.
+    pub follow_symlinks: bool,
+    /// Whether files and folders whose name starts with a dot are imported.
+    pub include_hidden: bool,
+}
+
+impl ImportPath {
+    /// Creates an entry with the default rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The imported folder or file.
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            include: vec![],
+            exclude: DEFAULT_EXCLUDES.iter().map(|p| p.to_string()).collect(),
+            max_depth: None,
+            follow_symlinks: false,
+            include_hidden: false,
+        }
+    }
+}
+
+/// The stored form of an `ImportPath`, which older configs wrote as a plain path.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportPathEntry {
+    Path(String),
+    Options {
+        path: String,
+        #[serde(default)]
+        include: Vec<String>,
+        #[serde(default)]
+        exclude: Vec<String>,
+        #[serde(default)]
+        max_depth: Option<usize>,
+        #[serde(default)]
+        follow_symlinks: bool,
+        #[serde(default)]
+        include_hidden: bool,
+    },
+}
+
+impl From<ImportPathEntry> for ImportPath {
+    fn from(entry: ImportPathEntry) -> Self {
+        match entry {
+            // Plain paths were scanned without rules, so they keep importing
+            // hidden files and following links rather than gaining the
+            // default excludes.
+            ImportPathEntry::Path(path) => ImportPath {
+                exclude: vec![],
+                follow_symlinks: true,
+                include_hidden: true,
+                ..ImportPath::new(path)
+            },
+            ImportPathEntry::Options {
+                path,
+                include,
+                exclude,
+                max_depth,
+                follow_symlinks,
+                include_hidden,
+            } => ImportPath {
+                path,
+                include,
+                exclude,
+                max_depth,
+                follow_symlinks,
+                include_hidden,
+            },
+        }
+    }
+}
+
+/// How imports and rescans run.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ImportSettings {
+    /// Number of files analysed at the same time.
+    pub concurrency: usize,
+    /// How often attached volumes are checked for import paths that went
+    /// offline or came back, in seconds. Applies to libraries opened later.
+    pub volume_poll_secs: u64,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            volume_poll_secs: 5,
+        }
+    }
+}
+
+/// Thresholds of the audio analysis.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct AnalysisSettings {
+    /// Detected tempos with a weaker periodicity than this are discarded.
+    pub min_tempo_confidence: f64,
+    /// Drum predictions at least this confident are added as tags.
+    pub drum_tag_confidence: f64,
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        Self {
+            min_tempo_confidence: 0.2,
+            drum_tag_confidence: 0.5,
+        }
+    }
+}
+
+/// Size limits of the analysis cache, in megabytes.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// Size of the in-memory tier.
+    pub memory_mb: u64,
+    /// Size of the on-disk tier.
+    pub disk_mb: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            memory_mb: 256,
+            disk_mb: 2048,
+        }
+    }
+}
+
+/// How sounds are previewed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PlaybackSettings {
+    /// Tempo and key that synced previews are played at.
+    pub preview_target: PreviewTarget,
+    /// Volume playback starts at, from 0 to 1.
+    pub volume: f32,
+    /// Whether looping is enabled when the app starts.
+    pub looping: bool,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            preview_target: PreviewTarget::default(),
+            volume: 1.0,
+            looping: false,
+        }
+    }
+}
+
+/// The colour scheme of the interface.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follows the operating system.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Options of the interface, which only the UI reads.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct UiSettings {
+    pub theme: Theme,
+    /// Number of sounds loaded per page of the sound list.
+    pub page_size: usize,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// How long deleted sounds are kept.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct TrashSettings {
+    /// Days after which deleted sounds are purged.
+    pub retention_days: u32,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+/// Connection pool sizes and SQLite tuning, applied when a library is opened.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct DatabaseSettings {
+    /// Connections used for writes. SQLite allows a single writer at a
+    /// time, so more than one only moves the waiting into SQLite, where
+    /// upgrading a read transaction can fail with "database is locked".
+    pub write_connections: u32,
+    /// Connections used for reads, which run alongside writes in WAL mode.
+    pub read_connections: u32,
+    /// How long a statement waits for a lock before failing, in milliseconds.
+    pub busy_timeout_ms: u64,
+    /// Number of prepared statements cached per connection.
+    pub statement_cache_capacity: usize,
+}
+
+impl Default for DatabaseSettings {
+    fn default() -> Self {
+        Self {
+            write_connections: 1,
+            read_connections: 4,
+            busy_timeout_ms: 5000,
+            statement_cache_capacity: 128,
+        }
+    }
+}
+
+/// The settings the user can change, grouped by area.
+///
+/// Missing sections and fields take their defaults, so files written by
+/// older versions of the same layout version still load.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub import: ImportSettings,
+    pub analysis: AnalysisSettings,
+    pub cache: CacheSettings,
+    pub playback: PlaybackSettings,
+    pub ui: UiSettings,
+    pub trash: TrashSettings,
+    pub database: DatabaseSettings,
+}
+
+impl Settings {
+    /// Checks every value against its allowed range.
+    ///
+    /// # Returns
+    ///
+    /// `Ok` if all values are valid, or an error message listing every
+    /// invalid value.
+    pub fn validate(&self) -> Result<(), String> {
+        let errors = self.invalid_fields();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            let messages: Vec<String> = errors.into_iter().map(|(_, message)| message).collect();
+            Err(messages.join("; "))
+        }
+    }
+
+    /// Resets every value outside its allowed range to its default.
+    ///
+    /// # Returns
+    ///
+    /// A message for each value that was reset.
+    pub fn reset_invalid(&mut self) -> Vec<String> {
+        let defaults = Self::default();
+        let errors = self.invalid_fields();
+        for (field, _) in &errors {
+            match *field {
+                "import.concurrency" => self.import.concurrency = defaults.import.concurrency,
+                "import.volume_poll_secs" => {
+                    self.import.volume_poll_secs = defaults.import.volume_poll_secs
+                }
+                "analysis.min_tempo_confidence" => {
+                    self.analysis.min_tempo_confidence = defaults.analysis.min_tempo_confidence
+                }
+                "analysis.drum_tag_confidence" => {
+                    self.analysis.drum_tag_confidence = defaults.analysis.drum_tag_confidence
+                }
+                "cache.memory_mb" => self.cache.memory_mb = defaults.cache.memory_mb,
+                "playback.preview_target.bpm" => self.playback.preview_target.bpm = None,
+                "playback.preview_target.key" => self.playback.preview_target.key = None,
+                "playback.volume" => self.playback.volume = defaults.playback.volume,
+                "ui.page_size" => self.ui.page_size = defaults.ui.page_size,
+                "database.write_connections" => {
+                    self.database.write_connections = defaults.database.write_connections
+                }
+                "database.read_connections" => {
+                    self.database.read_connections = defaults.database.read_connections
+                }
+                _ => {}
+            }
+        }
+        errors.into_iter().map(|(_, message)| message).collect()
+    }
+
+    /// Returns the path and a description of every invalid value.
+    fn invalid_fields(&self) -> Vec<(&'static str, String)> {
+        let mut errors = vec![];
+        let mut check = |valid: bool, field: &'static str, message: String| {
+            if !valid {
+                errors.push((field, message));
+            }
+        };
+
+        check(
+            (1..=64).contains(&self.import.concurrency),
+            "import.concurrency",
+            format!(
+                "import.concurrency must be between 1 and 64, got {}",
+                self.import.concurrency
+            ),
+        );
+        check(
+            (1..=3600).contains(&self.import.volume_poll_secs),
+            "import.volume_poll_secs",
+            format!(
+                "import.volume_poll_secs must be between 1 and 3600, got {}",
+                self.import.volume_poll_secs
+            ),
+        );
+        check(
+            (0.0..=1.0).contains(&self.analysis.min_tempo_confidence),
+            "analysis.min_tempo_confidence",
+            format!(
+                "analysis.min_tempo_confidence must be between 0 and 1, got {}",
+                self.analysis.min_tempo_confidence
+            ),
+        );
+        check(
+            (0.0..=1.0).contains(&self.analysis.drum_tag_confidence),
+            "analysis.drum_tag_confidence",
+            format!(
+                "analysis.drum_tag_confidence must be between 0 and 1, got {}",
+                self.analysis.drum_tag_confidence
+            ),
+        );
+        check(
+            self.cache.memory_mb >= 1,
+            "cache.memory_mb",
+            "cache.memory_mb must be at least 1".to_string(),
+        );
+        if let Some(bpm) = self.playback.preview_target.bpm {
+            check(
+                (20.0..=400.0).contains(&bpm),
+                "playback.preview_target.bpm",
+                format!(
+                    "playback.preview_target.bpm must be between 20 and 400, got {}",
+                    bpm
+                ),
+            );
+        }
+        if let Some(key) = self.playback.preview_target.key.as_deref() {
+            check(
+                MusicalKey::parse(key).is_some(),
+                "playback.preview_target.key",
+                format!("playback.preview_target.key is not a key: '{}'", key),
+            );
+        }
+        check(
+            (0.0..=1.0).contains(&self.playback.volume),
+            "playback.volume",
+            format!(
+                "playback.volume must be between 0 and 1, got {}",
+                self.playback.volume
+            ),
+        );
+        check(
+            (1..=MAX_PAGE_SIZE).contains(&self.ui.page_size),
+            "ui.page_size",
+            format!(
+                "ui.page_size must be between 1 and {}, got {}",
+                MAX_PAGE_SIZE, self.ui.page_size
+            ),
+        );
+        check(
+            self.database.write_connections >= 1,
+            "database.write_connections",
+            "database.write_connections must be at least 1".to_string(),
+        );
+        check(
+            self.database.read_connections >= 1,
+            "database.read_connections",
+            "database.read_connections must be at least 1".to_string(),
+        );
+
+        errors
+    }
+}
+
+/// Name of the library holding the data of versions without libraries.
+pub const DEFAULT_LIBRARY: &str = "Default";
+
+/// A library and the settings that belong to it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LibrarySettings {
+    /// The name of the library, unique among libraries.
+    pub name: String,
+    /// The directory holding the library's database, cache and backups,
+    /// relative to the data directory. Empty for the default library, which
+    /// lives in the data directory itself.
+    #[serde(default)]
+    pub dir: String,
+    /// The folders imported into the library.
+    #[serde(default)]
+    pub imported_paths: Vec<ImportPath>,
+    /// The volumes the imported folders live on.
+    #[serde(default)]
+    pub import_volumes: HashMap<String, Volume>,
+}
+
+impl LibrarySettings {
+    fn new(name: String, dir: String) -> Self {
+        Self {
+            name,
+            dir,
+            imported_paths: vec![],
+            import_volumes: HashMap::new(),
+        }
+    }
 }
 
+/// The contents of the settings file.
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Config {
-    imported_paths: Vec<String>,
+struct Config {
+    version: u32,
+    #[serde(flatten)]
+    settings: Settings,
+    #[serde(default)]
+    libraries: Vec<LibrarySettings>,
+    #[serde(default)]
+    active_library: String,
 }
 
 impl Config {
     fn new() -> Self {
-        log(
-            LogLevel::Info,
-            "Config::new",
-            "Creating new Config instance.",
-        );
         Self {
-            imported_paths: vec![],
+            version: SETTINGS_VERSION,
+            settings: Settings::default(),
+            libraries: vec![],
+            active_library: String::new(),
+        }
+        .with_libraries()
+    }
+
+    /// Makes sure a library exists and one of them is active.
+    fn with_libraries(mut self) -> Self {
+        if self.libraries.is_empty() {
+            self.libraries.push(LibrarySettings::new(
+                DEFAULT_LIBRARY.to_string(),
+                String::new(),
+            ));
+        }
+        if !self
+            .libraries
+            .iter()
+            .any(|library| library.name == self.active_library)
+        {
+            self.active_library = self.libraries[0].name.clone();
+        }
+        self
+    }
+
+    /// Parses a settings file, migrating files written by older versions.
+    ///
+    /// A settings section that cannot be read takes its defaults, and
+    /// values outside their allowed range are reset to their defaults, so
+    /// one bad value never discards the libraries or the other settings.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the settings and a message for everything that
+    /// was reset, or an error message if the file is malformed or from a
+    /// newer version.
+    fn parse(contents: &str) -> Result<(Self, Vec<String>), String> {
+        let value = serde_json::from_str::<Value>(contents)
+            .map_err(|e| format!("The settings file is not valid JSON: {}", e))?;
+        let mut value = migrate(value)?;
+        let Some(map) = value.as_object_mut() else {
+            return Err("The settings file is not a JSON object".to_string());
+        };
+
+        let mut resets = vec![];
+        let mut settings = Settings {
+            import: section(map, "import", &mut resets),
+            analysis: section(map, "analysis", &mut resets),
+            cache: section(map, "cache", &mut resets),
+            playback: section(map, "playback", &mut resets),
+            ui: section(map, "ui", &mut resets),
+            trash: section(map, "trash", &mut resets),
+            database: section(map, "database", &mut resets),
+        };
+        resets.extend(settings.reset_invalid());
+
+        let mut config = serde_json::from_value::<Self>(value)
+            .map_err(|e| format!("The settings file is invalid: {}", e))?;
+        config.settings = settings;
+        Ok((config.with_libraries(), resets))
+    }
+
+    /// Returns the settings of the active library.
+    fn active(&self) -> &LibrarySettings {
+        self.library(&self.active_library)
+            .unwrap_or(&self.libraries[0])
+    }
+
+    /// Returns the settings of a library.
+    fn library(&self, name: &str) -> Option<&LibrarySettings> {
+        self.libraries.iter().find(|library| library.name == name)
+    }
+
+    /// Returns the settings of a library for changing them.
+    fn library_mut(&mut self, name: &str) -> Option<&mut LibrarySettings> {
+        self.libraries
+            .iter_mut()
+            .find(|library| library.name == name)
+    }
+}
+
+/// Takes a section out of the settings file.
+///
+/// # Returns
+///
+/// The section, or its defaults if it is missing or cannot be read, in
+/// which case the problem is added to `resets`.
+fn section<T: DeserializeOwned + Default>(
+    map: &mut Map<String, Value>,
+    key: &str,
+    resets: &mut Vec<String>,
+) -> T {
+    match map.remove(key).map(serde_json::from_value) {
+        None => T::default(),
+        Some(Ok(section)) => section,
+        Some(Err(e)) => {
+            resets.push(format!("{} cannot be read: {}", key, e));
+            T::default()
+        }
+    }
+}
+
+/// Upgrades the contents of a settings file to the current layout.
+///
+/// Version 1 files kept the import paths of their only library and the
+/// preview target and trash retention at the top level.
+fn migrate(mut value: Value) -> Result<Value, String> {
+    let Some(map) = value.as_object_mut() else {
+        return Err("The settings file is not a JSON object".to_string());
+    };
+    let version = match map.get("version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("Invalid settings version: {}", version))?,
+    };
+    if version > u64::from(SETTINGS_VERSION) {
+        return Err(format!(
+            "The settings file was written by a newer version (format {})",
+            version
+        ));
+    }
+
+    if version < 2 {
+        let imported_paths = map.remove("imported_paths");
+        let import_volumes = map.remove("import_volumes");
+        if !map.contains_key("libraries") {
+            map.insert(
+                "libraries".to_string(),
+                json!([{
+                    "name": DEFAULT_LIBRARY,
+                    "dir": "",
+                    "imported_paths": imported_paths.unwrap_or_else(|| json!([])),
+                    "import_volumes": import_volumes.unwrap_or_else(|| json!({})),
+                }]),
+            );
         }
+        if let Some(target) = map.remove("preview_target") {
+            map.insert("playback".to_string(), json!({ "preview_target": target }));
+        }
+        if let Some(days) = map.remove("trash_retention_days") {
+            map.insert("trash".to_string(), json!({ "retention_days": days }));
+        }
+    }
+
+    map.insert("version".to_string(), json!(SETTINGS_VERSION));
+    Ok(value)
+}
+
+/// Writes a file by writing a temporary file next to it and renaming it
+/// over the original, so a crash never leaves a half-written file.
+fn write_atomically(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let temp = path.with_extension("json.tmp");
+    let written = fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write '{}': {}", path.display(), e));
     }
+    Ok(())
+}
+
+/// What `get_settings` reports to the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsState {
+    pub settings: Settings,
+    /// Why the settings file, or some of its values, could not be loaded at
+    /// startup, in which case their defaults are in use.
+    pub load_error: Option<String>,
+}
 
-    fn load() -> Self {
-        let config_dir = config_dir().expect("Failed to get config directory");
-        let config_path = config_dir.join("soundlab").join("config.json");
+/// Loads, validates and saves the settings file.
+pub struct SettingsManager {
+    path: PathBuf,
+    config: Mutex<Config>,
+    load_error: Option<String>,
+}
 
-        match fs::read_to_string(&config_path) {
-            Ok(content) => {
+impl SettingsManager {
+    /// Loads the settings file, migrating files written by older versions.
+    ///
+    /// Invalid values are replaced by their defaults. A file that cannot be
+    /// read or parsed at all is moved aside to `config.invalid.json` so it
+    /// is not overwritten, and the defaults are used. Either problem is kept
+    /// for `state` so the UI can show it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the settings file.
+    pub fn load(path: PathBuf) -> Self {
+        let loaded = match fs::read_to_string(&path) {
+            Ok(contents) => Config::parse(&contents).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("The settings file cannot be read: {}", e)),
+        };
+
+        let (config, load_error) = match loaded {
+            Ok(Some((config, resets))) if resets.is_empty() => {
                 log(
                     LogLevel::Info,
-                    "Config::load",
-                    &format!("Loading config from '{}'", config_path.display()),
+                    "SettingsManager::load",
+                    &format!("Loaded settings from '{}'", path.display()),
+                );
+                (config, None)
+            }
+            Ok(Some((config, resets))) => {
+                let error = format!(
+                    "Some settings were invalid and use their defaults: {}",
+                    resets.join("; ")
                 );
-                serde_json::from_str(&content).unwrap_or_else(|_| {
-                    log(
-                        LogLevel::Error,
-                        "Config::load",
-                        "Failed to parse config, using default settings.",
-                    );
-                    Self::new()
-                })
+                log(LogLevel::Warn, "SettingsManager::load", &error);
+                (config, Some(error))
             }
-            Err(_) => {
+            Ok(None) => {
                 log(
                     LogLevel::Warn,
-                    "Config::load",
-                    "Config file not found, using default settings.",
+                    "SettingsManager::load",
+                    "Settings file not found, using default settings.",
                 );
-                Self::new()
+                (Config::new(), None)
             }
+            Err(error) => {
+                let invalid = path.with_extension("invalid.json");
+                let error = match fs::rename(&path, &invalid) {
+                    Ok(()) => format!(
+                        "{}. The file was moved to '{}' and the default settings are in use.",
+                        error,
+                        invalid.display()
+                    ),
+                    Err(_) => format!("{}. The default settings are in use.", error),
+                };
+                log(LogLevel::Error, "SettingsManager::load", &error);
+                (Config::new(), Some(error))
+            }
+        };
+
+        Self {
+            path,
+            config: Mutex::new(config),
+            load_error,
+        }
+    }
+
+    /// Writes the settings file.
+    fn write(&self, config: &Config) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(config)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        write_atomically(&self.path, &contents)?;
+        log(
+            LogLevel::Info,
+            "SettingsManager::write",
+            &format!("Settings saved to '{}'", self.path.display()),
+        );
+        Ok(())
+    }
+
+    /// Writes the settings file, logging failures.
+    fn save(&self, config: &Config) {
+        if let Err(e) = self.write(config) {
+            log(LogLevel::Error, "SettingsManager::save", &e);
         }
     }
 
-    fn save(&self) {
-        let config_dir = config_dir().expect("Failed to get config directory");
-        let config_path = config_dir.join("soundlab").join("config.json");
+    pub fn get(&self) -> Settings {
+        self.config.lock().unwrap().settings.clone()
+    }
 
-        if let Some(parent) = config_path.parent() {
-            if !parent.exists() {
-                if let Err(e) = fs::create_dir_all(parent) {
-                    log(
-                        LogLevel::Error,
-                        "Config::save",
-                        &format!("Failed to create config directory: {}", e),
-                    );
-                    return;
-                }
+    pub fn state(&self) -> SettingsState {
+        SettingsState {
+            settings: self.get(),
+            load_error: self.load_error.clone(),
+        }
+    }
+
+    /// Changes the settings, keeping the old ones if the new ones are
+    /// invalid or cannot be saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - Applies the change to a copy of the current settings.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new settings, or an error message listing
+    /// the invalid values or why saving failed.
+    pub fn update(&self, change: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+        let mut config = self.config.lock().unwrap();
+        let mut settings = config.settings.clone();
+        change(&mut settings);
+        settings.validate()?;
+
+        if settings != config.settings {
+            let previous = std::mem::replace(&mut config.settings, settings);
+            if let Err(e) = self.write(&config) {
+                config.settings = previous;
+                return Err(e);
             }
         }
+        Ok(config.settings.clone())
+    }
 
-        match serde_json::to_string_pretty(self) {
-            Ok(content) => {
-                if let Err(e) = fs::write(&config_path, content) {
-                    log(
-                        LogLevel::Error,
-                        "Config::save",
-                        &format!("Failed to save config: {}", e),
-                    );
-                } else {
-                    log(
-                        LogLevel::Info,
-                        "Config::save",
-                        &format!("Config saved successfully to '{}'", config_path.display()),
-                    );
-                }
+    /// Adds an import path to a library.
+    ///
+    /// The import path methods take the name of the library they apply to,
+    /// so an importer of a library that is no longer active never changes
+    /// the settings of the active one.
+    pub fn add_import_path(&self, library: &str, path: String) {
+        let mut config = self.config.lock().unwrap();
+        let Some(library) = config.library_mut(library) else {
+            return;
+        };
+        if !library
+            .imported_paths
+            .iter()
+            .any(|entry| entry.path == path)
+        {
+            library.imported_paths.push(ImportPath::new(path));
+            self.save(&config);
+        }
+    }
+
+    pub fn remove_import_path(&self, library: &str, path: &str) -> Vec<String> {
+        let mut config = self.config.lock().unwrap();
+        let Some(settings) = config.library_mut(library) else {
+            return vec![];
+        };
+
+        if let Some(index) = settings
+            .imported_paths
+            .iter()
+            .position(|entry| entry.path == path)
+        {
+            settings.imported_paths.remove(index);
+            settings.import_volumes.remove(path);
+            self.save(&config);
+        }
+
+        config
+            .library(library)
+            .map(|library| {
+                library
+                    .imported_paths
+                    .iter()
+                    .map(|entry| entry.path.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn replace_import_path(&self, library: &str, old: &str, new: String) {
+        let mut config = self.config.lock().unwrap();
+        let Some(library) = config.library_mut(library) else {
+            return;
+        };
+        if let Some(index) = library
+            .imported_paths
+            .iter()
+            .position(|entry| entry.path == old)
+        {
+            if let Some(volume) = library.import_volumes.remove(old) {
+                library.import_volumes.insert(new.clone(), volume);
             }
-            Err(e) => log(
-                LogLevel::Error,
-                "Config::save",
-                &format!("Failed to serialize config: {}", e),
-            ),
+            if library.imported_paths.iter().any(|entry| entry.path == new) {
+                library.imported_paths.remove(index);
+            } else {
+                library.imported_paths[index].path = new;
+            }
+            self.save(&config);
         }
     }
-}
 
-pub fn add_import_path(path: String) {
-    let mut settings = SETTINGS.lock().unwrap();
-    if !settings.imported_paths.contains(&path) {
-        settings.imported_paths.push(path);
-        settings.save();
+    pub fn get_import_paths(&self, library: &str) -> Vec<String> {
+        self.import_path_options(library)
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect()
+    }
+
+    pub fn import_path_options(&self, library: &str) -> Vec<ImportPath> {
+        self.config
+            .lock()
+            .unwrap()
+            .library(library)
+            .map(|library| library.imported_paths.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn update_import_path_options(&self, library: &str, options: ImportPath) {
+        let mut config = self.config.lock().unwrap();
+        let Some(library) = config.library_mut(library) else {
+            return;
+        };
+        let existing = library
+            .imported_paths
+            .iter()
+            .position(|entry| entry.path == options.path);
+        match existing {
+            Some(index) if library.imported_paths[index] == options => return,
+            Some(index) => library.imported_paths[index] = options,
+            None => library.imported_paths.push(options),
+        }
+        self.save(&config);
+    }
+
+    pub fn import_volumes(&self, library: &str) -> HashMap<String, Volume> {
+        self.config
+            .lock()
+            .unwrap()
+            .library(library)
+            .map(|library| library.import_volumes.clone())
+            .unwrap_or_default()
     }
-}
 
-pub fn remove_import_path(path: &str) -> Vec<String> {
-    let mut settings = SETTINGS.lock().unwrap();
+    pub fn set_import_volume(&self, library: &str, path: &str, volume: Volume) {
+        let mut config = self.config.lock().unwrap();
+        let Some(library) = config.library_mut(library) else {
+            return;
+        };
+        if library.import_volumes.get(path) != Some(&volume) {
+            library.import_volumes.insert(path.to_string(), volume);
+            self.save(&config);
+        }
+    }
+
+    pub fn libraries(&self) -> Vec<LibrarySettings> {
+        self.config.lock().unwrap().libraries.clone()
+    }
 
-    if let Some(index) = settings.imported_paths.iter().position(|p| p == path) {
-        settings.imported_paths.remove(index);
-        settings.save();
+    pub fn active_library(&self) -> LibrarySettings {
+        self.config.lock().unwrap().active().clone()
     }
 
-    settings.imported_paths.clone()
+    /// Adds a library with its own directory below `libraries` in the data
+    /// directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the library.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new library, or an error message if the name
+    /// is empty or taken.
+    pub fn add_library(&self, name: &str) -> Result<LibrarySettings, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Library name must not be empty".to_string());
+        }
+
+        let mut config = self.config.lock().unwrap();
+        if config
+            .libraries
+            .iter()
+            .any(|library| library.name.eq_ignore_ascii_case(name))
+        {
+            return Err(format!("A library named '{}' already exists", name));
+        }
+
+        let slug: String = name
+            .chars()
+            .map(|c| match c {
+                c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase(),
+                _ => '-',
+            })
+            .collect();
+        let slug = match slug.trim_matches('-') {
+            "" => "library",
+            slug => slug,
+        };
+        let mut dir = format!("libraries/{}", slug);
+        let mut attempt = 1;
+        while config.libraries.iter().any(|library| library.dir == dir)
+            || library_dir(&dir).exists()
+        {
+            attempt += 1;
+            dir = format!("libraries/{}-{}", slug, attempt);
+        }
+
+        let library = LibrarySettings::new(name.to_string(), dir);
+        config.libraries.push(library.clone());
+        if let Err(e) = self.write(&config) {
+            config.libraries.pop();
+            return Err(e);
+        }
+        Ok(library)
+    }
+
+    pub fn set_active_library(&self, name: &str) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap();
+        if !config.libraries.iter().any(|library| library.name == name) {
+            return Err(format!("Library '{}' does not exist", name));
+        }
+        if config.active_library != name {
+            config.active_library = name.to_string();
+            self.save(&config);
+        }
+        Ok(())
+    }
 }
 
-pub fn get_import_paths() -> Vec<String> {
-    SETTINGS.lock().unwrap().imported_paths.clone()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_import_paths_keep_their_old_behaviour() {
+        let options: ImportPath = serde_json::from_value(json!("/samples")).unwrap();
+        assert_eq!(options.path, "/samples");
+        assert!(options.include.is_empty());
+        assert!(options.exclude.is_empty());
+        assert_eq!(options.max_depth, None);
+        assert!(options.follow_symlinks);
+        assert!(options.include_hidden);
+    }
+
+    #[test]
+    fn import_path_options_take_defaults_for_missing_rules() {
+        let options: ImportPath =
+            serde_json::from_value(json!({ "path": "/samples", "max_depth": 2 })).unwrap();
+        assert_eq!(options.max_depth, Some(2));
+        assert!(!options.follow_symlinks);
+        assert!(!options.include_hidden);
+    }
+
+    #[test]
+    fn migrate_moves_version_1_fields_into_the_default_library() {
+        let value = migrate(json!({
+            "imported_paths": ["/samples"],
+            "import_volumes": { "/samples": { "mount_point": "/", "uuid": null } },
+            "preview_target": { "bpm": 120.0, "key": "Am" },
+            "trash_retention_days": 7,
+        }))
+        .unwrap();
+
+        assert_eq!(value["version"], json!(SETTINGS_VERSION));
+        assert_eq!(value["libraries"][0]["name"], json!(DEFAULT_LIBRARY));
+        assert_eq!(value["libraries"][0]["imported_paths"], json!(["/samples"]));
+        assert_eq!(value["playback"]["preview_target"]["bpm"], json!(120.0));
+        assert_eq!(value["trash"]["retention_days"], json!(7));
+        assert!(value.get("imported_paths").is_none());
+    }
+
+    #[test]
+    fn migrate_rejects_newer_and_malformed_files() {
+        assert!(migrate(json!({ "version": SETTINGS_VERSION + 1 })).is_err());
+        assert!(migrate(json!({ "version": "two" })).is_err());
+        assert!(migrate(json!([])).is_err());
+    }
+
+    #[test]
+    fn parse_resets_only_invalid_values() {
+        let contents = json!({
+            "version": SETTINGS_VERSION,
+            "import": { "concurrency": 0, "volume_poll_secs": 9 },
+            "ui": { "page_size": "many" },
+            "playback": { "volume": 0.5 },
+            "libraries": [{ "name": "Mine", "dir": "mine" }],
+            "active_library": "Mine",
+        })
+        .to_string();
+        let (config, resets) = Config::parse(&contents).unwrap();
+
+        assert_eq!(resets.len(), 2);
+        let defaults = Settings::default();
+        assert_eq!(
+            config.settings.import.concurrency,
+            defaults.import.concurrency
+        );
+        assert_eq!(config.settings.import.volume_poll_secs, 9);
+        assert_eq!(config.settings.ui, defaults.ui);
+        assert_eq!(config.settings.playback.volume, 0.5);
+        assert_eq!(config.active().name, "Mine");
+    }
+
+    #[test]
+    fn parse_adds_a_library_when_none_is_stored() {
+        let (config, resets) = Config::parse(r#"{"version": 2}"#).unwrap();
+        assert!(resets.is_empty());
+        assert_eq!(config.active().name, DEFAULT_LIBRARY);
+    }
 }
@@ -0,0 +1,218 @@
+use crate::db::fingerprint::FingerprintRepository;
+use crate::db::sound::Sound;
+use crate::utils::logger::{log, LogLevel};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+/// A sound returned by a similarity search.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarSound {
+    /// The similar sound.
+    pub sound: Sound,
+    /// Standardised distance to the reference sound; smaller is more similar.
+    pub distance: f64,
+}
+
+/// In-memory nearest-neighbour index over sound timbre descriptors.
+///
+/// All descriptors are kept in memory and mirrored to the `fingerprints`
+/// table. Queries scan every descriptor after standardising each dimension
+/// by its spread across the library, which is fast enough for libraries of
+/// a few hundred thousand sounds and always exact. The spreads are computed
+/// once and kept until a descriptor changes.
+pub struct FingerprintIndex {
+    repo: FingerprintRepository,
+    vectors: RwLock<HashMap<i64, Vec<f32>>>,
+    /// Inverse spreads of the descriptors and the length they were computed for.
+    spread: Mutex<Option<(usize, Arc<Vec<f64>>)>>,
+}
+
+impl FingerprintIndex {
+    /// Loads all stored descriptors into a new index.
+    ///
+    /// # Arguments
+    ///
+    /// * `repo` - The repository the descriptors are read from and written to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the index, or an error message if loading fails.
+    pub async fn load(repo: FingerprintRepository) -> Result<Self, String> {
+        let vectors: HashMap<i64, Vec<f32>> = repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error loading fingerprints: {}", e))?
+            .into_iter()
+            .collect();
+
+        log(
+            LogLevel::Info,
+            "FingerprintIndex::load",
+            &format!("Loaded {} fingerprints.", vectors.len()),
+        );
+
+        Ok(Self {
+            repo,
+            vectors: RwLock::new(vectors),
+            spread: Mutex::new(None),
+        })
+    }
+
+    /// Stores the descriptor of a sound, replacing any previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_id` - The ID of the sound.
+    /// * `vector` - The timbre descriptor.
+    pub async fn insert(&self, sound_id: i64, vector: Vec<f32>) -> Result<(), String> {
+        self.repo
+            .upsert(sound_id, &vector)
+            .await
+            .map_err(|e| format!("Error storing fingerprint: {}", e))?;
+        self.vectors.write().await.insert(sound_id, vector);
+        self.spread.lock().unwrap().take();
+        Ok(())
+    }
+
+    /// Removes the descriptor of a sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_id` - The ID of the sound.
+    pub async fn remove(&self, sound_id: i64) -> Result<(), String> {
+        self.repo
+            .delete(sound_id)
+            .await
+            .map_err(|e| format!("Error deleting fingerprint: {}", e))?;
+        self.vectors.write().await.remove(&sound_id);
+        self.spread.lock().unwrap().take();
+        Ok(())
+    }
+
+    /// Replaces every descriptor with the stored ones, for example after the
+    /// database was restored from a backup.
+    pub async fn reload(&self) -> Result<(), String> {
+        let vectors = self
+            .repo
+            .get_all()
+            .await
+            .map_err(|e| format!("Error loading fingerprints: {}", e))?;
+        *self.vectors.write().await = vectors.into_iter().collect();
+        self.spread.lock().unwrap().take();
+        Ok(())
+    }
+
+    /// Loads the stored descriptors of restored sounds back into the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_ids` - The IDs of the restored sounds.
+    pub async fn restore(&self, sound_ids: &[i64]) -> Result<(), String> {
+        let rows = self
+            .repo
+            .get_many(sound_ids)
+            .await
+            .map_err(|e| format!("Error loading fingerprints: {}", e))?;
+        self.vectors.write().await.extend(rows);
+        self.spread.lock().unwrap().take();
+        Ok(())
+    }
+
+    /// Drops the descriptors of deleted sounds from the index, keeping the
+    /// stored copies.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_ids` - The IDs of the deleted sounds.
+    pub async fn forget(&self, sound_ids: &[i64]) {
+        let mut vectors = self.vectors.write().await;
+        for sound_id in sound_ids {
+            vectors.remove(sound_id);
+        }
+        self.spread.lock().unwrap().take();
+    }
+
+    /// Finds the sounds whose timbre is closest to a given sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `sound_id` - The ID of the reference sound.
+    /// * `limit` - The maximum number of neighbours to return.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing `(sound_id, distance)` pairs ordered from most
+    /// to least similar, or an error message if the reference sound has no
+    /// descriptor.
+    pub async fn nearest(&self, sound_id: i64, limit: usize) -> Result<Vec<(i64, f64)>, String> {
+        let vectors = self.vectors.read().await;
+        let target = vectors
+            .get(&sound_id)
+            .ok_or_else(|| format!("Sound {} has no fingerprint", sound_id))?;
+
+        let scale = {
+            let mut spread = self.spread.lock().unwrap();
+            match spread.as_ref() {
+                Some((len, scale)) if *len == target.len() => Arc::clone(scale),
+                _ => {
+                    let scale = Arc::new(inverse_spread(&vectors, target.len()));
+                    *spread = Some((target.len(), Arc::clone(&scale)));
+                    scale
+                }
+            }
+        };
+        let mut distances: Vec<(i64, f64)> = vectors
+            .iter()
+            .filter(|(&id, vector)| id != sound_id && vector.len() == target.len())
+            .map(|(&id, vector)| {
+                let distance = vector
+                    .iter()
+                    .zip(target)
+                    .zip(scale.iter())
+                    .map(|((a, b), s)| ((a - b) as f64 * s).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                (id, distance)
+            })
+            .collect();
+
+        if limit < distances.len() {
+            distances.select_nth_unstable_by(limit, |a, b| a.1.total_cmp(&b.1));
+            distances.truncate(limit);
+        }
+        distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        Ok(distances)
+    }
+}
+
+/// Returns the reciprocal standard deviation of every descriptor dimension.
+///
+/// Dimensions without any spread get a weight of zero.
+fn inverse_spread(vectors: &HashMap<i64, Vec<f32>>, len: usize) -> Vec<f64> {
+    let mut sum = vec![0.0f64; len];
+    let mut sum_sq = vec![0.0f64; len];
+    let mut count = 0.0;
+
+    for vector in vectors.values().filter(|v| v.len() == len) {
+        for (index, &value) in vector.iter().enumerate() {
+            sum[index] += value as f64;
+            sum_sq[index] += (value as f64).powi(2);
+        }
+        count += 1.0;
+    }
+
+    sum.iter()
+        .zip(&sum_sq)
+        .map(|(s, sq)| {
+            let variance = sq / count - (s / count).powi(2);
+            if variance > 1e-12 {
+                1.0 / variance.sqrt()
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
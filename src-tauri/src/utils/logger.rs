@@ -1,4 +1,4 @@
-use dirs::config_dir;
+use crate::utils::paths::log_dir;
 use once_cell::sync::Lazy;
 use std::fs::{create_dir_all, OpenOptions};
 use std::io::Write;
@@ -20,8 +20,7 @@ pub struct Logger {
 
 impl Logger {
     fn new() -> Self {
-        let app_data_path = config_dir().expect("Failed to get config directory");
-        let log_dir = app_data_path.join("soundlab").join("logs");
+        let log_dir = log_dir();
 
         if let Err(e) = create_dir_all(&log_dir) {
             eprintln!("Logger Error: Failed to create log directory: {}", e);
@@ -65,11 +64,8 @@ impl Logger {
 
     pub fn clear_file() {
         if let Ok(_logger) = LOGGER.lock() {
-            if let Some(log_path) =
-                config_dir().map(|p| p.join("soundlab").join("logs").join("soundlab.log"))
-            {
-                let _ = OpenOptions::new().write(true).truncate(true).open(log_path);
-            }
+            let log_path = log_dir().join("soundlab.log");
+            let _ = OpenOptions::new().write(true).truncate(true).open(log_path);
         }
     }
 }
@@ -1 +1,3 @@
 pub mod logger;
+pub mod paths;
+pub mod trash;
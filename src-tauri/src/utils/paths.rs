@@ -0,0 +1,128 @@
+use dirs::config_dir;
+use once_cell::sync::Lazy;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that sets the data directory.
+const DATA_DIR_ENV: &str = "SOUNDLAB_DATA_DIR";
+/// Command line flag that sets the data directory, given as
+/// `--data-dir <path>` or `--data-dir=<path>`.
+const DATA_DIR_FLAG: &str = "--data-dir";
+/// Command line flag that enables portable mode.
+const PORTABLE_FLAG: &str = "--portable";
+/// File next to the executable that enables portable mode when present.
+const PORTABLE_MARKER: &str = "soundlab.portable";
+/// Directory next to the executable that holds the data in portable mode.
+const PORTABLE_DATA_DIR: &str = "data";
+
+static DATA_DIR: Lazy<DataDir> = Lazy::new(DataDir::resolve);
+
+/// Where the data directory setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataDirSource {
+    /// The `--data-dir` command line flag.
+    CommandLine,
+    /// The `SOUNDLAB_DATA_DIR` environment variable.
+    Environment,
+    /// Portable mode, enabled by `--portable` or the marker file.
+    Portable,
+    /// The `soundlab` directory in the user's configuration directory.
+    Default,
+}
+
+/// The directory holding the database, settings, logs, cache and backups.
+#[derive(Debug, Clone)]
+pub struct DataDir {
+    /// The absolute path of the directory.
+    pub path: PathBuf,
+    /// How the directory was chosen.
+    pub source: DataDirSource,
+}
+
+impl DataDir {
+    /// Picks the data directory from, in order of precedence, the command
+    /// line flag, the environment variable, portable mode and the default.
+    ///
+    /// This runs before the logger exists, so problems are printed instead
+    /// of logged.
+    fn resolve() -> Self {
+        let args: Vec<String> = env::args().skip(1).collect();
+
+        let flag = args.iter().enumerate().find_map(|(index, arg)| {
+            match arg.strip_prefix(DATA_DIR_FLAG)? {
+                "" => args.get(index + 1).cloned(),
+                value => value.strip_prefix('=').map(str::to_string),
+            }
+        });
+        if let Some(path) = flag.filter(|path| !path.is_empty()) {
+            return Self::new(PathBuf::from(path), DataDirSource::CommandLine);
+        }
+
+        if let Some(path) = env::var_os(DATA_DIR_ENV).filter(|path| !path.is_empty()) {
+            return Self::new(PathBuf::from(path), DataDirSource::Environment);
+        }
+
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        if let Some(exe_dir) = exe_dir {
+            if args.iter().any(|arg| arg == PORTABLE_FLAG) || exe_dir.join(PORTABLE_MARKER).exists()
+            {
+                return Self::new(exe_dir.join(PORTABLE_DATA_DIR), DataDirSource::Portable);
+            }
+        } else if args.iter().any(|arg| arg == PORTABLE_FLAG) {
+            eprintln!("Paths Error: Cannot locate the executable, portable mode is unavailable");
+        }
+
+        let config_dir = config_dir().expect("Failed to get config directory");
+        Self::new(config_dir.join("soundlab"), DataDirSource::Default)
+    }
+
+    fn new(path: PathBuf, source: DataDirSource) -> Self {
+        let path = std::path::absolute(&path).unwrap_or(path);
+        Self { path, source }
+    }
+}
+
+/// Returns the data directory, resolving it on first use.
+pub fn data_dir() -> &'static DataDir {
+    &DATA_DIR
+}
+
+/// Returns the directory of a library.
+///
+/// # Arguments
+///
+/// * `dir` - The directory relative to the data directory, or an empty
+///   string for the data directory itself.
+pub fn library_dir(dir: &str) -> PathBuf {
+    match dir {
+        "" => DATA_DIR.path.clone(),
+        dir => DATA_DIR.path.join(dir),
+    }
+}
+
+/// Returns the path of a library's database.
+pub fn database_path(library_dir: &Path) -> PathBuf {
+    library_dir.join("database.db")
+}
+
+/// Returns the directory a library's database backups are written to.
+pub fn backup_dir(library_dir: &Path) -> PathBuf {
+    library_dir.join("backups")
+}
+
+/// Returns the directory of a library's analysis cache.
+pub fn cache_dir(library_dir: &Path) -> PathBuf {
+    library_dir.join("cache")
+}
+
+/// Returns the path of the settings file.
+pub fn config_path() -> PathBuf {
+    DATA_DIR.path.join("config.json")
+}
+
+/// Returns the directory log files are written to.
+pub fn log_dir() -> PathBuf {
+    DATA_DIR.path.join("logs")
+}
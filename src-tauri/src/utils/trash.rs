@@ -0,0 +1,287 @@
+use std::path::{Path, PathBuf};
+
+/// Moves a file to the trash as described by the freedesktop.org Trash
+/// specification.
+///
+/// Files on the same filesystem as the home trash go there; others go to
+/// the trash at the top of their own volume, so trashing never copies data.
+/// A `.trashinfo` entry is written as well, which lets file managers
+/// restore the file. This blocks, so it must only be called from blocking threads.
+///
+/// # Arguments
+///
+/// * `path` - The absolute path of the file.
+///
+/// # Returns
+///
+/// A `Result` containing the path of the file inside the trash, or an error
+/// message if no trash is usable for the file.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn trash_file(path: &Path) -> Result<PathBuf, String> {
+    xdg::trash_file(path)
+}
+
+/// Moves a file from the trash back to where it was deleted from.
+///
+/// This blocks, so it must only be called from blocking threads.
+///
+/// # Arguments
+///
+/// * `trashed` - The path of the file inside the trash.
+/// * `original` - Where the file lived before it was trashed.
+///
+/// # Returns
+///
+/// A `Result` indicating success, or an error message if the file is gone
+/// from the trash or its original location is taken.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn restore_file(trashed: &Path, original: &Path) -> Result<(), String> {
+    xdg::restore_file(trashed, original)
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn trash_file(_path: &Path) -> Result<PathBuf, String> {
+    Err("Moving files to the trash is not supported on this platform".to_string())
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn restore_file(_trashed: &Path, _original: &Path) -> Result<(), String> {
+    Err("Restoring files from the trash is not supported on this platform".to_string())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod xdg {
+    use crate::import::volume::volume_of;
+    use std::fs::{self, DirBuilder, OpenOptions};
+    use std::io::{ErrorKind, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::path::{Path, PathBuf};
+
+    /// The sticky bit required on a shared `$topdir/.Trash` directory.
+    const STICKY: u32 = 0o1000;
+
+    /// A trash directory and how paths are recorded in its info files.
+    struct Trash {
+        dir: PathBuf,
+        /// The volume root paths are recorded relative to, or `None` for
+        /// the home trash, which records absolute paths.
+        top_dir: Option<PathBuf>,
+    }
+
+    pub fn trash_file(path: &Path) -> Result<PathBuf, String> {
+        let metadata = fs::symlink_metadata(path)
+            .map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+        let trash = trash_for(path, metadata.dev())?;
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| format!("'{}' has no file name", path.display()))?;
+
+        let recorded = match &trash.top_dir {
+            Some(top_dir) => path.strip_prefix(top_dir).unwrap_or(path),
+            None => path,
+        };
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(recorded),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        for attempt in 1.. {
+            let name = unique_name(file_name.as_bytes(), attempt);
+            let info_path = trash.dir.join("info").join(format!("{}.trashinfo", name));
+            let target = trash.dir.join("files").join(&name);
+
+            let mut file = match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Cannot write trash info: {}", e)),
+            };
+            if fs::symlink_metadata(&target).is_ok() {
+                let _ = fs::remove_file(&info_path);
+                continue;
+            }
+            let moved = file
+                .write_all(info.as_bytes())
+                .map_err(|e| format!("Cannot write trash info: {}", e))
+                .and_then(|_| {
+                    fs::rename(path, &target).map_err(|e| {
+                        format!("Cannot move '{}' to the trash: {}", path.display(), e)
+                    })
+                });
+            return match moved {
+                Ok(()) => Ok(target),
+                Err(e) => {
+                    let _ = fs::remove_file(&info_path);
+                    Err(e)
+                }
+            };
+        }
+        unreachable!()
+    }
+
+    pub fn restore_file(trashed: &Path, original: &Path) -> Result<(), String> {
+        if fs::symlink_metadata(original).is_ok() {
+            return Err(format!("'{}' already exists", original.display()));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Cannot create '{}': {}", parent.display(), e))?;
+        }
+        fs::rename(trashed, original).map_err(|e| {
+            format!(
+                "Cannot restore '{}' from the trash: {}",
+                original.display(),
+                e
+            )
+        })?;
+
+        if let (Some(files), Some(name)) = (trashed.parent(), trashed.file_name()) {
+            if let Some(trash) = files.parent() {
+                let mut info = name.to_os_string();
+                info.push(".trashinfo");
+                let _ = fs::remove_file(trash.join("info").join(info));
+            }
+        }
+        Ok(())
+    }
+
+    /// Picks the trash for a file on the device `dev`.
+    fn trash_for(path: &Path, dev: u64) -> Result<Trash, String> {
+        let home = dirs::data_dir()
+            .ok_or("Cannot determine the data directory")?
+            .join("Trash");
+        if nearest_existing(&home).is_some_and(|ancestor| ancestor.dev() == dev) {
+            prepare(&home)?;
+            return Ok(Trash {
+                dir: home,
+                top_dir: None,
+            });
+        }
+
+        let top_dir = PathBuf::from(
+            volume_of(&path.to_string_lossy())
+                .ok_or_else(|| format!("No volume contains '{}'", path.display()))?
+                .mount_point,
+        );
+        let uid = unsafe { libc::getuid() };
+
+        let shared = top_dir.join(".Trash");
+        if fs::symlink_metadata(&shared)
+            .is_ok_and(|metadata| metadata.is_dir() && metadata.mode() & STICKY != 0)
+        {
+            let dir = shared.join(uid.to_string());
+            if prepare(&dir).is_ok() {
+                return Ok(Trash {
+                    dir,
+                    top_dir: Some(top_dir),
+                });
+            }
+        }
+
+        let dir = top_dir.join(format!(".Trash-{}", uid));
+        prepare(&dir)?;
+        Ok(Trash {
+            dir,
+            top_dir: Some(top_dir),
+        })
+    }
+
+    /// Creates the `files` and `info` directories of a trash, readable only
+    /// by the user.
+    fn prepare(dir: &Path) -> Result<(), String> {
+        for sub in ["files", "info"] {
+            DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir.join(sub))
+                .map_err(|e| format!("Cannot create trash '{}': {}", dir.display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the metadata of the closest existing ancestor of a path.
+    fn nearest_existing(path: &Path) -> Option<fs::Metadata> {
+        path.ancestors().find_map(|dir| fs::metadata(dir).ok())
+    }
+
+    /// Returns the name to store a file under for the given attempt, adding
+    /// a counter before the extension from the second attempt on.
+    fn unique_name(file_name: &[u8], attempt: usize) -> String {
+        let file_name = String::from_utf8_lossy(file_name);
+        if attempt == 1 {
+            return file_name.into_owned();
+        }
+        match file_name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => format!("{}.{}.{}", stem, attempt, ext),
+            _ => format!("{}.{}", file_name, attempt),
+        }
+    }
+
+    /// Percent-encodes a path for the `Path` key of an info file.
+    fn encode_path(path: &Path) -> String {
+        let mut encoded = String::new();
+        for &byte in path.as_os_str().as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        encoded
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn numbers_repeated_names_before_the_extension() {
+            assert_eq!(unique_name(b"kick.wav", 1), "kick.wav");
+            assert_eq!(unique_name(b"kick.wav", 2), "kick.2.wav");
+            assert_eq!(unique_name(b"kick.808.wav", 3), "kick.808.3.wav");
+            assert_eq!(unique_name(b"kick", 2), "kick.2");
+            assert_eq!(unique_name(b".hidden", 2), ".hidden.2");
+        }
+
+        #[test]
+        fn encodes_reserved_bytes_in_paths() {
+            assert_eq!(
+                encode_path(Path::new("/samples/Drum Kit/kick.wav")),
+                "/samples/Drum%20Kit/kick.wav"
+            );
+            assert_eq!(encode_path(Path::new("100%/é.wav")), "100%25/%C3%A9.wav");
+        }
+
+        #[test]
+        fn trashed_files_are_restored_to_their_place() {
+            let dir = tempfile::tempdir().unwrap();
+            std::env::set_var("XDG_DATA_HOME", dir.path().join("data"));
+            let original = dir.path().join("samples/kick.wav");
+            fs::create_dir_all(original.parent().unwrap()).unwrap();
+            fs::write(&original, b"kick").unwrap();
+
+            let trashed = trash_file(&original).unwrap();
+            assert!(!original.exists());
+            assert_eq!(trashed, dir.path().join("data/Trash/files/kick.wav"));
+            let info =
+                fs::read_to_string(dir.path().join("data/Trash/info/kick.wav.trashinfo")).unwrap();
+            assert!(info.contains(&format!("Path={}", encode_path(&original))));
+
+            fs::write(&original, b"snare").unwrap();
+            assert!(restore_file(&trashed, &original).is_err());
+            fs::remove_file(&original).unwrap();
+
+            restore_file(&trashed, &original).unwrap();
+            assert_eq!(fs::read(&original).unwrap(), b"kick");
+            assert!(!dir
+                .path()
+                .join("data/Trash/info/kick.wav.trashinfo")
+                .exists());
+        }
+    }
+}
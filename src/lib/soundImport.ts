@@ -20,6 +20,31 @@ export async function recacheSounds(): Promise<void> {
 	}
 }
 
+export interface RescanSummary {
+	added: number;
+	updated: number;
+	relocated: number;
+	missing: number;
+}
+
+export async function rescan(path?: string): Promise<RescanSummary> {
+	try {
+		return (await invoke('rescan', { path: path ?? null })) as RescanSummary;
+	} catch (error) {
+		console.error('Error rescanning import paths:', error);
+		throw error;
+	}
+}
+
+export async function relinkSounds(oldRoot: string, newRoot: string): Promise<Sound[]> {
+	try {
+		return (await invoke('relink_sounds', { oldRoot, newRoot })) as Sound[];
+	} catch (error) {
+		console.error('Error relinking sounds:', error);
+		throw error;
+	}
+}
+
 export async function importDirectory(): Promise<void> {
 	try {
 		const selected = await open({ directory: true });
@@ -31,31 +56,381 @@ export async function importDirectory(): Promise<void> {
 	}
 }
 
-export const getSounds = async (): Promise<Sound[]> => {
+export type SortField =
+	| 'name'
+	| 'lufs'
+	| 'true_peak'
+	| 'rms'
+	| 'crest_factor'
+	| 'duration'
+	| 'bpm'
+	| 'date_added'
+	| 'rating'
+	| 'play_count';
+
+export interface SoundCursor {
+	value: number | string | null;
+	id: number;
+}
+
+export interface SoundPage {
+	sounds: Sound[];
+	total: number;
+	next_cursor: SoundCursor | null;
+}
+
+export interface SoundListOptions {
+	query?: Record<string, unknown> & { sort_by?: SortField; descending?: boolean };
+	cursor?: SoundCursor | null;
+	limit?: number;
+}
+
+export async function getSoundPage(options: SoundListOptions = {}): Promise<SoundPage> {
 	try {
-		const sounds = (await invoke('get_sounds')) as {
-			id: string;
-			name: string;
-			path: string;
-			is_favorite: boolean;
-			tags: string[];
-		}[];
-		return sounds.map((sound) => ({
-			id: parseInt(sound.id, 10),
-			name: sound.name,
-			path: sound.path,
-			is_favorite: sound.is_favorite,
-			tags: sound.tags,
-		}));
+		const page = (await invoke('get_sounds', {
+			query: options.query ?? null,
+			cursor: options.cursor ?? null,
+			limit: options.limit ?? null,
+		})) as SoundPage;
+		return {
+			...page,
+			sounds: page.sounds.map((sound) => ({
+				...sound,
+				id: parseInt(String(sound.id), 10),
+			})),
+		};
 	} catch (error) {
 		console.error('Error fetching sounds:', error);
 		throw error;
 	}
+}
+
+export const getSounds = async (query?: SoundListOptions['query']): Promise<Sound[]> => {
+	const sounds: Sound[] = [];
+	let cursor: SoundCursor | null = null;
+	do {
+		const page: SoundPage = await getSoundPage({ query, cursor, limit: 1000 });
+		sounds.push(...page.sounds);
+		cursor = page.next_cursor;
+	} while (cursor);
+	return sounds;
 };
 
-export async function deleteSound(id: string): Promise<void> {
+export type SoundEdit =
+	| { action: 'favorite' }
+	| { action: 'unfavorite' }
+	| { action: 'tag'; tag: string }
+	| { action: 'untag'; tag: string }
+	| { action: 'delete'; trash_files?: boolean; delete_untrashable_files?: boolean }
+	| { action: 'move_to_collection'; collection_id: number | null };
+
+export interface BatchSummary {
+	requested: number;
+	changed: number;
+	unchanged: number;
+	not_found: number[];
+	failed: { id: number; error: string }[];
+}
+
+export async function editSounds(ids: number[], edit: SoundEdit): Promise<BatchSummary> {
+	try {
+		return (await invoke('edit_sounds', { ids, edit })) as BatchSummary;
+	} catch (error) {
+		console.error('Error editing sounds:', error);
+		throw error;
+	}
+}
+
+export async function reanalyzeSounds(ids: number[]): Promise<BatchSummary> {
+	try {
+		return (await invoke('reanalyze_sounds', { ids })) as BatchSummary;
+	} catch (error) {
+		console.error('Error re-analysing sounds:', error);
+		throw error;
+	}
+}
+
+export interface Collection {
+	id: number;
+	name: string;
+	sound_count: number;
+}
+
+export async function getCollections(): Promise<Collection[]> {
+	try {
+		return (await invoke('get_collections')) as Collection[];
+	} catch (error) {
+		console.error('Error fetching collections:', error);
+		throw error;
+	}
+}
+
+export async function createCollection(name: string): Promise<number> {
+	try {
+		return (await invoke('create_collection', { name })) as number;
+	} catch (error) {
+		console.error('Error creating collection:', error);
+		throw error;
+	}
+}
+
+export async function deleteCollection(id: number): Promise<void> {
+	try {
+		await invoke('delete_collection', { id });
+	} catch (error) {
+		console.error('Error deleting collection:', error);
+		throw error;
+	}
+}
+
+export async function restoreSounds(ids: number[]): Promise<BatchSummary> {
+	try {
+		return (await invoke('restore_sounds', { ids })) as BatchSummary;
+	} catch (error) {
+		console.error('Error restoring sounds:', error);
+		throw error;
+	}
+}
+
+export async function purgeDeletedSounds(all: boolean): Promise<number> {
+	try {
+		return (await invoke('purge_deleted_sounds', { all })) as number;
+	} catch (error) {
+		console.error('Error purging deleted sounds:', error);
+		throw error;
+	}
+}
+
+export async function getTrashRetentionDays(): Promise<number> {
+	try {
+		return (await invoke('get_trash_retention_days')) as number;
+	} catch (error) {
+		console.error('Error fetching trash retention:', error);
+		throw error;
+	}
+}
+
+export async function setTrashRetentionDays(days: number): Promise<number> {
+	try {
+		return (await invoke('set_trash_retention_days', { days })) as number;
+	} catch (error) {
+		console.error('Error updating trash retention:', error);
+		throw error;
+	}
+}
+
+export interface Settings {
+	import: { concurrency: number; volume_poll_secs: number };
+	analysis: { min_tempo_confidence: number; drum_tag_confidence: number };
+	cache: { memory_mb: number; disk_mb: number };
+	playback: {
+		preview_target: { bpm: number | null; key: string | null };
+		volume: number;
+		looping: boolean;
+	};
+	ui: { theme: 'system' | 'light' | 'dark'; page_size: number };
+	trash: { retention_days: number };
+	database: {
+		write_connections: number;
+		read_connections: number;
+		busy_timeout_ms: number;
+		statement_cache_capacity: number;
+	};
+}
+
+export interface SettingsState {
+	settings: Settings;
+	load_error: string | null;
+}
+
+export async function getSettings(): Promise<SettingsState> {
+	try {
+		return (await invoke('get_settings')) as SettingsState;
+	} catch (error) {
+		console.error('Error fetching settings:', error);
+		throw error;
+	}
+}
+
+export async function updateSettings(settings: Settings): Promise<Settings> {
+	try {
+		return (await invoke('update_settings', { settings })) as Settings;
+	} catch (error) {
+		console.error('Error updating settings:', error);
+		throw error;
+	}
+}
+
+export type LibraryFormat = 'json' | 'csv';
+
+export interface LibraryImportSummary {
+	entries: number;
+	matched_by_path: number;
+	matched_by_hash: number;
+	imported: number;
+	updated: number;
+	created_collections: number;
+	unmatched: string[];
+}
+
+export async function exportLibrary(format: LibraryFormat, path: string): Promise<number> {
+	try {
+		return (await invoke('export_library', { format, path })) as number;
+	} catch (error) {
+		console.error('Error exporting library:', error);
+		throw error;
+	}
+}
+
+export async function importLibrary(file: string): Promise<LibraryImportSummary> {
+	try {
+		return (await invoke('import_library', { file })) as LibraryImportSummary;
+	} catch (error) {
+		console.error(`Error importing library '${file}':`, error);
+		throw error;
+	}
+}
+
+export interface LibraryInfo {
+	name: string;
+	path: string;
+	active: boolean;
+	import_paths: string[];
+}
+
+export async function listLibraries(): Promise<LibraryInfo[]> {
+	try {
+		return (await invoke('list_libraries')) as LibraryInfo[];
+	} catch (error) {
+		console.error('Error listing libraries:', error);
+		throw error;
+	}
+}
+
+export async function createLibrary(name: string): Promise<LibraryInfo> {
+	try {
+		return (await invoke('create_library', { name })) as LibraryInfo;
+	} catch (error) {
+		console.error(`Error creating library '${name}':`, error);
+		throw error;
+	}
+}
+
+export async function switchLibrary(name: string): Promise<LibraryInfo> {
+	try {
+		return (await invoke('switch_library', { name })) as LibraryInfo;
+	} catch (error) {
+		console.error(`Error switching to library '${name}':`, error);
+		throw error;
+	}
+}
+
+export interface BackupInfo {
+	name: string;
+	path: string;
+	reason: string;
+	created_at: number;
+	size: number;
+}
+
+export interface IntegrityReport {
+	ok: boolean;
+	messages: string[];
+}
+
+export async function createBackup(): Promise<BackupInfo> {
+	try {
+		return (await invoke('create_backup')) as BackupInfo;
+	} catch (error) {
+		console.error('Error creating backup:', error);
+		throw error;
+	}
+}
+
+export async function listBackups(): Promise<BackupInfo[]> {
+	try {
+		return (await invoke('list_backups')) as BackupInfo[];
+	} catch (error) {
+		console.error('Error listing backups:', error);
+		throw error;
+	}
+}
+
+export async function restoreBackup(name: string): Promise<void> {
+	try {
+		await invoke('restore_backup', { name });
+	} catch (error) {
+		console.error('Error restoring backup:', error);
+		throw error;
+	}
+}
+
+export async function checkIntegrity(): Promise<IntegrityReport> {
+	try {
+		return (await invoke('check_integrity')) as IntegrityReport;
+	} catch (error) {
+		console.error('Error checking database integrity:', error);
+		throw error;
+	}
+}
+
+export interface JournalEntry {
+	id: number;
+	label: string;
+	created_at: number;
+	undone: boolean;
+}
+
+export async function undo(): Promise<JournalEntry | null> {
+	try {
+		return (await invoke('undo')) as JournalEntry | null;
+	} catch (error) {
+		console.error('Error undoing edit:', error);
+		throw error;
+	}
+}
+
+export async function redo(): Promise<JournalEntry | null> {
+	try {
+		return (await invoke('redo')) as JournalEntry | null;
+	} catch (error) {
+		console.error('Error redoing edit:', error);
+		throw error;
+	}
+}
+
+export async function getHistory(): Promise<JournalEntry[]> {
+	try {
+		return (await invoke('get_history')) as JournalEntry[];
+	} catch (error) {
+		console.error('Error fetching edit history:', error);
+		throw error;
+	}
+}
+
+export async function setRating(id: number, rating: number | null): Promise<void> {
+	try {
+		await invoke('set_rating', { id, rating });
+	} catch (error) {
+		console.error('Error setting sound rating:', error);
+		throw error;
+	}
+}
+
+export interface DeleteOptions {
+	/** Also move the file to the desktop trash. */
+	trashFile?: boolean;
+	/** Delete the file outright if it cannot be moved to the trash. */
+	deleteUntrashableFile?: boolean;
+}
+
+export async function deleteSound(id: string, options: DeleteOptions = {}): Promise<void> {
 	try {
-		await invoke('delete_sound', { id });
+		await invoke('delete_sound', {
+			id,
+			trashFile: options.trashFile ?? false,
+			deleteUntrashableFile: options.deleteUntrashableFile ?? false,
+		});
 	} catch (error) {
 		console.error('Error deleting sound:', error);
 		throw error;
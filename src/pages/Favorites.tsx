@@ -15,8 +15,7 @@ export const Favorites = () => {
 		const fetchFavorites = async () => {
 			try {
 				setIsLoading(true);
-				const allSounds: Sound[] = await getSounds();
-				const favs = allSounds.filter((sound) => sound.is_favorite);
+				const favs: Sound[] = await getSounds({ favorites_only: true });
 				setFavorites(favs);
 			} catch (err) {
 				console.error('Error fetching favorites:', err);
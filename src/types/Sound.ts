@@ -4,4 +4,37 @@ export interface Sound {
 	path: string;
 	is_favorite: boolean;
 	tags: string[];
+	lufs?: number | null;
+	true_peak?: number | null;
+	rms?: number | null;
+	crest_factor?: number | null;
+	trim_start?: number | null;
+	trim_end?: number | null;
+	duration?: number | null;
+	bpm?: number | null;
+	kind?: 'one_shot' | 'loop' | null;
+	drum_class?:
+		| 'kick'
+		| 'snare'
+		| 'clap'
+		| 'closed_hat'
+		| 'open_hat'
+		| 'tom'
+		| 'perc'
+		| '808'
+		| 'cymbal'
+		| null;
+	drum_confidence?: number | null;
+	auto_tag?: string | null;
+	play_count?: number;
+	last_played_at?: number | null;
+	file_size?: number | null;
+	modified_at?: number | null;
+	content_hash?: string | null;
+	status?: 'ok' | 'missing' | 'unreadable' | 'empty' | 'corrupt' | 'offline';
+	added_at?: number | null;
+	rating?: number | null;
+	collection_id?: number | null;
+	deleted_at?: number | null;
+	trashed_path?: string | null;
 }